@@ -1,155 +1,130 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::mover::{EdgePolicy, Mover};
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 #[derive(Debug, Default)]
 struct Wind {
     accumulator: f64,
 }
 
-#[derive(Debug, Default)]
-struct Mover {
-    location: DVec2,
-    velocity: DVec2,
-    acceleration: DVec2,
-    mass: f64,
-
-    angle: f64,
-    angular_velocity: f64,
-    angular_acceleration: f64,
+fn mover_size(mover: &Mover) -> f64 {
+    mover.mass * 16.0
 }
 
-impl Mover {
-    fn new(mass: f64, x: f64, y: f64) -> Self {
-        Self {
-            location: DVec2::new(x, y),
-            mass,
-            ..Default::default()
-        }
-    }
+fn display_mover(mover: &Mover, canvas: &mut impl Canvas, alpha: f64) -> Result<(), ProcessingErr> {
+    core::stroke_grayscale(canvas, 0.0);
+    core::fill_grayscale_alpha(canvas, 175.0, 200.0);
 
-    fn size(&self) -> f64 {
-        self.mass * 16.0
-    }
+    core::push_matrix(canvas);
 
-    fn bounce_edges(&mut self, screen: &Screen) {
-        let hs = self.size() / 2.0;
+    let location = mover.interpolated_location(alpha);
+    core::translate(canvas, location.x, location.y);
+    core::rotate(canvas, mover.interpolated_angle(alpha));
 
-        if (self.location.x + hs) > screen.width() as f64 {
-            self.location.x = screen.width() as f64 - hs;
-            self.velocity.x *= -1.0;
-        } else if self.location.x < hs {
-            self.location.x = hs;
-            self.velocity.x *= -1.0;
-        }
+    let state = canvas.draw_state();
+    core::shapes::rect_mode(canvas, core::shapes::RectMode::Center);
+    core::shapes::rect(canvas, 0.0, 0.0, mover_size(mover), mover_size(mover))?;
+    canvas.set_draw_state(state);
+
+    core::pop_matrix(canvas);
 
-        if (self.location.y + hs) > screen.height() as f64 {
-            self.location.y = screen.height() as f64 - hs;
-            self.velocity.y *= -1.0;
-        } else if self.location.y < hs {
-            self.location.y = hs;
-            self.velocity.y *= -1.0;
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Tumble {
+    movers: Vec<Mover>,
+    wind: Wind,
+    size: DVec2,
+}
+
+impl Tumble {
+    fn new(width: u32, height: u32) -> Self {
+        let mut rng = core::random::rng();
+
+        let mut movers = vec![];
+        for _ in 0..100 {
+            let x = rng.gen_range(0..width) as f64;
+            let y = rng.gen_range(0..height / 4) as f64;
+
+            let mut mover = Mover::new(rng.gen_range(0.1..5.0), x, y);
+            mover.radius = mover_size(&mover) / 2.0;
+            mover.max_angular_speed = Some(100.0);
+            mover.edge_policy = EdgePolicy::Bounce;
+            movers.push(mover);
         }
-    }
 
-    fn apply_force(&mut self, force: DVec2) {
-        let force = if self.mass > 0.0 {
-            force / self.mass
-        } else {
-            force
-        };
-        self.acceleration += force;
+        Self {
+            movers,
+            wind: Wind::default(),
+            size: DVec2::new(width as f64, height as f64),
+        }
     }
+}
 
+impl FixedSketch for Tumble {
     fn update(&mut self, dt: f64) {
-        self.velocity += self.acceleration * dt;
-        self.location += self.velocity * dt;
+        self.wind.accumulator += dt;
 
-        self.angular_velocity = core::math::clampf(
-            self.angular_velocity + self.angular_acceleration * dt,
-            -100.0,
-            100.0,
-        );
-        self.angle += self.angular_velocity * dt;
+        let wind_force = DVec2::new(core::noise(self.wind.accumulator, 0.5), 0.0) * 20.0;
+        let gravity = DVec2::new(0.0, 100.0);
 
-        // special sauce
-        self.angular_acceleration = self.acceleration.x * 10.0;
-
-        self.acceleration = DVec2::default();
-    }
+        for mover in self.movers.iter_mut() {
+            mover.apply_force(wind_force);
+            mover.apply_force(gravity * mover.mass);
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale_alpha(screen, 175.0, 200.0);
+            // special sauce, spin with the horizontal push on the next update
+            let spin = mover.acceleration.x * 10.0;
 
-        screen.push_matrix();
+            mover.update(dt);
+            mover.angular_acceleration = spin;
 
-        core::translate(screen, self.location.x, self.location.y);
-        core::rotate(screen, self.angle);
+            mover.check_bounds(DVec2::ZERO, self.size);
+        }
+    }
 
-        screen.rect_mode(&core::shapes::RectMode::Center.to_string());
-        core::shapes::rect(screen, 0.0, 0.0, self.size(), self.size())?;
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-        screen.pop_matrix();
+        for mover in self.movers.iter() {
+            display_mover(mover, canvas, alpha)?;
+        }
 
         Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+fn main() -> Result<(), ProcessingErr> {
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Tumble::new(WIDTH, HEIGHT),
+    )
 }
 
-fn draw(
-    screen: &mut Screen,
-    dt: f64,
-    mut movers: impl AsMut<[Mover]>,
-    wind: &mut Wind,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
 
-    wind.accumulator += dt;
+    use super::*;
 
-    let wind_force = DVec2::new(core::noise(wind.accumulator, 0.5), 0.0) * 20.0;
-    let gravity = DVec2::new(0.0, 100.0);
+    #[test]
+    fn tumble_snapshot() {
+        core::random::set_seed(1);
 
-    for mover in movers.as_mut() {
-        mover.apply_force(wind_force);
-        mover.apply_force(gravity * mover.mass);
+        let mut tumble = FixedSteps::new(FixedTimestep::default(), Tumble::new(WIDTH, HEIGHT));
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 60, &mut tumble).unwrap();
 
-        mover.update(dt);
-        mover.bounce_edges(screen);
-        mover.display(screen)?;
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/tumble.png"),
+        );
     }
-
-    Ok(())
-}
-
-fn main() -> Result<(), ProcessingErr> {
-    let movers = Rc::new(RefCell::new(None));
-    let mut wind = Wind::default();
-
-    core::run(
-        || {
-            let mut rng = rand::thread_rng();
-            let screen = setup()?;
-
-            let mut mvrs = vec![];
-            for _ in 0..100 {
-                let x = rng.gen_range(0..screen.width()) as f64;
-                let y = rng.gen_range(0..screen.height() / 4) as f64;
-                mvrs.push(Mover::new(rng.gen_range(0.1..5.0), x, y));
-            }
-            *movers.borrow_mut() = Some(mvrs);
-
-            Ok(screen)
-        },
-        |screen, dt| draw(screen, dt, movers.borrow_mut().as_mut().unwrap(), &mut wind),
-    )?;
-
-    Ok(())
 }
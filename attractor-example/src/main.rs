@@ -1,11 +1,13 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::mover::{Mover, UNIT_STEP};
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 const G: f64 = 1.0;
 
 #[derive(Debug, Default)]
@@ -32,12 +34,12 @@ impl Attractor {
     }
 
     #[allow(dead_code)]
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale_alpha(screen, 175.0, 200.0);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale_alpha(canvas, 175.0, 200.0);
 
         core::shapes::ellipse(
-            screen,
+            canvas,
             self.location.x,
             self.location.y,
             self.mass * 2.0,
@@ -46,119 +48,102 @@ impl Attractor {
     }
 }
 
-#[derive(Debug, Default)]
-struct Mover {
-    location: DVec2,
-    velocity: DVec2,
-    acceleration: DVec2,
-    mass: f64,
+fn display_mover(mover: &Mover, canvas: &mut impl Canvas, alpha: f64) -> Result<(), ProcessingErr> {
+    core::stroke_grayscale(canvas, 0.0);
+    core::fill_grayscale(canvas, 0.0);
+
+    // interpolate between simulation steps
+    let location = mover.interpolated_location(alpha);
+
+    core::shapes::ellipse(
+        canvas,
+        location.x,
+        location.y,
+        mover.mass * 16.0,
+        mover.mass * 16.0,
+    )
 }
 
-impl Mover {
-    fn new(mass: f64, x: f64, y: f64) -> Self {
-        Self {
-            location: DVec2::new(x, y),
-            mass,
-            ..Default::default()
+#[derive(Debug)]
+struct Field {
+    movers: Vec<Mover>,
+    attractors: Vec<Attractor>,
+}
+
+impl Field {
+    fn random(width: u32, height: u32) -> Self {
+        let mut rng = core::random::rng();
+
+        let mut movers = vec![];
+        for _ in 0..10 {
+            let x = rng.gen_range(0..width) as f64;
+            let y = rng.gen_range(0..height) as f64;
+            movers.push(Mover::new(rng.gen_range(0.1..2.0), x, y));
+        }
+
+        let mut attractors = vec![];
+        for _ in 0..10 {
+            let x = rng.gen_range(0..width) as f64;
+            let y = rng.gen_range(0..height) as f64;
+            attractors.push(Attractor::new(rng.gen_range(10.0..20.0), x, y));
         }
-    }
 
-    fn apply_force(&mut self, force: DVec2) {
-        let force = if self.mass > 0.0 {
-            force / self.mass
-        } else {
-            force
-        };
-        self.acceleration += force;
+        Self { movers, attractors }
     }
+}
 
+impl FixedSketch for Field {
     fn update(&mut self, _dt: f64) {
-        self.velocity += self.acceleration; // * dt;
-        self.location += self.velocity; // * dt;
+        for mover in self.movers.iter_mut() {
+            for attractor in self.attractors.iter() {
+                let f = attractor.attract(mover);
+                mover.apply_force(f);
+            }
 
-        self.acceleration = DVec2::default();
+            mover.update(UNIT_STEP);
+        }
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 0.0);
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-        core::shapes::ellipse(
-            screen,
-            self.location.x,
-            self.location.y,
-            self.mass * 16.0,
-            self.mass * 16.0,
-        )
-    }
-}
+        /*for attractor in self.attractors.iter() {
+            attractor.display(canvas)?;
+        }*/
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
-
-fn draw(
-    screen: &mut Screen,
-    dt: f64,
-    mut movers: impl AsMut<[Mover]>,
-    attractors: impl AsRef<[Attractor]>,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
-
-    /*for attractor in attractors.as_ref() {
-        attractor.display(screen)?;
-    }*/
-
-    for mover in movers.as_mut() {
-        for attractor in attractors.as_ref() {
-            let f = attractor.attract(mover);
-            mover.apply_force(f);
+        for mover in self.movers.iter() {
+            display_mover(mover, canvas, alpha)?;
         }
 
-        mover.update(dt);
-        mover.display(screen)?;
+        Ok(())
     }
-
-    Ok(())
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let movers = Rc::new(RefCell::new(None));
-    let attractors = Rc::new(RefCell::new(None));
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Field::random(WIDTH, HEIGHT),
+    )
+}
 
-    core::run(
-        || {
-            let mut rng = rand::thread_rng();
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
 
-            let screen = setup()?;
+    use super::*;
 
-            let mut mvrs = vec![];
-            for _ in 0..10 {
-                let x = rng.gen_range(0..screen.width()) as f64;
-                let y = rng.gen_range(0..screen.height()) as f64;
-                mvrs.push(Mover::new(rng.gen_range(0.1..2.0), x, y));
-            }
-            *movers.borrow_mut() = Some(mvrs);
+    #[test]
+    fn field_snapshot() {
+        core::random::set_seed(1);
 
-            let mut attrs = vec![];
-            for _ in 0..10 {
-                let x = rng.gen_range(0..screen.width()) as f64;
-                let y = rng.gen_range(0..screen.height()) as f64;
-                attrs.push(Attractor::new(rng.gen_range(10.0..20.0), x, y));
-            }
-            *attractors.borrow_mut() = Some(attrs);
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                movers.borrow_mut().as_mut().unwrap(),
-                attractors.borrow().as_ref().unwrap(),
-            )
-        },
-    )?;
-
-    Ok(())
+        let mut field = FixedSteps::new(FixedTimestep::default(), Field::random(WIDTH, HEIGHT));
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 60, &mut field).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/field.png"),
+        );
+    }
 }
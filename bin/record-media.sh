@@ -0,0 +1,12 @@
+#! /bin/sh
+
+# regenerates the GIFs in media/
+# pass --seed to reproduce a previous recording
+
+set -e
+
+FRAMES=${FRAMES:-0..180}
+
+for sketch in angular-example attractor-example game-of-life perlin-example intro-exercise5; do
+    cargo run --release --bin $sketch -- --record media/$sketch.gif --record-frames $FRAMES "$@"
+done
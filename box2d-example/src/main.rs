@@ -1,25 +1,40 @@
-use std::cell::RefCell;
+use std::io;
 use std::ops::DerefMut;
-use std::rc::Rc;
 
+use core::box2d::debug::DebugDraw;
+use core::box2d::joints::MouseDrag;
+use core::box2d::scene::{Scene, SceneError, SceneHandles};
+use core::box2d::{Box2dWorld, ContactEventKind};
+use core::color::{self, Color};
+use core::shapes::EndMode;
+use core::{B2dSketch, Canvas};
+use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
-use rand::Rng;
 use wrapped2d::b2;
 use wrapped2d::b2::Joint;
-use wrapped2d::dynamics::world::callbacks::ContactAccess;
 use wrapped2d::user_data::UserDataTypes;
 
 struct CustomUserData;
 
 impl UserDataTypes for CustomUserData {
-    // TODO: no clue what we can actually use here
-    type BodyData = ();
+    // index into the sketch's boxes, everything else is None
+    type BodyData = Option<usize>;
     type JointData = ();
     type FixtureData = ();
 }
 
-type World = b2::World<CustomUserData>;
+type World = Box2dWorld<CustomUserData>;
+
+const WIDTH: u32 = 400;
+const HEIGHT: u32 = 300;
+
+const SCENE_PATH: &str = "data/box2d-scene.ron";
+
+// normal impulse that counts as an impact rather than resting contact
+const IMPACT_IMPULSE: f32 = 2.0;
+
+// how much of the impact color fades each frame
+const IMPACT_FADE: f64 = 0.05;
 
 #[derive(Debug)]
 struct Windmill {
@@ -31,17 +46,12 @@ struct Windmill {
 
 impl Windmill {
     #[allow(dead_code)]
-    fn new(world: &mut World, screen: &Screen, x: f64, y: f64) -> Self {
-        let box1 = BoxBox::new(world, screen, x, y, 120.0, 10.0);
-        let box2 = BoxBox::new(world, screen, x, y, 10.0, 40.0);
+    fn new(world: &mut World, x: f64, y: f64) -> Self {
+        let box1 = BoxBox::new(world, None, x, y, 120.0, 10.0);
+        let box2 = BoxBox::new(world, None, x, y, 10.0, 40.0);
 
-        let mut rjd = b2::RevoluteJointDef::new(box1.body.unwrap(), box2.body.unwrap());
-        rjd.init(
-            world,
-            box1.body.unwrap(),
-            box2.body.unwrap(),
-            world.body(box1.body.unwrap()).world_center(),
-        );
+        let mut rjd =
+            world.revolute_joint_def(box1.body.unwrap(), box2.body.unwrap(), DVec2::new(x, y));
         rjd.motor_speed = std::f32::consts::PI * 2.0;
         rjd.max_motor_torque = 1000.0;
         rjd.enable_motor = true;
@@ -62,159 +72,111 @@ impl Windmill {
     }
 
     #[allow(dead_code)]
-    fn display(&self, screen: &mut Screen, world: &World) -> Result<(), ProcessingErr> {
-        self.box1.display(screen, world)?;
-        self.box2.display(screen, world)?;
+    fn display(&self, canvas: &mut impl Canvas, world: &World) -> Result<(), ProcessingErr> {
+        self.box1.display(canvas, world)?;
+        self.box2.display(canvas, world)?;
 
         Ok(())
     }
 }
 
+// bodies and joints loaded from the scene file, drawn from their fixtures
 #[derive(Debug)]
-struct Surface {
-    body: b2::BodyHandle,
-    fixture: b2::FixtureHandle,
+struct Scenery {
+    handles: SceneHandles,
 }
 
-impl Surface {
-    fn new(world: &mut World, screen: &Screen, vertices: impl AsRef<Vec<b2::Vec2>>) -> Self {
-        // convert to world coords
-        let mut surface = Vec::with_capacity(vertices.as_ref().len());
-        for vertex in vertices.as_ref() {
-            surface.push(core::coord_pixels_to_world(
-                screen,
-                vertex.x as f64,
-                vertex.y as f64,
-            ));
-        }
-
-        let mut bd = b2::BodyDef::new();
-        bd.body_type = b2::BodyType::Static;
-        let body = world.create_body_with(&bd, ());
+impl Scenery {
+    fn load(world: &mut World) -> Result<Self, SceneError> {
+        let handles = Scene::load(SCENE_PATH)?.build(world)?;
 
-        let cs = b2::ChainShape::new_chain(&surface);
-        let fixture = world.body_mut(body).create_fast_fixture(&cs, 1.0);
-
-        Self { body, fixture }
+        Ok(Self { handles })
     }
 
-    fn display(&self, screen: &mut Screen, world: &World) -> Result<(), ProcessingErr> {
-        let body = world.body(self.body);
-        let fixture = body.fixture(self.fixture);
-        let shape = fixture.shape();
-
-        screen.fill_off();
-        screen.stroke_weight(1.0);
-        core::stroke_grayscale(screen, 0.0);
+    fn display(&self, canvas: &mut impl Canvas, world: &World) -> Result<(), ProcessingErr> {
+        core::stroke_weight(canvas, 1.0);
+        core::stroke_grayscale(canvas, 0.0);
 
-        // TODO: beginShape();
+        for joint in self.handles.joints.iter() {
+            let joint = world.joint(*joint);
+            let a = world.vector_world_to_pixels(joint.anchor_a());
+            let b = world.vector_world_to_pixels(joint.anchor_b());
 
-        let vertices = match &*shape {
-            b2::UnknownShape::Chain(shape) => shape.vertices(),
-            _ => panic!("unexpected shape type {:?}", fixture.shape_type()),
-        };
-
-        for vertex in vertices {
-            let _v = core::vector_world_to_pixels(screen, *vertex);
-            //vertex(v.x, v.y);
+            core::shapes::line(canvas, a.x as f64, a.y as f64, b.x as f64, b.y as f64)?;
         }
 
-        // TODO: endShape(CLOSE);
-
-        Ok(())
-    }
-}
-
-#[derive(Debug)]
-struct Boundary {
-    body: b2::BodyHandle,
-
-    w: f64,
-    h: f64,
-}
-
-impl Boundary {
-    fn new(world: &mut World, screen: &Screen, x: f64, y: f64, w: f64, h: f64) -> Self {
-        let mut bd = b2::BodyDef::new();
-        bd.body_type = b2::BodyType::Static;
-        bd.position = core::coord_pixels_to_world(screen, x, y);
-        let body = world.create_body_with(&bd, ());
-
-        let ps = b2::PolygonShape::new_box(
-            core::scalar_pixels_to_world(w / 2.0) as f32,
-            core::scalar_pixels_to_world(h / 2.0) as f32,
-        );
-
-        world.body_mut(body).create_fast_fixture(&ps, 1.0);
-
-        Self { body, w, h }
-    }
-
-    fn display(&self, screen: &mut Screen, world: &World) -> Result<(), ProcessingErr> {
-        let body = world.body(self.body);
-        let pos = core::get_body_pixel_coord(screen, &body);
-
-        core::fill_grayscale(screen, 0.0);
-        core::stroke_grayscale(screen, 0.0);
-
-        screen.rect_mode(&core::shapes::RectMode::Center.to_string());
-        core::shapes::rect(screen, pos.x as f64, pos.y as f64, self.w, self.h)?;
+        for body in self.handles.bodies.iter() {
+            display_body(canvas, world, *body)?;
+        }
 
         Ok(())
     }
 }
 
-#[derive(Debug)]
-struct Pair {
-    p1: BoxBox,
-    p2: BoxBox,
-}
-
-impl Pair {
-    fn new(world: &mut World, screen: &Screen, x: f64, y: f64) -> Self {
-        let mut rng = rand::thread_rng();
+fn display_body(
+    canvas: &mut impl Canvas,
+    world: &World,
+    handle: b2::BodyHandle,
+) -> Result<(), ProcessingErr> {
+    let body = world.body(handle);
+    let pos = world.get_body_pixel_coord(&body);
+
+    // fixture vertices are relative to the body in world units
+    let to_pixels = |v: &b2::Vec2| {
+        let y = world.scalar_world_to_pixels(v.y as f64);
+        DVec2::new(
+            world.scalar_world_to_pixels(v.x as f64),
+            if world.y_flip() { -y } else { y },
+        )
+    };
+
+    core::push_matrix(canvas);
+
+    core::translate(canvas, pos.x as f64, pos.y as f64);
+    core::rotate(canvas, world.angle_world_to_pixels(body.angle() as f64));
+
+    for (_, fixture) in body.fixtures() {
+        // static bodies are solid, everything else matches the boxes
+        if body.body_type() == b2::BodyType::Static {
+            core::fill_grayscale(canvas, 0.0);
+        } else {
+            core::fill_grayscale(canvas, 175.0);
+        }
 
-        let len = 32.0;
+        match &*fixture.shape() {
+            b2::UnknownShape::Polygon(shape) => {
+                let vertices: Vec<_> = (0..shape.vertex_count())
+                    .map(|i| to_pixels(shape.vertex(i)))
+                    .collect();
+                core::shapes::polygon(canvas, &vertices, EndMode::Close)?;
+            }
+            b2::UnknownShape::Circle(shape) => {
+                let center = to_pixels(&shape.position());
+                let d = world.scalar_world_to_pixels(shape.radius() as f64) * 2.0;
 
-        let p1 = BoxBox::new(world, screen, x, y, 16.0, 16.0);
-        let p2 = BoxBox::new(
-            world,
-            screen,
-            x + rng.gen_range(-1.0..=1.0) as f64,
-            y + rng.gen_range(-1.0..=1.0) as f64,
-            16.0,
-            16.0,
-        );
+                core::shapes::ellipse_mode(canvas, core::shapes::RectMode::Center);
+                core::shapes::ellipse(canvas, center.x, center.y, d, d)?;
+            }
+            b2::UnknownShape::Chain(shape) => {
+                let vertices: Vec<_> = shape.vertices().iter().map(to_pixels).collect();
 
-        let mut djd = b2::DistanceJointDef::new(p1.body.unwrap(), p2.body.unwrap());
-        djd.length = core::scalar_pixels_to_world(len) as f32;
-        world.create_joint(&djd);
+                core::fill_off(canvas);
+                core::shapes::polygon(canvas, &vertices, EndMode::Open)?;
+            }
+            b2::UnknownShape::Edge(shape) => {
+                let a = to_pixels(&shape.v1());
+                let b = to_pixels(&shape.v2());
 
-        Self { p1, p2 }
+                core::shapes::line(canvas, a.x, a.y, b.x, b.y)?;
+            }
+            // shapes box2d doesn't expose aren't drawn
+            b2::UnknownShape::Unknown => {}
+        }
     }
 
-    fn display(&self, screen: &mut Screen, world: &World) -> Result<(), ProcessingErr> {
-        let p1 = world.body(self.p1.body.unwrap());
-        let pos1 = core::get_body_pixel_coord(screen, &p1);
-
-        let p2 = world.body(self.p2.body.unwrap());
-        let pos2 = core::get_body_pixel_coord(screen, &p2);
-
-        core::stroke_grayscale(screen, 0.0);
-
-        core::shapes::line(
-            screen,
-            pos1.x as f64,
-            pos1.y as f64,
-            pos2.x as f64,
-            pos2.y as f64,
-        )?;
+    core::pop_matrix(canvas);
 
-        self.p1.display(screen, world)?;
-        self.p2.display(screen, world)?;
-
-        Ok(())
-    }
+    Ok(())
 }
 
 #[derive(Debug)]
@@ -223,18 +185,21 @@ struct BoxBox {
 
     w: f64,
     h: f64,
+
+    // 1 right after a hard hit, fading to 0
+    impact: f64,
 }
 
 impl BoxBox {
-    fn new(world: &mut World, screen: &Screen, x: f64, y: f64, w: f64, h: f64) -> Self {
+    fn new(world: &mut World, id: Option<usize>, x: f64, y: f64, w: f64, h: f64) -> Self {
         let mut bd = b2::BodyDef::new();
         bd.body_type = b2::BodyType::Dynamic;
-        bd.position = core::coord_pixels_to_world(screen, x, y);
-        let body = world.create_body_with(&bd, ());
+        bd.position = world.coord_pixels_to_world(x, y);
+        let body = world.create_body_with(&bd, id);
 
         let ps = b2::PolygonShape::new_box(
-            core::scalar_pixels_to_world(w / 2.0) as f32,
-            core::scalar_pixels_to_world(h / 2.0) as f32,
+            world.scalar_pixels_to_world(w / 2.0) as f32,
+            world.scalar_pixels_to_world(h / 2.0) as f32,
         );
 
         let mut fd = b2::FixtureDef::new();
@@ -248,9 +213,15 @@ impl BoxBox {
             body: Some(body),
             w,
             h,
+
+            impact: 0.0,
         }
     }
 
+    fn update(&mut self) {
+        self.impact = (self.impact - IMPACT_FADE).max(0.0);
+    }
+
     #[allow(dead_code)]
     fn apply_force(&self, world: World, force: &b2::Vec2) {
         let mut body = world.body_mut(self.body.unwrap());
@@ -259,23 +230,25 @@ impl BoxBox {
         body.apply_force(force, &pos, true);
     }
 
-    fn display(&self, screen: &mut Screen, world: &World) -> Result<(), ProcessingErr> {
+    fn display(&self, canvas: &mut impl Canvas, world: &World) -> Result<(), ProcessingErr> {
         let body = world.body(self.body.unwrap());
-        let pos = core::get_body_pixel_coord(screen, &body);
+        let pos = world.get_body_pixel_coord(&body);
         let a = body.angle();
 
-        core::fill_grayscale(screen, 175.0);
-        core::stroke_grayscale(screen, 0.0);
+        // flash red on impact
+        let color = color::lerp_color(Color::grayscale(175.0), Color::RED, self.impact as f32);
+        core::fill(canvas, color);
+        core::stroke_grayscale(canvas, 0.0);
 
-        screen.push_matrix();
+        core::push_matrix(canvas);
 
-        core::translate(screen, pos.x as f64, pos.y as f64);
-        core::rotate(screen, -a as f64);
+        core::translate(canvas, pos.x as f64, pos.y as f64);
+        core::rotate(canvas, world.angle_world_to_pixels(a as f64));
 
-        screen.rect_mode(&core::shapes::RectMode::Center.to_string());
-        core::shapes::rect(screen, 0.0, 0.0, self.w, self.h)?;
+        core::shapes::rect_mode(canvas, core::shapes::RectMode::Center);
+        core::shapes::rect(canvas, 0.0, 0.0, self.w, self.h)?;
 
-        screen.pop_matrix();
+        core::pop_matrix(canvas);
 
         Ok(())
     }
@@ -286,118 +259,109 @@ impl BoxBox {
     }
 }
 
-struct ContactListener;
+// debug renderer and mouse dragging, driven by the keyboard and mouse
+#[derive(Debug)]
+struct Controls {
+    debug: DebugDraw,
+    drag: MouseDrag,
+}
+
+struct Boxes {
+    world: World,
+    boxes: Vec<BoxBox>,
+    scenery: Scenery,
+    controls: Controls,
+}
+
+impl Boxes {
+    fn load() -> Result<Self, ProcessingErr> {
+        let mut world = World::with_size(WIDTH, HEIGHT, DVec2::new(0.0, -9.81))
+            .with_post_solve_events(IMPACT_IMPULSE);
+        let scenery = Scenery::load(&mut world).map_err(scene_not_loaded)?;
 
-impl b2::ContactListener<CustomUserData> for ContactListener {
-    fn begin_contact(&mut self, _cp: ContactAccess<CustomUserData>) {
-        // TODO:
+        Ok(Self {
+            world,
+            boxes: vec![],
+            scenery,
+            controls: Controls {
+                debug: DebugDraw {
+                    enabled: false,
+                    contacts: true,
+                    ..Default::default()
+                },
+                drag: MouseDrag::new(),
+            },
+        })
     }
 }
 
-fn setup<'a>() -> Result<(Screen<'a>, World), ProcessingErr> {
-    let screen = core::create_canvas(400, 300)?;
+impl B2dSketch for Boxes {
+    type UserData = CustomUserData;
 
-    let gravity = b2::Vec2 { x: 0., y: -9.81 };
-    let mut world = World::new(&gravity);
+    fn world(&mut self) -> &mut World {
+        &mut self.world
+    }
 
-    world.set_contact_listener(Box::new(ContactListener));
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _dt: f64) -> Result<(), ProcessingErr> {
+        let Self {
+            world,
+            boxes,
+            scenery,
+            controls,
+        } = self;
 
-    Ok((screen, world))
-}
+        core::background_grayscale(canvas, 255.0);
 
-fn draw(
-    screen: &mut Screen,
-    world: &mut World,
-    _: f64,
-    boxes: &mut Vec<BoxBox>,
-    pairs: impl AsRef<[Pair]>,
-    boundaries: impl AsRef<[Boundary]>,
-    surfaces: impl AsRef<[Surface]>,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+        controls.debug.handle_input();
 
-    if core::input::mouse_is_pressed() {
-        let x = screen.mouse_x();
-        let y = screen.mouse_y();
-        boxes.push(BoxBox::new(world, screen, x, y, 16.0, 16.0));
-    }
+        // click a box to drag it, click anywhere else to add boxes
+        let dragging = controls.drag.update(world);
+        if !dragging && core::input::mouse_is_pressed() {
+            let mouse = core::input::mouse_position();
+            let id = boxes.len();
+            boxes.push(BoxBox::new(world, Some(id), mouse.x, mouse.y, 16.0, 16.0));
+        }
 
-    for boxbox in boxes.iter() {
-        boxbox.display(screen, world)?;
-    }
+        for boxbox in boxes.iter_mut() {
+            boxbox.update();
+        }
 
-    for pair in pairs.as_ref().iter() {
-        pair.display(screen, world)?;
-    }
+        for event in world.drain_contact_events() {
+            if let ContactEventKind::PostSolve { .. } = event.kind {
+                for id in [event.data_a, event.data_b].into_iter().flatten() {
+                    boxes[id].impact = 1.0;
+                }
+            }
+        }
 
-    for boundary in boundaries.as_ref().iter() {
-        boundary.display(screen, world)?;
-    }
+        // D switches to the debug renderer
+        if controls.debug.enabled {
+            controls.debug.draw(canvas, world)?;
+        } else {
+            for boxbox in boxes.iter() {
+                boxbox.display(canvas, world)?;
+            }
 
-    for surface in surfaces.as_ref().iter() {
-        surface.display(screen, world)?;
+            scenery.display(canvas, world)?;
+        }
+
+        controls.drag.draw(canvas, world)
     }
+}
 
-    Ok(())
+// processing has no error for assets other than images
+fn scene_not_loaded(err: SceneError) -> ProcessingErr {
+    let err = match err {
+        SceneError::Io(err) => io::Error::new(err.kind(), format!("{}: {}", SCENE_PATH, err)),
+        err => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", SCENE_PATH, err),
+        ),
+    };
+
+    ProcessingErr::ImageNotFound(err)
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let boxes = Rc::new(RefCell::new(None));
-    let pairs = Rc::new(RefCell::new(None));
-    let boundaries = Rc::new(RefCell::new(None));
-    let surfaces = Rc::new(RefCell::new(None));
-
-    core::b2d_run(
-        || {
-            let (screen, mut world) = setup()?;
-
-            *boxes.borrow_mut() = Some(vec![]);
-
-            let hw = screen.width() as f64 / 2.0;
-            let hh = screen.height() as f64 / 2.0;
-
-            *pairs.borrow_mut() = Some(vec![Pair::new(&mut world, &screen, hw + 30.0, hh - 30.0)]);
-
-            *boundaries.borrow_mut() = Some(vec![Boundary::new(
-                &mut world,
-                &screen,
-                hw - 50.0,
-                hh - 50.0,
-                100.0,
-                10.0,
-            )]);
-
-            let hw = screen.width() as f32 / 2.0;
-            let hh = screen.height() as f32 / 2.0;
-
-            let vertices = vec![
-                b2::Vec2 {
-                    x: 0.0,
-                    y: hh + 50.0,
-                },
-                b2::Vec2 {
-                    x: hw,
-                    y: hh + 50.0,
-                },
-                b2::Vec2 { x: hw, y: hh },
-            ];
-
-            *surfaces.borrow_mut() = Some(vec![Surface::new(&mut world, &screen, vertices)]);
-
-            Ok((screen, world))
-        },
-        |screen, world, dt| {
-            draw(
-                screen,
-                world,
-                dt,
-                boxes.borrow_mut().as_mut().unwrap(),
-                pairs.borrow_mut().as_mut().unwrap(),
-                boundaries.borrow_mut().as_mut().unwrap(),
-                surfaces.borrow_mut().as_mut().unwrap(),
-            )
-        },
-    )?;
-
-    Ok(())
+    core::b2d_run_sketch(WIDTH, HEIGHT, Boxes::load()?)
 }
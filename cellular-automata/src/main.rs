@@ -2,8 +2,11 @@
 
 // TODO: finish this
 
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
 
 #[derive(Debug)]
 struct CA {
@@ -14,8 +17,8 @@ struct CA {
 }
 
 impl CA {
-    fn new(screen: &Screen) -> Self {
-        let len = screen.width() as usize;
+    fn new(width: u32) -> Self {
+        let len = width as usize;
         let mut cells = vec![0; len];
         cells[len / 2] = 1;
 
@@ -53,16 +56,16 @@ impl CA {
         self.ruleset[index]
     }
 
-    fn draw(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
         for i in 0..self.cells.len() {
             if self.cells[i] == 1 {
-                core::fill_grayscale(screen, 0.0);
+                core::fill_grayscale(canvas, 0.0);
             } else {
-                core::fill_grayscale(screen, 255.0);
+                core::fill_grayscale(canvas, 255.0);
             }
 
             core::shapes::rect(
-                screen,
+                canvas,
                 i as f64 * self.cell_size,
                 self.generation as f64 * self.cell_size,
                 self.cell_size,
@@ -74,18 +77,29 @@ impl CA {
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
-
-fn draw(screen: &mut Screen, _dt: f64) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+impl Sketch for CA {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    Ok(())
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    core::run(setup, draw)?;
+    core::run_sketch(WIDTH, HEIGHT, CA::new(WIDTH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn ca_snapshot() {
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 1, &mut CA::new(WIDTH)).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/ca.png"),
+        );
+    }
 }
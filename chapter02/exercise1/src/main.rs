@@ -1,11 +1,12 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 #[derive(Debug, Default)]
 struct Balloon {
     location: DVec2,
@@ -21,15 +22,15 @@ impl Balloon {
         }
     }
 
-    fn check_edges(&mut self, screen: &Screen) {
-        if self.location.x > screen.width() as f64 {
-            self.location.x = screen.width() as f64;
+    fn check_edges(&mut self, size: DVec2) {
+        if self.location.x > size.x {
+            self.location.x = size.x;
         } else if self.location.x < 0.0 {
             self.location.x = 0.0;
         }
 
-        if self.location.y > screen.height() as f64 {
-            self.location.y = screen.height() as f64;
+        if self.location.y > size.y {
+            self.location.y = size.y;
         } else if self.location.y < 0.0 {
             self.location.y = 0.0;
             self.velocity.y *= -0.2;
@@ -47,11 +48,11 @@ impl Balloon {
         self.acceleration = DVec2::default();
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 0.0);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 0.0);
 
-        core::shapes::ellipse(screen, self.location.x, self.location.y, 16.0, 16.0)
+        core::shapes::ellipse(canvas, self.location.x, self.location.y, 16.0, 16.0)
     }
 }
 
@@ -60,69 +61,92 @@ struct Wind {
     accumulator: f64,
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+impl Wind {
+    fn force(&self) -> DVec2 {
+        DVec2::new(core::noise(self.accumulator, 0.5), 0.0) * 20.0
+    }
 }
 
-fn draw(
-    screen: &mut Screen,
-    dt: f64,
-    mut balloons: impl AsMut<[Balloon]>,
-    wind: &mut Wind,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+#[derive(Debug)]
+struct Balloons {
+    balloons: Vec<Balloon>,
+    wind: Wind,
+    size: DVec2,
+}
+
+impl Balloons {
+    fn new(width: u32, height: u32) -> Self {
+        let mut rng = core::random::rng();
+
+        let mut balloons = vec![];
+        for _ in 0..20 {
+            let x = rng.gen_range(0..width) as f64;
+            balloons.push(Balloon::new(x, height as f64 - rng.gen_range(0.0..10.0)));
+        }
 
-    wind.accumulator += dt;
+        Self {
+            balloons,
+            wind: Wind::default(),
+            size: DVec2::new(width as f64, height as f64),
+        }
+    }
+}
+
+impl FixedSketch for Balloons {
+    fn update(&mut self, dt: f64) {
+        self.wind.accumulator += dt;
 
-    let balloon_force = DVec2::new(0.0, -10.0);
-    let wind_force = DVec2::new(core::noise(wind.accumulator, 0.5), 0.0) * 20.0;
+        let balloon_force = DVec2::new(0.0, -10.0);
+        let wind_force = self.wind.force();
 
-    for balloon in balloons.as_mut() {
-        balloon.apply_force(balloon_force);
-        balloon.apply_force(wind_force);
+        for balloon in self.balloons.iter_mut() {
+            balloon.apply_force(balloon_force);
+            balloon.apply_force(wind_force);
 
-        balloon.update(dt);
-        balloon.check_edges(screen);
-        balloon.display(screen)?;
+            balloon.update(dt);
+            balloon.check_edges(self.size);
+        }
     }
 
-    // stole this from dave - render the wind strength
-    core::shapes::rect(screen, 300.0, 20.0, wind_force.x * 16.0, 32.0)?;
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
+
+        for balloon in self.balloons.iter() {
+            balloon.display(canvas)?;
+        }
+
+        // stole this from dave - render the wind strength
+        core::shapes::rect(canvas, 300.0, 20.0, self.wind.force().x * 16.0, 32.0)?;
 
-    Ok(())
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let balloons = Rc::new(RefCell::new(None));
-    let mut wind = Wind::default();
-
-    core::run(
-        || {
-            let mut rng = rand::thread_rng();
-
-            let screen = setup()?;
-
-            let mut bs = vec![];
-            for _ in 0..20 {
-                let x = rng.gen_range(0..screen.width()) as f64;
-                bs.push(Balloon::new(
-                    x,
-                    screen.height() as f64 - rng.gen_range(0.0..10.0),
-                ));
-            }
-            *balloons.borrow_mut() = Some(bs);
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                balloons.borrow_mut().as_mut().unwrap(),
-                &mut wind,
-            )
-        },
-    )?;
-
-    Ok(())
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Balloons::new(WIDTH, HEIGHT),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
+
+    use super::*;
+
+    #[test]
+    fn balloons_snapshot() {
+        core::random::set_seed(1);
+
+        let mut balloons = FixedSteps::new(FixedTimestep::default(), Balloons::new(WIDTH, HEIGHT));
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 60, &mut balloons).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/balloons.png"),
+        );
+    }
 }
@@ -1,47 +1,68 @@
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+#[derive(Debug)]
+struct Baton {
+    angle: f64,
+    avelocity: f64,
+    aacceleration: f64,
+}
+
+impl Default for Baton {
+    fn default() -> Self {
+        Self {
+            angle: 0.0,
+            avelocity: 0.0,
+            aacceleration: 0.001,
+        }
+    }
 }
 
-fn draw(
-    screen: &mut Screen,
-    angle: &mut f64,
-    avelocity: &mut f64,
-    aacceleration: &mut f64,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
-
-    core::fill_grayscale(screen, 175.0);
-    core::stroke_grayscale(screen, 0.0);
-    screen.rect_mode(&core::shapes::RectMode::Center.to_string());
-
-    core::translate(
-        screen,
-        screen.width() as f64 / 2.0,
-        screen.height() as f64 / 2.0,
-    );
-    core::rotate(screen, *angle);
-
-    core::shapes::line(screen, -50.0, 0.0, 50.0, 0.0)?;
-    core::shapes::ellipse(screen, 50.0, 0.0, 8.0, 8.0)?;
-    core::shapes::ellipse(screen, -50.0, 0.0, 8.0, 8.0)?;
-
-    *avelocity += *aacceleration;
-    *angle += *avelocity;
-
-    Ok(())
+impl Sketch for Baton {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
+
+        core::fill_grayscale(canvas, 175.0);
+        core::stroke_grayscale(canvas, 0.0);
+        core::shapes::rect_mode(canvas, core::shapes::RectMode::Center);
+
+        core::translate(
+            canvas,
+            canvas.width() as f64 / 2.0,
+            canvas.height() as f64 / 2.0,
+        );
+        core::rotate(canvas, self.angle);
+
+        core::shapes::line(canvas, -50.0, 0.0, 50.0, 0.0)?;
+        core::shapes::ellipse(canvas, 50.0, 0.0, 8.0, 8.0)?;
+        core::shapes::ellipse(canvas, -50.0, 0.0, 8.0, 8.0)?;
+
+        self.avelocity += self.aacceleration;
+        self.angle += self.avelocity;
+
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let mut angle = 0.0;
-    let mut avelocity = 0.0;
-    let mut aacceleration = 0.001;
+    core::run_sketch(WIDTH, HEIGHT, Baton::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    core::run(setup, |screen, _| {
-        draw(screen, &mut angle, &mut avelocity, &mut aacceleration)
-    })?;
+    #[test]
+    fn baton_snapshot() {
+        let mut baton = Baton::default();
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 30, &mut baton).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/baton.png"),
+        );
+    }
 }
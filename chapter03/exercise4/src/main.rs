@@ -1,38 +1,60 @@
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    let mut screen = core::create_canvas(640, 360)?;
-    core::background_grayscale(&mut screen, 220.0);
-    Ok(screen)
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+#[derive(Debug, Default)]
+struct Spiral {
+    r: f64,
+    theta: f64,
 }
 
-fn draw(screen: &mut Screen, r: &mut f64, theta: &mut f64) -> Result<(), ProcessingErr> {
-    let x = *r * theta.cos();
-    let y = *r * theta.sin();
+impl Sketch for Spiral {
+    fn setup<C: Canvas>(&mut self, canvas: &mut C) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 220.0);
+
+        Ok(())
+    }
+
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        let x = self.r * self.theta.cos();
+        let y = self.r * self.theta.sin();
 
-    screen.stroke_off();
-    core::fill_grayscale(screen, 0.0);
+        core::stroke_off(canvas);
+        core::fill_grayscale(canvas, 0.0);
 
-    core::shapes::ellipse(
-        screen,
-        x + screen.width() as f64 / 2.0,
-        y + screen.height() as f64 / 2.0,
-        16.0,
-        16.0,
-    )?;
+        core::shapes::ellipse(
+            canvas,
+            x + canvas.width() as f64 / 2.0,
+            y + canvas.height() as f64 / 2.0,
+            16.0,
+            16.0,
+        )?;
 
-    *r += 0.05;
-    *theta += 0.01;
+        self.r += 0.05;
+        self.theta += 0.01;
 
-    Ok(())
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let mut r = 0.0;
-    let mut theta = 0.0;
+    core::run_sketch(WIDTH, HEIGHT, Spiral::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    core::run(setup, |screen, _| draw(screen, &mut r, &mut theta))?;
+    #[test]
+    fn spiral_snapshot() {
+        let mut spiral = Spiral::default();
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 600, &mut spiral).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/spiral.png"),
+        );
+    }
 }
@@ -0,0 +1,266 @@
+use glam::DVec2;
+use processing::errors::ProcessingErr;
+use processing::Key;
+use wrapped2d::b2;
+use wrapped2d::user_data::UserDataTypes;
+
+use super::{Box2dWorld, Projection};
+use crate::input;
+use crate::shapes::RectMode;
+use crate::Canvas;
+
+// length of the transform axes in world units
+const AXIS_LENGTH: f64 = 0.4;
+
+// contact points are drawn the same size at any scale
+const CONTACT_SIZE: f64 = 4.0;
+
+/// Draws every body, fixture and joint in a world
+///
+/// Shapes are colored by Box2D, static bodies green,
+/// kinematic bodies blue, sleeping bodies gray and awake dynamic bodies pink
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DebugDraw {
+    pub enabled: bool,
+
+    pub shapes: bool,
+    pub joints: bool,
+    pub aabbs: bool,
+    pub center_of_mass: bool,
+    pub contacts: bool,
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+
+            shapes: true,
+            joints: true,
+            aabbs: false,
+            center_of_mass: false,
+            contacts: false,
+        }
+    }
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Toggles drawing from the keyboard
+    ///
+    /// D toggles the renderer, 1-5 toggle shapes, joints, AABBs, centers of mass and contacts
+    pub fn handle_input(&mut self) {
+        let toggles = [
+            (Key::D, &mut self.enabled),
+            (Key::Key1, &mut self.shapes),
+            (Key::Key2, &mut self.joints),
+            (Key::Key3, &mut self.aabbs),
+            (Key::Key4, &mut self.center_of_mass),
+            (Key::Key5, &mut self.contacts),
+        ];
+
+        for (key, flag) in toggles {
+            if input::key_pressed(key) {
+                *flag = !*flag;
+            }
+        }
+    }
+
+    pub fn draw<U: UserDataTypes>(
+        &self,
+        canvas: &mut impl Canvas,
+        world: &mut Box2dWorld<U>,
+    ) -> Result<(), ProcessingErr> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let mut flags = b2::DrawFlags::empty();
+        if self.shapes {
+            flags = flags | b2::DrawFlags::DRAW_SHAPE;
+        }
+        if self.joints {
+            flags = flags | b2::DrawFlags::DRAW_JOINT;
+        }
+        if self.aabbs {
+            flags = flags | b2::DrawFlags::DRAW_AABB;
+        }
+        if self.center_of_mass {
+            flags = flags | b2::DrawFlags::DRAW_CENTER_OF_MASS;
+        }
+
+        // the renderer changes colors as it goes, the sketch gets its own back after
+        let state = canvas.draw_state();
+
+        // circles are given by their center
+        canvas.ellipse_mode(RectMode::Center);
+
+        let mut renderer = Renderer {
+            canvas,
+            projection: world.projection,
+            result: Ok(()),
+        };
+
+        world.world.draw_debug_data(&mut renderer, flags);
+
+        if self.contacts {
+            for point in world.contact_points() {
+                renderer.contact_point(&point);
+            }
+        }
+
+        let result = renderer.result;
+        canvas.set_draw_state(state);
+
+        result
+    }
+}
+
+// b2::Draw adapter from world units to the canvas
+struct Renderer<'a, C: Canvas> {
+    canvas: &'a mut C,
+    projection: Projection,
+
+    // the first error, b2::Draw has no way to report them
+    result: Result<(), ProcessingErr>,
+}
+
+impl<'a, C: Canvas> Renderer<'a, C> {
+    fn to_pixels(&self, v: &b2::Vec2) -> (f64, f64) {
+        let p = self.projection.world_to_pixels(v.x as f64, v.y as f64);
+        (p.x as f64, p.y as f64)
+    }
+
+    fn record(&mut self, result: Result<(), ProcessingErr>) {
+        if self.result.is_ok() {
+            self.result = result;
+        }
+    }
+
+    fn stroke(&mut self, color: &b2::Color) {
+        self.canvas.stroke(
+            color.r * 255.0,
+            color.g * 255.0,
+            color.b * 255.0,
+            color.a * 255.0,
+        );
+    }
+
+    // solid shapes are filled with a faded outline color, like the Box2D testbed
+    fn fill(&mut self, color: &b2::Color) {
+        self.canvas.fill(
+            color.r * 127.5,
+            color.g * 127.5,
+            color.b * 127.5,
+            color.a * 127.5,
+        );
+    }
+
+    fn polygon(&mut self, vertices: &[b2::Vec2], filled: bool) {
+        let points: Vec<_> = vertices
+            .iter()
+            .map(|v| {
+                let (x, y) = self.to_pixels(v);
+                DVec2::new(x, y)
+            })
+            .collect();
+
+        if !filled {
+            self.canvas.fill_off();
+        }
+
+        let result = self.canvas.polygon(&points, true);
+        self.record(result);
+    }
+
+    fn circle(&mut self, center: &b2::Vec2, radius: f32) {
+        let (x, y) = self.to_pixels(center);
+        let d = self.projection.scale * radius as f64 * 2.0;
+
+        let result = self.canvas.ellipse(x, y, d, d);
+        self.record(result);
+    }
+
+    fn segment(&mut self, p1: &b2::Vec2, p2: &b2::Vec2) {
+        let (x1, y1) = self.to_pixels(p1);
+        let (x2, y2) = self.to_pixels(p2);
+
+        let result = self.canvas.line(x1, y1, x2, y2);
+        self.record(result);
+    }
+
+    fn contact_point(&mut self, point: &b2::Vec2) {
+        let (x, y) = self.to_pixels(point);
+
+        self.canvas.stroke_off();
+        self.canvas.fill(255.0, 64.0, 64.0, 255.0);
+
+        let result = self.canvas.ellipse(x, y, CONTACT_SIZE, CONTACT_SIZE);
+        self.record(result);
+    }
+}
+
+impl<'a, C: Canvas> b2::Draw for Renderer<'a, C> {
+    fn draw_polygon(&mut self, vertices: &[b2::Vec2], color: &b2::Color) {
+        self.stroke(color);
+        self.polygon(vertices, false);
+    }
+
+    fn draw_solid_polygon(&mut self, vertices: &[b2::Vec2], color: &b2::Color) {
+        self.stroke(color);
+        self.fill(color);
+        self.polygon(vertices, true);
+    }
+
+    fn draw_circle(&mut self, center: &b2::Vec2, radius: f32, color: &b2::Color) {
+        self.stroke(color);
+        self.canvas.fill_off();
+        self.circle(center, radius);
+    }
+
+    fn draw_solid_circle(
+        &mut self,
+        center: &b2::Vec2,
+        radius: f32,
+        axis: &b2::Vec2,
+        color: &b2::Color,
+    ) {
+        self.stroke(color);
+        self.fill(color);
+        self.circle(center, radius);
+
+        // radius along the axis shows the rotation
+        let edge = b2::Vec2 {
+            x: center.x + axis.x * radius,
+            y: center.y + axis.y * radius,
+        };
+        self.segment(center, &edge);
+    }
+
+    fn draw_segment(&mut self, p1: &b2::Vec2, p2: &b2::Vec2, color: &b2::Color) {
+        self.stroke(color);
+        self.segment(p1, p2);
+    }
+
+    fn draw_transform(&mut self, xf: &b2::Transform) {
+        let axis = AXIS_LENGTH as f32;
+
+        let x_axis = b2::Vec2 {
+            x: xf.pos.x + xf.rot.cos * axis,
+            y: xf.pos.y + xf.rot.sin * axis,
+        };
+        let y_axis = b2::Vec2 {
+            x: xf.pos.x - xf.rot.sin * axis,
+            y: xf.pos.y + xf.rot.cos * axis,
+        };
+
+        self.canvas.stroke(255.0, 0.0, 0.0, 255.0);
+        self.segment(&xf.pos, &x_axis);
+
+        self.canvas.stroke(0.0, 255.0, 0.0, 255.0);
+        self.segment(&xf.pos, &y_axis);
+    }
+}
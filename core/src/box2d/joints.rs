@@ -0,0 +1,310 @@
+use glam::DVec2;
+use processing::errors::ProcessingErr;
+use processing::MouseButton;
+use wrapped2d::b2;
+use wrapped2d::b2::Joint;
+use wrapped2d::user_data::UserDataTypes;
+
+use super::Box2dWorld;
+use crate::input;
+use crate::Canvas;
+
+// half size in pixels of the area searched for a body under the mouse
+const PICK_RADIUS: f64 = 1.0;
+
+/* joint definitions */
+
+// anchors and lengths are given in pixels, the definitions are returned
+// so motors, limits and springs can be set before `create_joint`
+
+impl<U: UserDataTypes> Box2dWorld<U> {
+    /// Static body without fixtures for pinning joints to the world
+    ///
+    /// Created on first use and shared by every joint that needs it
+    pub fn ground_body(&mut self) -> b2::BodyHandle
+    where
+        U::BodyData: Default,
+    {
+        match self.ground {
+            Some(ground) => ground,
+            None => {
+                let ground = self.world.create_body(&b2::BodyDef::new());
+                self.ground = Some(ground);
+                ground
+            }
+        }
+    }
+
+    /// Bodies rotating around a shared anchor
+    pub fn revolute_joint_def(
+        &self,
+        a: b2::BodyHandle,
+        b: b2::BodyHandle,
+        anchor: DVec2,
+    ) -> b2::RevoluteJointDef {
+        let mut def = b2::RevoluteJointDef::new(a, b);
+        def.init(&self.world, a, b, &self.pixels_to_world(anchor));
+        def
+    }
+
+    /// Bodies sliding along an axis through the anchor, the axis is a direction in pixels
+    pub fn prismatic_joint_def(
+        &self,
+        a: b2::BodyHandle,
+        b: b2::BodyHandle,
+        anchor: DVec2,
+        axis: DVec2,
+    ) -> b2::PrismaticJointDef {
+        let mut axis = axis.normalize_or_zero();
+        if self.projection.y_flip {
+            axis.y = -axis.y;
+        }
+
+        let mut def = b2::PrismaticJointDef::new(a, b);
+        def.init(
+            &self.world,
+            a,
+            b,
+            &self.pixels_to_world(anchor),
+            &super::to_b2(axis),
+        );
+        def
+    }
+
+    /// Anchors held at their current distance apart
+    pub fn distance_joint_def(
+        &self,
+        a: b2::BodyHandle,
+        b: b2::BodyHandle,
+        anchor_a: DVec2,
+        anchor_b: DVec2,
+    ) -> b2::DistanceJointDef {
+        let mut def = b2::DistanceJointDef::new(a, b);
+        def.init(
+            &self.world,
+            a,
+            b,
+            &self.pixels_to_world(anchor_a),
+            &self.pixels_to_world(anchor_b),
+        );
+        def
+    }
+
+    /// Bodies glued together at the anchor
+    pub fn weld_joint_def(
+        &self,
+        a: b2::BodyHandle,
+        b: b2::BodyHandle,
+        anchor: DVec2,
+    ) -> b2::WeldJointDef {
+        let mut def = b2::WeldJointDef::new(a, b);
+        def.init(&self.world, a, b, &self.pixels_to_world(anchor));
+        def
+    }
+
+    /// Anchors kept at most `max_length` pixels apart
+    pub fn rope_joint_def(
+        &self,
+        a: b2::BodyHandle,
+        b: b2::BodyHandle,
+        anchor_a: DVec2,
+        anchor_b: DVec2,
+        max_length: f64,
+    ) -> b2::RopeJointDef {
+        let mut def = b2::RopeJointDef::new(a, b);
+        def.local_anchor_a = self
+            .world
+            .body(a)
+            .local_point(&self.pixels_to_world(anchor_a));
+        def.local_anchor_b = self
+            .world
+            .body(b)
+            .local_point(&self.pixels_to_world(anchor_b));
+        def.max_length = self.scalar_pixels_to_world(max_length) as f32;
+        def
+    }
+
+    /// Pulls the body towards the target, attached to the ground body
+    ///
+    /// `max_force` is in multiples of the body's mass
+    pub fn mouse_joint_def(
+        &mut self,
+        body: b2::BodyHandle,
+        target: DVec2,
+        max_force: f64,
+    ) -> b2::MouseJointDef
+    where
+        U::BodyData: Default,
+    {
+        let ground = self.ground_body();
+        let mass = self.world.body(body).mass();
+
+        let mut def = b2::MouseJointDef::new(ground, body);
+        def.target = self.pixels_to_world(target);
+        def.max_force = (max_force * mass as f64) as f32;
+        def.frequency = 5.0;
+        def.damping_ratio = 0.7;
+        def
+    }
+
+    /// Dynamic body with a fixture under the pixel position
+    pub fn body_at(&self, p: DVec2) -> Option<b2::BodyHandle> {
+        let point = self.pixels_to_world(p);
+
+        let d = self.scalar_pixels_to_world(PICK_RADIUS) as f32;
+        let aabb = b2::AABB {
+            lower: b2::Vec2 {
+                x: point.x - d,
+                y: point.y - d,
+            },
+            upper: b2::Vec2 {
+                x: point.x + d,
+                y: point.y + d,
+            },
+        };
+
+        let mut found = None;
+        self.world.query_aabb(
+            &mut |body: b2::BodyHandle, fixture: b2::FixtureHandle| {
+                let b = self.world.body(body);
+                if b.body_type() == b2::BodyType::Dynamic && b.fixture(fixture).test_point(&point) {
+                    found = Some(body);
+                    // stop the query
+                    return false;
+                }
+                true
+            },
+            &aabb,
+        );
+
+        found
+    }
+
+    fn pixels_to_world(&self, p: DVec2) -> b2::Vec2 {
+        self.projection.pixels_to_world(p.x, p.y)
+    }
+}
+
+/* mouse dragging */
+
+/// Click and drag dynamic bodies with a mouse joint
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MouseDrag {
+    pub button: MouseButton,
+
+    /// Strongest pull in multiples of the body's mass, like the Box2D testbed
+    pub max_force: f64,
+
+    joint: Option<b2::JointHandle>,
+}
+
+impl Default for MouseDrag {
+    fn default() -> Self {
+        Self {
+            button: MouseButton::Left,
+            max_force: 1000.0,
+
+            joint: None,
+        }
+    }
+}
+
+impl MouseDrag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.joint.is_some()
+    }
+
+    /// Picks up the body under the mouse on press, moves it while held and drops it on release
+    ///
+    /// Returns whether a body is being dragged, so sketches can skip their own click handling
+    pub fn update<U>(&mut self, world: &mut Box2dWorld<U>) -> bool
+    where
+        U: UserDataTypes,
+        U::BodyData: Default,
+        U::JointData: Default,
+    {
+        let mouse = input::mouse_position();
+
+        // the dragged body was destroyed since the last frame
+        self.joint = self.live_joint(world);
+
+        if input::mouse_pressed(self.button) && self.joint.is_none() {
+            if let Some(body) = world.body_at(mouse) {
+                let def = world.mouse_joint_def(body, mouse, self.max_force);
+                world.world.body_mut(body).set_awake(true);
+                self.joint = Some(world.world.create_joint(&def));
+            }
+        }
+
+        if let Some(joint) = self.joint {
+            if input::mouse_down(self.button) {
+                let target = world.pixels_to_world(mouse);
+                if let b2::UnknownJoint::Mouse(joint) = &mut **world.world.joint_mut(joint) {
+                    joint.set_target(&target);
+                }
+            } else {
+                self.release(world);
+            }
+        }
+
+        self.is_dragging()
+    }
+
+    /// Drops the body being dragged
+    pub fn release<U: UserDataTypes>(&mut self, world: &mut Box2dWorld<U>) {
+        if let Some(joint) = self.live_joint(world) {
+            world.world.destroy_joint(joint);
+        }
+
+        self.joint = None;
+    }
+
+    // Box2D destroys the joint along with the dragged body, leaving the handle dangling
+    fn live_joint<U: UserDataTypes>(&self, world: &Box2dWorld<U>) -> Option<b2::JointHandle> {
+        let joint = self.joint?;
+
+        world
+            .world
+            .joints()
+            .any(|(handle, _)| handle == joint)
+            .then_some(joint)
+    }
+
+    /// Line from the grabbed point to the mouse
+    pub fn draw<U: UserDataTypes>(
+        &self,
+        canvas: &mut impl Canvas,
+        world: &Box2dWorld<U>,
+    ) -> Result<(), ProcessingErr> {
+        let joint = match self.live_joint(world) {
+            Some(joint) => joint,
+            None => return Ok(()),
+        };
+
+        let (anchor, target) = match &**world.world.joint(joint) {
+            b2::UnknownJoint::Mouse(joint) => (joint.anchor_b(), *joint.target()),
+            _ => return Ok(()),
+        };
+
+        let anchor = world.vector_world_to_pixels(anchor);
+        let target = world.vector_world_to_pixels(target);
+
+        let state = canvas.draw_state();
+
+        canvas.stroke(0.0, 0.0, 0.0, 255.0);
+        let result = canvas.line(
+            anchor.x as f64,
+            anchor.y as f64,
+            target.x as f64,
+            target.y as f64,
+        );
+
+        canvas.set_draw_state(state);
+
+        result
+    }
+}
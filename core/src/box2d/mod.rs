@@ -0,0 +1,527 @@
+pub mod debug;
+pub mod joints;
+pub mod scene;
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+use glam::DVec2;
+use wrapped2d::b2;
+use wrapped2d::dynamics::world::callbacks::{ContactAccess, ContactListener};
+use wrapped2d::user_data::{UserData, UserDataTypes};
+
+use crate::timestep::FixedTimestep;
+use crate::Canvas;
+
+// https://github.com/shiffman/Box2D-for-Processing/blob/master/Box2D-for-Processing/src/shiffman/box2d/Box2DProcessing.java
+
+/// Pixels per world unit
+pub const DEFAULT_SCALE: f64 = 10.0;
+
+pub const DEFAULT_VELOCITY_ITERATIONS: i32 = 10;
+pub const DEFAULT_POSITION_ITERATIONS: i32 = 8;
+
+/// Contact events kept for `drain_contact_events`, the oldest are dropped past this
+pub const MAX_CONTACT_EVENTS: usize = 4096;
+
+// which solve callbacks are queued as events, they're sent every step
+// for every touching contact so they would push out begin and end events
+#[derive(Debug, Default, Copy, Clone)]
+struct SolveEvents {
+    pre_solve: bool,
+
+    // smallest normal impulse that's queued, None to skip post solve events
+    post_solve: Option<f32>,
+}
+
+// mapping between world units and pixels
+#[derive(Debug, Copy, Clone)]
+struct Projection {
+    scale: f64,
+    origin: DVec2,
+    y_flip: bool,
+}
+
+impl Projection {
+    fn world_to_pixels(&self, wx: f64, wy: f64) -> b2::Vec2 {
+        let px = self.origin.x + wx * self.scale;
+        let py = if self.y_flip {
+            self.origin.y - wy * self.scale
+        } else {
+            self.origin.y + wy * self.scale
+        };
+
+        b2::Vec2 {
+            x: px as f32,
+            y: py as f32,
+        }
+    }
+
+    fn pixels_to_world(&self, px: f64, py: f64) -> b2::Vec2 {
+        let wx = (px - self.origin.x) / self.scale;
+        let wy = if self.y_flip {
+            (self.origin.y - py) / self.scale
+        } else {
+            (py - self.origin.y) / self.scale
+        };
+
+        b2::Vec2 {
+            x: wx as f32,
+            y: wy as f32,
+        }
+    }
+
+    // pixel offsets, such as vertices relative to a body, ignore the origin
+    fn offset_to_world(&self, dx: f64, dy: f64) -> b2::Vec2 {
+        let dy = if self.y_flip { -dy } else { dy };
+
+        b2::Vec2 {
+            x: (dx / self.scale) as f32,
+            y: (dy / self.scale) as f32,
+        }
+    }
+
+    fn angle(&self, angle: f64) -> f64 {
+        if self.y_flip {
+            -angle
+        } else {
+            angle
+        }
+    }
+}
+
+/// Box2D world along with the mapping between world units and pixels
+///
+/// Derefs to the `b2::World` so bodies and joints are created on it directly
+pub struct Box2dWorld<U: UserDataTypes> {
+    world: b2::World<U>,
+    projection: Projection,
+
+    timestep: FixedTimestep,
+    velocity_iterations: i32,
+    position_iterations: i32,
+
+    // static body joints are pinned to, see `ground_body`
+    ground: Option<b2::BodyHandle>,
+
+    // shared with the contact listener installed on the world
+    contacts: Rc<RefCell<Vec<b2::Vec2>>>,
+    events: Rc<RefCell<VecDeque<ContactEvent<U::BodyData>>>>,
+    solve_events: Rc<Cell<SolveEvents>>,
+}
+
+impl<U> Box2dWorld<U>
+where
+    U: UserDataTypes + 'static,
+    U::BodyData: Clone,
+{
+    /// World with its origin in the center of the canvas and Y pointing up
+    pub fn new(canvas: &impl Canvas, gravity: DVec2) -> Self {
+        Self::with_size(canvas.width(), canvas.height(), gravity)
+    }
+
+    /// World with its origin in the center of a canvas of the given size,
+    /// for sketches that create their world before the canvas exists
+    pub fn with_size(width: u32, height: u32, gravity: DVec2) -> Self {
+        let mut world = Self {
+            world: b2::World::new(&to_b2(gravity)),
+            projection: Projection {
+                scale: DEFAULT_SCALE,
+                origin: DVec2::new(width as f64 / 2.0, height as f64 / 2.0),
+                y_flip: true,
+            },
+
+            timestep: FixedTimestep::default(),
+            velocity_iterations: DEFAULT_VELOCITY_ITERATIONS,
+            position_iterations: DEFAULT_POSITION_ITERATIONS,
+
+            ground: None,
+
+            contacts: Rc::default(),
+            events: Rc::default(),
+            solve_events: Rc::default(),
+        };
+        world.install_listener(None);
+
+        world
+    }
+
+    /// Sets the listener for contact callbacks
+    ///
+    /// Replaces `b2::World::set_contact_listener`
+    /// so contacts are still recorded for the debug renderer and event queue
+    pub fn set_contact_listener(&mut self, listener: Box<dyn ContactListener<U>>) {
+        self.install_listener(Some(listener));
+    }
+
+    fn install_listener(&mut self, inner: Option<Box<dyn ContactListener<U>>>) {
+        self.world.set_contact_listener(Box::new(RecordingListener {
+            contacts: self.contacts.clone(),
+            events: self.events.clone(),
+            solve_events: self.solve_events.clone(),
+            inner,
+        }));
+    }
+}
+
+impl<U: UserDataTypes> Box2dWorld<U> {
+    /// Sets the number of pixels per world unit
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.projection.scale = scale;
+        self
+    }
+
+    /// Sets the pixel location of the world origin
+    pub fn with_origin(mut self, x: f64, y: f64) -> Self {
+        self.projection.origin = DVec2::new(x, y);
+        self
+    }
+
+    /// Sets whether world Y points up the screen
+    pub fn with_y_flip(mut self, y_flip: bool) -> Self {
+        self.projection.y_flip = y_flip;
+        self
+    }
+
+    pub fn with_timestep(mut self, timestep: FixedTimestep) -> Self {
+        self.timestep = timestep;
+        self
+    }
+
+    /// Sets the constraint solver iterations used each step
+    pub fn with_iterations(mut self, velocity: i32, position: i32) -> Self {
+        self.velocity_iterations = velocity;
+        self.position_iterations = position;
+        self
+    }
+
+    /// Queues `PreSolve` events as well, one per touching contact every step
+    pub fn with_pre_solve_events(self) -> Self {
+        self.solve_events.set(SolveEvents {
+            pre_solve: true,
+            ..self.solve_events.get()
+        });
+        self
+    }
+
+    /// Queues `PostSolve` events as well, for contacts solved with at least this normal impulse
+    ///
+    /// Resting contacts are solved every step,
+    /// a threshold above their impulse keeps just the impacts
+    pub fn with_post_solve_events(self, min_normal_impulse: f32) -> Self {
+        self.solve_events.set(SolveEvents {
+            post_solve: Some(min_normal_impulse),
+            ..self.solve_events.get()
+        });
+        self
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.projection.scale
+    }
+
+    pub fn origin(&self) -> DVec2 {
+        self.projection.origin
+    }
+
+    pub fn y_flip(&self) -> bool {
+        self.projection.y_flip
+    }
+
+    pub fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    pub fn world(&self) -> &b2::World<U> {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut b2::World<U> {
+        &mut self.world
+    }
+
+    /// Gravity in world units
+    pub fn gravity(&self) -> DVec2 {
+        from_b2(self.world.gravity())
+    }
+
+    pub fn set_gravity(&mut self, gravity: DVec2) {
+        self.world.set_gravity(&to_b2(gravity));
+    }
+
+    /// Contact points in world units from the last step
+    pub fn contact_points(&self) -> Vec<b2::Vec2> {
+        self.contacts.borrow().clone()
+    }
+
+    /// Takes the contact events since the last drain, oldest first
+    ///
+    /// Events build up across steps until they're drained,
+    /// up to `MAX_CONTACT_EVENTS`. Only `Begin` and `End` events are queued
+    /// unless `with_pre_solve_events` or `with_post_solve_events` is used
+    pub fn drain_contact_events(&mut self) -> Vec<ContactEvent<U::BodyData>> {
+        self.events.borrow_mut().drain(..).collect()
+    }
+
+    /// Steps the world at the fixed rate, returns the number of steps taken
+    pub fn step(&mut self, dt: f64) -> usize {
+        let steps = self.timestep.advance(dt);
+        if steps > 0 {
+            self.contacts.borrow_mut().clear();
+        }
+
+        for _ in 0..steps {
+            self.world.step(
+                self.timestep.step() as f32,
+                self.velocity_iterations,
+                self.position_iterations,
+            );
+            self.world.clear_forces();
+        }
+
+        steps
+    }
+
+    /* world to pixels */
+
+    pub fn coord_world_to_pixels(&self, wx: f64, wy: f64) -> b2::Vec2 {
+        self.projection.world_to_pixels(wx, wy)
+    }
+
+    pub fn vector_world_to_pixels(&self, v: b2::Vec2) -> b2::Vec2 {
+        self.coord_world_to_pixels(v.x as f64, v.y as f64)
+    }
+
+    pub fn scalar_world_to_pixels(&self, val: f64) -> f64 {
+        val * self.projection.scale
+    }
+
+    /// Body angle as a canvas rotation
+    pub fn angle_world_to_pixels(&self, angle: f64) -> f64 {
+        self.projection.angle(angle)
+    }
+
+    pub fn get_body_pixel_coord(&self, b: &b2::Body) -> b2::Vec2 {
+        let xf = b.transform();
+        self.coord_world_to_pixels(xf.pos.x as f64, xf.pos.y as f64)
+    }
+
+    /* pixels to world */
+
+    pub fn coord_pixels_to_world(&self, px: f64, py: f64) -> b2::Vec2 {
+        self.projection.pixels_to_world(px, py)
+    }
+
+    pub fn vector_pixels_to_world(&self, v: b2::Vec2) -> b2::Vec2 {
+        self.coord_pixels_to_world(v.x as f64, v.y as f64)
+    }
+
+    pub fn scalar_pixels_to_world(&self, val: f64) -> f64 {
+        val / self.projection.scale
+    }
+
+    /// Canvas rotation as a body angle
+    pub fn angle_pixels_to_world(&self, angle: f64) -> f64 {
+        self.projection.angle(angle)
+    }
+}
+
+impl<U: UserDataTypes> Deref for Box2dWorld<U> {
+    type Target = b2::World<U>;
+
+    fn deref(&self) -> &b2::World<U> {
+        &self.world
+    }
+}
+
+impl<U: UserDataTypes> DerefMut for Box2dWorld<U> {
+    fn deref_mut(&mut self) -> &mut b2::World<U> {
+        &mut self.world
+    }
+}
+
+/* contact events */
+
+/// Which contact callback an event came from
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ContactEventKind {
+    /// The fixtures started touching
+    Begin,
+    /// The fixtures stopped touching
+    End,
+    /// The contact is about to be solved, sent every step while touching
+    ///
+    /// Only queued after `Box2dWorld::with_pre_solve_events`
+    PreSolve,
+    /// The contact was solved with the largest impulses over its points
+    ///
+    /// Only queued after `Box2dWorld::with_post_solve_events`
+    PostSolve {
+        normal_impulse: f32,
+        tangent_impulse: f32,
+    },
+}
+
+/// Contact between two fixtures, queued during the step for the sketch to handle
+///
+/// `data_a` and `data_b` are the body user data, used to map bodies back to sketch objects
+#[derive(Debug, Clone)]
+pub struct ContactEvent<D> {
+    pub kind: ContactEventKind,
+
+    pub body_a: b2::BodyHandle,
+    pub fixture_a: b2::FixtureHandle,
+    pub data_a: D,
+
+    pub body_b: b2::BodyHandle,
+    pub fixture_b: b2::FixtureHandle,
+    pub data_b: D,
+
+    /// Contact points in world units, empty if the fixtures aren't touching
+    pub points: Vec<b2::Vec2>,
+    /// Normal from A to B in world units
+    pub normal: b2::Vec2,
+}
+
+impl<D> ContactEvent<D> {
+    pub fn involves(&self, body: b2::BodyHandle) -> bool {
+        self.body_a == body || self.body_b == body
+    }
+
+    /// The body on the other side of the contact along with its user data
+    pub fn other(&self, body: b2::BodyHandle) -> Option<(b2::BodyHandle, &D)> {
+        if self.body_a == body {
+            Some((self.body_b, &self.data_b))
+        } else if self.body_b == body {
+            Some((self.body_a, &self.data_a))
+        } else {
+            None
+        }
+    }
+}
+
+// records contact points and events, and forwards every callback to the user listener
+struct RecordingListener<U: UserDataTypes> {
+    contacts: Rc<RefCell<Vec<b2::Vec2>>>,
+    events: Rc<RefCell<VecDeque<ContactEvent<U::BodyData>>>>,
+    solve_events: Rc<Cell<SolveEvents>>,
+    inner: Option<Box<dyn ContactListener<U>>>,
+}
+
+impl<U> RecordingListener<U>
+where
+    U: UserDataTypes,
+    U::BodyData: Clone,
+{
+    // contact points in world units, empty if the fixtures aren't touching
+    fn points(access: &ContactAccess<U>) -> Vec<b2::Vec2> {
+        if !access.contact.is_touching() {
+            return vec![];
+        }
+
+        let count = access.contact.manifold().point_count.max(0) as usize;
+        let world_manifold = access.contact.world_manifold();
+
+        world_manifold.points.iter().take(count).copied().collect()
+    }
+
+    fn record(&mut self, kind: ContactEventKind, access: &ContactAccess<U>) {
+        let (body_a, fixture_a) = access.contact.fixture_a();
+        let (body_b, fixture_b) = access.contact.fixture_b();
+
+        let mut events = self.events.borrow_mut();
+        if events.len() == MAX_CONTACT_EVENTS {
+            events.pop_front();
+        }
+
+        events.push_back(ContactEvent {
+            kind,
+
+            body_a,
+            fixture_a,
+            data_a: access.body_a.user_data().clone(),
+
+            body_b,
+            fixture_b,
+            data_b: access.body_b.user_data().clone(),
+
+            points: Self::points(access),
+            normal: access.contact.world_manifold().normal,
+        });
+    }
+}
+
+impl<U> ContactListener<U> for RecordingListener<U>
+where
+    U: UserDataTypes,
+    U::BodyData: Clone,
+{
+    fn begin_contact(&mut self, access: ContactAccess<U>) {
+        self.record(ContactEventKind::Begin, &access);
+
+        if let Some(inner) = self.inner.as_mut() {
+            inner.begin_contact(access);
+        }
+    }
+
+    fn end_contact(&mut self, access: ContactAccess<U>) {
+        self.record(ContactEventKind::End, &access);
+
+        if let Some(inner) = self.inner.as_mut() {
+            inner.end_contact(access);
+        }
+    }
+
+    fn pre_solve(&mut self, access: ContactAccess<U>, manifold: &b2::Manifold) {
+        self.contacts.borrow_mut().extend(Self::points(&access));
+
+        if self.solve_events.get().pre_solve {
+            self.record(ContactEventKind::PreSolve, &access);
+        }
+
+        if let Some(inner) = self.inner.as_mut() {
+            inner.pre_solve(access, manifold);
+        }
+    }
+
+    fn post_solve(&mut self, access: ContactAccess<U>, impulse: &b2::ContactImpulse) {
+        let count = impulse.count.max(0) as usize;
+        let max = |impulses: &[f32]| {
+            impulses
+                .iter()
+                .take(count)
+                .fold(0.0f32, |a, &b| a.max(b.abs()))
+        };
+
+        let normal_impulse = max(&impulse.normal_impulses);
+        let queued = match self.solve_events.get().post_solve {
+            Some(min_normal_impulse) => normal_impulse >= min_normal_impulse,
+            None => false,
+        };
+
+        if queued {
+            let kind = ContactEventKind::PostSolve {
+                normal_impulse,
+                tangent_impulse: max(&impulse.tangent_impulses),
+            };
+            self.record(kind, &access);
+        }
+
+        if let Some(inner) = self.inner.as_mut() {
+            inner.post_solve(access, impulse);
+        }
+    }
+}
+
+pub fn to_b2(v: DVec2) -> b2::Vec2 {
+    b2::Vec2 {
+        x: v.x as f32,
+        y: v.y as f32,
+    }
+}
+
+pub fn from_b2(v: b2::Vec2) -> DVec2 {
+    DVec2::new(v.x as f64, v.y as f64)
+}
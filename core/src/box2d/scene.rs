@@ -0,0 +1,942 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use glam::DVec2;
+use serde::Deserialize;
+use wrapped2d::b2;
+use wrapped2d::user_data::UserDataTypes;
+
+use super::Box2dWorld;
+
+// https://box2d.org/documentation/md__d_1__git_hub_box2d_docs_collision.html#autotoc_md37
+const MAX_POLYGON_VERTICES: usize = 8;
+
+// b2_linearSlop, Box2D asserts that vertices are further apart than this in world units
+const LINEAR_SLOP: f64 = 0.005;
+
+/// Joints can be pinned to the world with this body name, see `Box2dWorld::ground_body`
+pub const GROUND: &str = "ground";
+
+/* scene description */
+
+// positions, sizes and vertices are in pixels with Y down
+// and angles are canvas rotations, the same as when drawing,
+// so scenes don't depend on the world scale or Y-flip
+
+/// Bodies and joints loaded from a RON file
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    /// Replaces the world gravity if set, in world units
+    pub gravity: Option<(f64, f64)>,
+
+    pub bodies: Vec<BodyDesc>,
+    pub joints: Vec<JointDesc>,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum BodyKind {
+    Static,
+    Kinematic,
+    #[default]
+    Dynamic,
+}
+
+impl From<BodyKind> for b2::BodyType {
+    fn from(kind: BodyKind) -> Self {
+        match kind {
+            BodyKind::Static => Self::Static,
+            BodyKind::Kinematic => Self::Kinematic,
+            BodyKind::Dynamic => Self::Dynamic,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct BodyDesc {
+    /// Used by joints and `SceneHandles::body`
+    pub name: Option<String>,
+    pub kind: BodyKind,
+
+    pub position: (f64, f64),
+    pub angle: f64,
+
+    pub linear_damping: f64,
+    pub angular_damping: f64,
+    pub fixed_rotation: bool,
+    pub bullet: bool,
+
+    pub fixtures: Vec<FixtureDesc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FixtureDesc {
+    pub shape: ShapeDesc,
+
+    #[serde(default)]
+    pub material: Material,
+    #[serde(default)]
+    pub sensor: bool,
+}
+
+/// Fixture shape relative to its body
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum ShapeDesc {
+    Box {
+        width: f64,
+        height: f64,
+        #[serde(default)]
+        center: (f64, f64),
+    },
+    Circle {
+        radius: f64,
+        #[serde(default)]
+        center: (f64, f64),
+    },
+    /// Convex polygon with up to 8 vertices
+    Polygon { vertices: Vec<(f64, f64)> },
+    /// One sided edges, usually for static terrain
+    Chain {
+        vertices: Vec<(f64, f64)>,
+        #[serde(default)]
+        looped: bool,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Material {
+    pub density: f64,
+    pub friction: f64,
+    pub restitution: f64,
+}
+
+impl Default for Material {
+    // Box2D defaults except for density, so dynamic bodies have mass
+    fn default() -> Self {
+        Self {
+            density: 1.0,
+            friction: 0.2,
+            restitution: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct JointDesc {
+    /// Used by `SceneHandles::joint`
+    #[serde(default)]
+    pub name: Option<String>,
+
+    pub body_a: String,
+    pub body_b: String,
+    #[serde(default)]
+    pub collide_connected: bool,
+
+    pub kind: JointKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum JointKind {
+    Revolute {
+        anchor: (f64, f64),
+        #[serde(default)]
+        motor: Option<Motor>,
+        /// Lower and upper angle
+        #[serde(default)]
+        limits: Option<(f64, f64)>,
+    },
+    Prismatic {
+        anchor: (f64, f64),
+        axis: (f64, f64),
+        #[serde(default)]
+        motor: Option<LinearMotor>,
+        /// Lower and upper translation
+        #[serde(default)]
+        limits: Option<(f64, f64)>,
+    },
+    Distance {
+        anchor_a: (f64, f64),
+        anchor_b: (f64, f64),
+        /// Defaults to the distance between the anchors
+        #[serde(default)]
+        length: Option<f64>,
+        #[serde(default)]
+        frequency: f64,
+        #[serde(default)]
+        damping_ratio: f64,
+    },
+    Weld {
+        anchor: (f64, f64),
+        #[serde(default)]
+        frequency: f64,
+        #[serde(default)]
+        damping_ratio: f64,
+    },
+    Rope {
+        anchor_a: (f64, f64),
+        anchor_b: (f64, f64),
+        max_length: f64,
+    },
+}
+
+/// Revolute motor, speed is a canvas rotation per second
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+pub struct Motor {
+    pub speed: f64,
+    pub max_torque: f64,
+}
+
+/// Prismatic motor, speed is in pixels per second
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+pub struct LinearMotor {
+    pub speed: f64,
+    pub max_force: f64,
+}
+
+/* errors */
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Parse(ron::Error),
+    UnknownBody(String),
+    DuplicateName(String),
+    InvalidBody(String),
+    InvalidShape(String),
+    InvalidJoint(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Parse(err) => write!(f, "{}", err),
+            Self::UnknownBody(name) => write!(f, "unknown body '{}'", name),
+            Self::DuplicateName(name) => write!(f, "name '{}' is used more than once", name),
+            Self::InvalidBody(reason) => write!(f, "invalid body: {}", reason),
+            Self::InvalidShape(reason) => write!(f, "invalid shape: {}", reason),
+            Self::InvalidJoint(reason) => write!(f, "invalid joint: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::Error> for SceneError {
+    fn from(err: ron::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+/* loading */
+
+/// Handles to everything a scene created
+#[derive(Debug, Default, Clone)]
+pub struct SceneHandles {
+    /// Bodies in the order they appear in the scene
+    pub bodies: Vec<b2::BodyHandle>,
+    /// Joints in the order they appear in the scene
+    pub joints: Vec<b2::JointHandle>,
+
+    body_names: HashMap<String, b2::BodyHandle>,
+    joint_names: HashMap<String, b2::JointHandle>,
+}
+
+impl SceneHandles {
+    pub fn body(&self, name: &str) -> Option<b2::BodyHandle> {
+        self.body_names.get(name).copied()
+    }
+
+    pub fn joint(&self, name: &str) -> Option<b2::JointHandle> {
+        self.joint_names.get(name).copied()
+    }
+}
+
+impl Scene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let source = fs::read_to_string(path)?;
+        Self::from_ron(&source)
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self, SceneError> {
+        Ok(ron::de::from_str(source)?)
+    }
+
+    /// Checks everything Box2D would otherwise abort on, `build` does this before creating anything
+    ///
+    /// `scale` is the pixels per world unit the scene is built at, see `Box2dWorld::scale`
+    pub fn validate(&self, scale: f64) -> Result<(), SceneError> {
+        if let Some((x, y)) = self.gravity {
+            if !finite([x, y]) {
+                return Err(SceneError::InvalidBody("gravity must be finite".to_owned()));
+            }
+        }
+
+        unique_names(self.bodies.iter().filter_map(|body| body.name.as_ref()))?;
+        unique_names(self.joints.iter().filter_map(|joint| joint.name.as_ref()))?;
+
+        for body in &self.bodies {
+            let values = [
+                body.position.0,
+                body.position.1,
+                body.angle,
+                body.linear_damping,
+                body.angular_damping,
+            ];
+            if !finite(values) {
+                return Err(SceneError::InvalidBody(format!(
+                    "'{}' has a value that isn't finite",
+                    body.name.as_deref().unwrap_or("unnamed")
+                )));
+            }
+
+            for fixture in &body.fixtures {
+                let material = fixture.material;
+                if !finite([material.density, material.friction, material.restitution]) {
+                    return Err(SceneError::InvalidShape(
+                        "material values must be finite".to_owned(),
+                    ));
+                }
+
+                fixture.shape.validate(scale)?;
+            }
+        }
+
+        for joint in &self.joints {
+            if !finite(joint.kind.values()) {
+                return Err(SceneError::InvalidJoint(format!(
+                    "'{}' to '{}' has a value that isn't finite",
+                    joint.body_a, joint.body_b
+                )));
+            }
+
+            for name in [&joint.body_a, &joint.body_b] {
+                let known = name == GROUND
+                    || self
+                        .bodies
+                        .iter()
+                        .any(|body| body.name.as_ref() == Some(name));
+                if !known {
+                    return Err(SceneError::UnknownBody(name.clone()));
+                }
+            }
+
+            if joint.body_a == joint.body_b {
+                return Err(SceneError::InvalidJoint(format!(
+                    "'{}' is joined to itself",
+                    joint.body_a
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the scene's bodies and joints in the world
+    ///
+    /// Nothing is created if the scene is invalid
+    pub fn build<U>(&self, world: &mut Box2dWorld<U>) -> Result<SceneHandles, SceneError>
+    where
+        U: UserDataTypes,
+        U::BodyData: Default,
+        U::JointData: Default,
+        U::FixtureData: Default,
+    {
+        self.validate(world.scale())?;
+
+        if let Some((x, y)) = self.gravity {
+            world.set_gravity(DVec2::new(x, y));
+        }
+
+        let mut handles = SceneHandles::default();
+
+        for desc in &self.bodies {
+            let body = build_body(world, desc);
+
+            handles.bodies.push(body);
+            if let Some(name) = &desc.name {
+                handles.body_names.insert(name.clone(), body);
+            }
+        }
+
+        for desc in &self.joints {
+            let joint = build_joint(world, &handles, desc);
+
+            handles.joints.push(joint);
+            if let Some(name) = &desc.name {
+                handles.joint_names.insert(name.clone(), joint);
+            }
+        }
+
+        Ok(handles)
+    }
+}
+
+impl ShapeDesc {
+    fn validate(&self, scale: f64) -> Result<(), SceneError> {
+        let invalid = |reason: &str| Err(SceneError::InvalidShape(reason.to_owned()));
+
+        // Box2D's tolerances are in world units
+        let slop = LINEAR_SLOP * scale;
+
+        match self {
+            Self::Box {
+                width,
+                height,
+                center,
+            } => {
+                if !finite([*width, *height, center.0, center.1]) {
+                    invalid("box values must be finite")
+                } else if *width <= slop || *height <= slop {
+                    invalid("box size must be positive")
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Circle { radius, center } => {
+                if !finite([*radius, center.0, center.1]) {
+                    invalid("circle values must be finite")
+                } else if *radius <= 0.0 {
+                    invalid("circle radius must be positive")
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Polygon { vertices } => {
+                let points = points(vertices);
+
+                if points.len() < 3 || points.len() > MAX_POLYGON_VERTICES {
+                    invalid("polygons need 3 to 8 vertices")
+                } else if !finite(vertices.iter().flat_map(|(x, y)| [*x, *y])) {
+                    invalid("polygon vertices must be finite")
+                } else if points
+                    .iter()
+                    .enumerate()
+                    .any(|(i, a)| points[i + 1..].iter().any(|b| a.distance(*b) <= slop))
+                {
+                    invalid("polygon vertices are too close together")
+                } else if convex_hull(&points).len() != points.len() {
+                    // Box2D would quietly use the hull, or abort if there isn't one
+                    invalid("polygons must be convex without collinear vertices")
+                } else if area(&convex_hull(&points)) <= slop * slop {
+                    invalid("polygon is too thin")
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Chain { vertices, looped } => {
+                let points = points(vertices);
+
+                // loops close back to their first vertex
+                let mut edges: Vec<_> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+                if *looped && points.len() > 1 {
+                    edges.push((points[points.len() - 1], points[0]));
+                }
+
+                if points.len() < 2 {
+                    invalid("chains need at least 2 vertices")
+                } else if *looped && points.len() < 3 {
+                    invalid("looped chains need at least 3 vertices")
+                } else if !finite(vertices.iter().flat_map(|(x, y)| [*x, *y])) {
+                    invalid("chain vertices must be finite")
+                } else if edges.iter().any(|(a, b)| a.distance(*b) <= slop) {
+                    invalid("chain vertices are too close together")
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+impl JointKind {
+    // every number in the joint, for checking they're finite
+    fn values(&self) -> Vec<f64> {
+        match self {
+            Self::Revolute {
+                anchor,
+                motor,
+                limits,
+            } => {
+                let mut values = vec![anchor.0, anchor.1];
+                values.extend(
+                    motor
+                        .iter()
+                        .flat_map(|motor| [motor.speed, motor.max_torque]),
+                );
+                values.extend(limits.iter().flat_map(|(lower, upper)| [*lower, *upper]));
+                values
+            }
+            Self::Prismatic {
+                anchor,
+                axis,
+                motor,
+                limits,
+            } => {
+                let mut values = vec![anchor.0, anchor.1, axis.0, axis.1];
+                values.extend(
+                    motor
+                        .iter()
+                        .flat_map(|motor| [motor.speed, motor.max_force]),
+                );
+                values.extend(limits.iter().flat_map(|(lower, upper)| [*lower, *upper]));
+                values
+            }
+            Self::Distance {
+                anchor_a,
+                anchor_b,
+                length,
+                frequency,
+                damping_ratio,
+            } => {
+                let mut values = vec![
+                    anchor_a.0,
+                    anchor_a.1,
+                    anchor_b.0,
+                    anchor_b.1,
+                    *frequency,
+                    *damping_ratio,
+                ];
+                values.extend(length);
+                values
+            }
+            Self::Weld {
+                anchor,
+                frequency,
+                damping_ratio,
+            } => vec![anchor.0, anchor.1, *frequency, *damping_ratio],
+            Self::Rope {
+                anchor_a,
+                anchor_b,
+                max_length,
+            } => vec![anchor_a.0, anchor_a.1, anchor_b.0, anchor_b.1, *max_length],
+        }
+    }
+}
+
+fn finite(values: impl IntoIterator<Item = f64>) -> bool {
+    values.into_iter().all(f64::is_finite)
+}
+
+fn unique_names<'a>(names: impl Iterator<Item = &'a String>) -> Result<(), SceneError> {
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            return Err(SceneError::DuplicateName(name.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+fn points(vertices: &[(f64, f64)]) -> Vec<DVec2> {
+    vertices.iter().map(|(x, y)| DVec2::new(*x, *y)).collect()
+}
+
+// shoelace area of a simple polygon
+fn area(points: &[DVec2]) -> f64 {
+    let twice: f64 = (0..points.len())
+        .map(|i| points[i].perp_dot(points[(i + 1) % points.len()]))
+        .sum();
+
+    twice.abs() / 2.0
+}
+
+// monotone chain hull that drops collinear points, in either winding
+fn convex_hull(points: &[DVec2]) -> Vec<DVec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+
+    let turns_left = |hull: &[DVec2], p: DVec2| {
+        let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+        (b - a).perp_dot(p - b) > 0.0
+    };
+
+    let mut hull: Vec<DVec2> = vec![];
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2 && !turns_left(&hull[start..], p) {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // the last point of each half is the first of the other
+        hull.pop();
+    }
+
+    hull
+}
+
+fn build_body<U>(world: &mut Box2dWorld<U>, desc: &BodyDesc) -> b2::BodyHandle
+where
+    U: UserDataTypes,
+    U::BodyData: Default,
+    U::FixtureData: Default,
+{
+    let mut bd = b2::BodyDef::new();
+    bd.body_type = desc.kind.into();
+    bd.position = world.coord_pixels_to_world(desc.position.0, desc.position.1);
+    bd.angle = world.angle_pixels_to_world(desc.angle) as f32;
+    bd.linear_damping = desc.linear_damping as f32;
+    bd.angular_damping = desc.angular_damping as f32;
+    bd.fixed_rotation = desc.fixed_rotation;
+    bd.bullet = desc.bullet;
+    let body = world.create_body(&bd);
+
+    for fixture in &desc.fixtures {
+        let mut fd = b2::FixtureDef::new();
+        fd.density = fixture.material.density as f32;
+        fd.friction = fixture.material.friction as f32;
+        fd.restitution = fixture.material.restitution as f32;
+        fd.is_sensor = fixture.sensor;
+
+        let mut body = world.body_mut(body);
+        match &fixture.shape {
+            ShapeDesc::Box {
+                width,
+                height,
+                center,
+            } => {
+                let shape = b2::PolygonShape::new_oriented_box(
+                    world.scalar_pixels_to_world(width / 2.0) as f32,
+                    world.scalar_pixels_to_world(height / 2.0) as f32,
+                    &offset(world, *center),
+                    0.0,
+                );
+                body.create_fixture(&shape, &mut fd);
+            }
+            ShapeDesc::Circle { radius, center } => {
+                let shape = b2::CircleShape::new_with(
+                    offset(world, *center),
+                    world.scalar_pixels_to_world(*radius) as f32,
+                );
+                body.create_fixture(&shape, &mut fd);
+            }
+            ShapeDesc::Polygon { vertices } => {
+                let shape = b2::PolygonShape::new_with(&offsets(world, vertices));
+                body.create_fixture(&shape, &mut fd);
+            }
+            ShapeDesc::Chain { vertices, looped } => {
+                let vertices = offsets(world, vertices);
+                let shape = if *looped {
+                    b2::ChainShape::new_loop(&vertices)
+                } else {
+                    b2::ChainShape::new_chain(&vertices)
+                };
+                body.create_fixture(&shape, &mut fd);
+            }
+        }
+    }
+
+    body
+}
+
+fn build_joint<U>(
+    world: &mut Box2dWorld<U>,
+    handles: &SceneHandles,
+    desc: &JointDesc,
+) -> b2::JointHandle
+where
+    U: UserDataTypes,
+    U::BodyData: Default,
+    U::JointData: Default,
+{
+    // validated before building
+    let mut body = |name: &str| match handles.body(name) {
+        Some(body) => body,
+        None => world.ground_body(),
+    };
+    let a = body(&desc.body_a);
+    let b = body(&desc.body_b);
+
+    let point = |(x, y): (f64, f64)| DVec2::new(x, y);
+
+    match &desc.kind {
+        JointKind::Revolute {
+            anchor,
+            motor,
+            limits,
+        } => {
+            let mut def = world.revolute_joint_def(a, b, point(*anchor));
+            def.collide_connected = desc.collide_connected;
+            if let Some(motor) = motor {
+                def.enable_motor = true;
+                def.motor_speed = world.angle_pixels_to_world(motor.speed) as f32;
+                def.max_motor_torque = motor.max_torque as f32;
+            }
+            if let Some((lower, upper)) = limits {
+                // flipping Y swaps which limit is lower
+                let (lower, upper) = if world.y_flip() {
+                    (-upper, -lower)
+                } else {
+                    (*lower, *upper)
+                };
+                def.enable_limit = true;
+                def.lower_angle = lower as f32;
+                def.upper_angle = upper as f32;
+            }
+            world.create_joint(&def)
+        }
+        JointKind::Prismatic {
+            anchor,
+            axis,
+            motor,
+            limits,
+        } => {
+            let mut def = world.prismatic_joint_def(a, b, point(*anchor), point(*axis));
+            def.collide_connected = desc.collide_connected;
+            if let Some(motor) = motor {
+                def.enable_motor = true;
+                def.motor_speed = world.scalar_pixels_to_world(motor.speed) as f32;
+                def.max_motor_force = motor.max_force as f32;
+            }
+            if let Some((lower, upper)) = limits {
+                def.enable_limit = true;
+                def.lower_translation = world.scalar_pixels_to_world(*lower) as f32;
+                def.upper_translation = world.scalar_pixels_to_world(*upper) as f32;
+            }
+            world.create_joint(&def)
+        }
+        JointKind::Distance {
+            anchor_a,
+            anchor_b,
+            length,
+            frequency,
+            damping_ratio,
+        } => {
+            let mut def = world.distance_joint_def(a, b, point(*anchor_a), point(*anchor_b));
+            def.collide_connected = desc.collide_connected;
+            if let Some(length) = length {
+                def.length = world.scalar_pixels_to_world(*length) as f32;
+            }
+            def.frequency = *frequency as f32;
+            def.damping_ratio = *damping_ratio as f32;
+            world.create_joint(&def)
+        }
+        JointKind::Weld {
+            anchor,
+            frequency,
+            damping_ratio,
+        } => {
+            let mut def = world.weld_joint_def(a, b, point(*anchor));
+            def.collide_connected = desc.collide_connected;
+            def.frequency = *frequency as f32;
+            def.damping_ratio = *damping_ratio as f32;
+            world.create_joint(&def)
+        }
+        JointKind::Rope {
+            anchor_a,
+            anchor_b,
+            max_length,
+        } => {
+            let mut def =
+                world.rope_joint_def(a, b, point(*anchor_a), point(*anchor_b), *max_length);
+            def.collide_connected = desc.collide_connected;
+            world.create_joint(&def)
+        }
+    }
+}
+
+fn offset<U: UserDataTypes>(world: &Box2dWorld<U>, (x, y): (f64, f64)) -> b2::Vec2 {
+    world.projection.offset_to_world(x, y)
+}
+
+fn offsets<U: UserDataTypes>(world: &Box2dWorld<U>, vertices: &[(f64, f64)]) -> Vec<b2::Vec2> {
+    vertices.iter().map(|v| offset(world, *v)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALE: f64 = 10.0;
+
+    fn body(name: &str, shape: ShapeDesc) -> BodyDesc {
+        BodyDesc {
+            name: Some(name.to_owned()),
+            fixtures: vec![FixtureDesc {
+                shape,
+                material: Material::default(),
+                sensor: false,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn joint(body_a: &str, body_b: &str) -> JointDesc {
+        JointDesc {
+            name: None,
+            body_a: body_a.to_owned(),
+            body_b: body_b.to_owned(),
+            collide_connected: false,
+            kind: JointKind::Weld {
+                anchor: (0.0, 0.0),
+                frequency: 0.0,
+                damping_ratio: 0.0,
+            },
+        }
+    }
+
+    fn square() -> ShapeDesc {
+        ShapeDesc::Polygon {
+            vertices: vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)],
+        }
+    }
+
+    fn validate_shape(shape: ShapeDesc) -> Result<(), SceneError> {
+        shape.validate(SCALE)
+    }
+
+    #[test]
+    fn accepts_valid_scene() {
+        let scene = Scene {
+            bodies: vec![body("a", square()), body("b", square())],
+            joints: vec![joint("a", "b"), joint("a", GROUND)],
+            ..Default::default()
+        };
+
+        assert!(scene.validate(SCALE).is_ok());
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let scene = Scene {
+            bodies: vec![body("a", square()), body("a", square())],
+            ..Default::default()
+        };
+        assert!(matches!(
+            scene.validate(SCALE),
+            Err(SceneError::DuplicateName(name)) if name == "a"
+        ));
+
+        let mut first = joint("a", "b");
+        first.name = Some("hinge".to_owned());
+        let second = first.clone();
+        let scene = Scene {
+            bodies: vec![body("a", square()), body("b", square())],
+            joints: vec![first, second],
+            ..Default::default()
+        };
+        assert!(matches!(
+            scene.validate(SCALE),
+            Err(SceneError::DuplicateName(name)) if name == "hinge"
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_joints() {
+        let scene = Scene {
+            bodies: vec![body("a", square())],
+            joints: vec![joint("a", "a")],
+            ..Default::default()
+        };
+        assert!(matches!(
+            scene.validate(SCALE),
+            Err(SceneError::InvalidJoint(_))
+        ));
+
+        let scene = Scene {
+            bodies: vec![body("a", square())],
+            joints: vec![joint("a", "missing")],
+            ..Default::default()
+        };
+        assert!(matches!(
+            scene.validate(SCALE),
+            Err(SceneError::UnknownBody(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_degenerate_polygons() {
+        let polygon = |vertices: &[(f64, f64)]| ShapeDesc::Polygon {
+            vertices: vertices.to_vec(),
+        };
+
+        // duplicate and near-coincident vertices
+        assert!(
+            validate_shape(polygon(&[(0.0, 0.0), (0.0, 0.0), (10.0, 0.0), (0.0, 10.0)])).is_err()
+        );
+        assert!(validate_shape(polygon(&[
+            (0.0, 0.0),
+            (0.01, 0.0),
+            (10.0, 0.0),
+            (0.0, 10.0)
+        ]))
+        .is_err());
+
+        // collinear
+        assert!(validate_shape(polygon(&[(0.0, 0.0), (5.0, 0.0), (10.0, 0.0)])).is_err());
+        assert!(
+            validate_shape(polygon(&[(0.0, 0.0), (5.0, 0.0), (10.0, 0.0), (0.0, 10.0)])).is_err()
+        );
+
+        // non-convex
+        assert!(validate_shape(polygon(&[
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (5.0, 2.0),
+            (10.0, 10.0),
+            (0.0, 10.0)
+        ]))
+        .is_err());
+
+        // either winding is fine
+        assert!(validate_shape(polygon(&[(0.0, 0.0), (0.0, 10.0), (10.0, 0.0)])).is_ok());
+        assert!(validate_shape(polygon(&[(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)])).is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_chains() {
+        let chain = |vertices: &[(f64, f64)], looped| ShapeDesc::Chain {
+            vertices: vertices.to_vec(),
+            looped,
+        };
+
+        assert!(validate_shape(chain(&[(0.0, 0.0), (10.0, 0.0)], false)).is_ok());
+        assert!(validate_shape(chain(&[(0.0, 0.0), (10.0, 0.0)], true)).is_err());
+        assert!(validate_shape(chain(&[(0.0, 0.0), (10.0, 0.0), (10.0, 0.0)], false)).is_err());
+
+        // the closing edge counts too
+        assert!(validate_shape(chain(
+            &[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 0.0)],
+            true
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_non_finite_values() {
+        assert!(validate_shape(ShapeDesc::Polygon {
+            vertices: vec![(0.0, 0.0), (f64::NAN, 0.0), (0.0, 10.0)],
+        })
+        .is_err());
+        assert!(validate_shape(ShapeDesc::Circle {
+            radius: f64::INFINITY,
+            center: (0.0, 0.0),
+        })
+        .is_err());
+
+        let mut bad = body("a", square());
+        bad.position.0 = f64::NAN;
+        let scene = Scene {
+            bodies: vec![bad],
+            ..Default::default()
+        };
+        assert!(matches!(
+            scene.validate(SCALE),
+            Err(SceneError::InvalidBody(_))
+        ));
+    }
+}
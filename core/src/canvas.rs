@@ -0,0 +1,573 @@
+use std::cell::RefCell;
+
+use glam::{DMat3, DVec2};
+use processing::errors::ProcessingErr;
+use processing::shapes::ellipse::Ellipse;
+use processing::shapes::line::Line;
+use processing::shapes::point::Point;
+use processing::shapes::quad::Quad;
+use processing::shapes::rect::Rect;
+use processing::shapes::triangle::Triangle;
+use processing::Screen;
+
+use crate::pixels::{create_texture, read_screen, PixelBuffer};
+use crate::shapes::{triangulate, RectMode};
+use crate::text::{glyph_runs, TextAlign, TextBaseline, TextStyle};
+use crate::{device_to_screen, device_to_screen_size, BlendMode};
+
+/// Drawing state shared by every primitive
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DrawState {
+    pub fill: bool,
+    pub stroke: bool,
+    pub stroke_weight: f64,
+
+    /// Kept while fill or stroke are off so they come back the same color
+    pub fill_color: [f32; 4],
+    pub stroke_color: [f32; 4],
+
+    pub blend_mode: BlendMode,
+
+    /// How `rect` arguments are interpreted
+    pub rect_mode: RectMode,
+
+    /// How `ellipse` arguments are interpreted
+    pub ellipse_mode: RectMode,
+}
+
+impl Default for DrawState {
+    fn default() -> Self {
+        Self {
+            fill: true,
+            stroke: true,
+            stroke_weight: 1.0,
+
+            fill_color: [255.0, 255.0, 255.0, 255.0],
+            stroke_color: [0.0, 0.0, 0.0, 255.0],
+
+            blend_mode: BlendMode::Blend,
+
+            rect_mode: RectMode::Corner,
+            ellipse_mode: RectMode::Center,
+        }
+    }
+}
+
+/// Drawing surface the core helpers render to
+///
+/// Colors are in the 0-255 range and coordinates
+/// are in pixels with the origin in the top left
+pub trait Canvas {
+    /// Backend specific texture type drawn by `image`
+    type Image;
+
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+
+    /* color */
+
+    fn background(&mut self, r: f32, g: f32, b: f32, a: f32);
+
+    fn fill(&mut self, r: f32, g: f32, b: f32, a: f32);
+    fn fill_off(&mut self);
+
+    fn stroke(&mut self, r: f32, g: f32, b: f32, a: f32);
+    fn stroke_off(&mut self);
+    fn stroke_weight(&mut self, weight: f64);
+
+    /* draw state */
+
+    fn draw_state(&self) -> DrawState;
+
+    /// Puts back a state returned by `draw_state`
+    fn set_draw_state(&mut self, state: DrawState) {
+        let [r, g, b, a] = state.fill_color;
+        self.fill(r, g, b, a);
+        if !state.fill {
+            self.fill_off();
+        }
+
+        let [r, g, b, a] = state.stroke_color;
+        self.stroke(r, g, b, a);
+        if !state.stroke {
+            self.stroke_off();
+        }
+
+        self.stroke_weight(state.stroke_weight);
+        self.blend_mode(state.blend_mode);
+        self.rect_mode(state.rect_mode);
+        self.ellipse_mode(state.ellipse_mode);
+    }
+
+    fn blend_mode(&mut self, mode: BlendMode);
+    fn rect_mode(&mut self, mode: RectMode);
+    fn ellipse_mode(&mut self, mode: RectMode);
+
+    /* transform */
+
+    fn push_matrix(&mut self);
+    fn pop_matrix(&mut self);
+    fn reset_matrix(&mut self);
+
+    /// Current transform from local coordinates to pixels
+    fn matrix(&self) -> DMat3;
+
+    /// Multiplies the current transform by the matrix
+    fn apply_matrix(&mut self, matrix: DMat3);
+
+    fn translate(&mut self, x: f64, y: f64) {
+        self.apply_matrix(DMat3::from_translation(DVec2::new(x, y)));
+    }
+
+    fn rotate(&mut self, angle: f64) {
+        self.apply_matrix(DMat3::from_angle(angle));
+    }
+
+    fn scale(&mut self, sx: f64, sy: f64) {
+        self.apply_matrix(DMat3::from_scale(DVec2::new(sx, sy)));
+    }
+
+    /// Shears along the x axis by the angle
+    fn shear_x(&mut self, angle: f64) {
+        self.apply_matrix(DMat3::from_cols_array(&[
+            1.0,
+            0.0,
+            0.0,
+            angle.tan(),
+            1.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ]));
+    }
+
+    /// Shears along the y axis by the angle
+    fn shear_y(&mut self, angle: f64) {
+        self.apply_matrix(DMat3::from_cols_array(&[
+            1.0,
+            angle.tan(),
+            0.0,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ]));
+    }
+
+    /* shapes */
+
+    fn point(&mut self, x: f64, y: f64) -> Result<(), ProcessingErr>;
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> Result<(), ProcessingErr>;
+
+    fn triangle(
+        &mut self,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x3: f64,
+        y3: f64,
+    ) -> Result<(), ProcessingErr>;
+
+    /// Rect with the arguments interpreted by the current rect mode
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr>;
+
+    /// Ellipse with the arguments interpreted by the current ellipse mode
+    fn ellipse(&mut self, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr>;
+
+    /// Polygon through the points, filled with the nonzero winding rule
+    ///
+    /// The outline is only stroked back to the first point if the polygon is closed
+    fn polygon(&mut self, points: &[DVec2], close: bool) -> Result<(), ProcessingErr>;
+
+    /* text */
+
+    fn text_size(&mut self, size: f64);
+    fn text_align(&mut self, align: TextAlign, baseline: TextBaseline);
+
+    fn text_style(&self) -> TextStyle;
+
+    /// Puts back a style returned by `text_style`
+    fn set_text_style(&mut self, style: TextStyle) {
+        self.text_size(style.size);
+        self.text_align(style.align, style.baseline);
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64) -> Result<(), ProcessingErr>;
+
+    /* image */
+
+    fn image(&mut self, x: f64, y: f64, image: &Self::Image) -> Result<(), ProcessingErr>;
+
+    /* pixels */
+
+    /// Copies the canvas contents into a pixel buffer
+    fn load_pixels(&mut self) -> Result<PixelBuffer, ProcessingErr>;
+
+    /// Converts a pixel buffer to an image this backend can draw
+    fn create_image(&mut self, pixels: &PixelBuffer) -> Result<Self::Image, ProcessingErr>;
+
+    /// Draws a pixel buffer over the canvas from the top left, ignoring the transform
+    fn update_pixels(&mut self, pixels: &PixelBuffer) -> Result<(), ProcessingErr> {
+        let image = self.create_image(pixels)?;
+
+        self.push_matrix();
+        self.apply_matrix(self.matrix().inverse());
+        let result = self.image(0.0, 0.0, &image);
+        self.pop_matrix();
+
+        result
+    }
+}
+
+/* processing backend */
+
+// state the processing screen doesn't let us read back
+#[derive(Debug)]
+struct ScreenState {
+    draw: DrawState,
+    text_style: TextStyle,
+
+    matrix: DMat3,
+    matrix_stack: Vec<DMat3>,
+}
+
+impl Default for ScreenState {
+    fn default() -> Self {
+        Self {
+            draw: DrawState::default(),
+            text_style: TextStyle::default(),
+
+            matrix: DMat3::IDENTITY,
+            matrix_stack: vec![],
+        }
+    }
+}
+
+thread_local! {
+    static SCREEN_STATE: RefCell<ScreenState> = RefCell::new(ScreenState::default());
+}
+
+impl<'a> Canvas for Screen<'a> {
+    type Image = processing::Texture2d;
+
+    fn width(&self) -> u32 {
+        Screen::width(self)
+    }
+
+    fn height(&self) -> u32 {
+        Screen::height(self)
+    }
+
+    fn background(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        Screen::background(self, r / 255.0, g / 255.0, b / 255.0, a / 255.0);
+    }
+
+    fn fill(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        SCREEN_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.draw.fill = true;
+            state.draw.fill_color = [r, g, b, a];
+        });
+
+        Screen::fill_on(self);
+        Screen::fill(self, &[r / 255.0], &[g / 255.0], &[b / 255.0], &[a / 255.0]);
+    }
+
+    fn fill_off(&mut self) {
+        SCREEN_STATE.with(|state| state.borrow_mut().draw.fill = false);
+
+        Screen::fill_off(self);
+    }
+
+    fn stroke(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        SCREEN_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.draw.stroke = true;
+            state.draw.stroke_color = [r, g, b, a];
+        });
+
+        Screen::stroke_on(self);
+        Screen::stroke(self, &[r / 255.0], &[g / 255.0], &[b / 255.0], &[a / 255.0]);
+    }
+
+    fn stroke_off(&mut self) {
+        SCREEN_STATE.with(|state| state.borrow_mut().draw.stroke = false);
+
+        Screen::stroke_off(self);
+    }
+
+    fn stroke_weight(&mut self, weight: f64) {
+        SCREEN_STATE.with(|state| state.borrow_mut().draw.stroke_weight = weight);
+
+        Screen::stroke_weight(self, weight as f32);
+    }
+
+    fn draw_state(&self) -> DrawState {
+        SCREEN_STATE.with(|state| state.borrow().draw)
+    }
+
+    fn blend_mode(&mut self, mode: BlendMode) {
+        SCREEN_STATE.with(|state| state.borrow_mut().draw.blend_mode = mode);
+
+        Screen::blend_mode(self, &mode.to_string());
+    }
+
+    // processing is left in its default corner and center modes,
+    // the arguments are converted before they reach it
+
+    fn rect_mode(&mut self, mode: RectMode) {
+        SCREEN_STATE.with(|state| state.borrow_mut().draw.rect_mode = mode);
+    }
+
+    fn ellipse_mode(&mut self, mode: RectMode) {
+        SCREEN_STATE.with(|state| state.borrow_mut().draw.ellipse_mode = mode);
+    }
+
+    fn push_matrix(&mut self) {
+        SCREEN_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            let matrix = state.matrix;
+            state.matrix_stack.push(matrix);
+        });
+    }
+
+    fn pop_matrix(&mut self) {
+        let matrix = SCREEN_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.matrix = state.matrix_stack.pop().unwrap_or(DMat3::IDENTITY);
+            state.matrix
+        });
+
+        load_matrix(self, matrix);
+    }
+
+    fn reset_matrix(&mut self) {
+        SCREEN_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.matrix = DMat3::IDENTITY;
+            state.matrix_stack.clear();
+        });
+
+        Screen::reset_matrix(self);
+    }
+
+    fn matrix(&self) -> DMat3 {
+        SCREEN_STATE.with(|state| state.borrow().matrix)
+    }
+
+    fn apply_matrix(&mut self, matrix: DMat3) {
+        let matrix = SCREEN_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.matrix = state.matrix * matrix;
+            state.matrix
+        });
+
+        load_matrix(self, matrix);
+    }
+
+    fn point(&mut self, x: f64, y: f64) -> Result<(), ProcessingErr> {
+        let (x, y) = device_to_screen(self, x, y);
+
+        let point = Point::new(self, &[x], &[y], &[0.0])?;
+        self.draw(&point)
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> Result<(), ProcessingErr> {
+        let (x1, y1) = device_to_screen(self, x1, y1);
+        let (x2, y2) = device_to_screen(self, x2, y2);
+
+        let line = Line::new(self, &[x1], &[y1], &[0.0], &[x2], &[y2], &[0.0])?;
+        self.draw(&line)
+    }
+
+    fn triangle(
+        &mut self,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x3: f64,
+        y3: f64,
+    ) -> Result<(), ProcessingErr> {
+        let (x1, y1) = device_to_screen(self, x1, y1);
+        let (x2, y2) = device_to_screen(self, x2, y2);
+        let (x3, y3) = device_to_screen(self, x3, y3);
+
+        let triangle = Triangle::new(
+            self,
+            &[x1],
+            &[y1],
+            &[0.0],
+            &[x2],
+            &[y2],
+            &[0.0],
+            &[x3],
+            &[y3],
+            &[0.0],
+        )?;
+        self.draw(&triangle)
+    }
+
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
+        let (x, y, w, h) = Canvas::draw_state(self).rect_mode.to_corner(x, y, w, h);
+
+        let (x, y) = device_to_screen(self, x, y);
+        let (w, h) = device_to_screen_size(self, w, h);
+
+        let rect = Rect::new(self, &[x], &[y], &[0.0], &[w], &[h])?;
+        self.draw(&rect)
+    }
+
+    fn ellipse(&mut self, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
+        let (x, y, w, h) = Canvas::draw_state(self).ellipse_mode.to_center(x, y, w, h);
+
+        let (x, y) = device_to_screen(self, x, y);
+        let (w, h) = device_to_screen_size(self, w, h);
+
+        let ellipse = Ellipse::new(self, &[x], &[y], &[0.0], &[w], &[h])?;
+        self.draw(&ellipse)
+    }
+
+    fn polygon(&mut self, points: &[DVec2], close: bool) -> Result<(), ProcessingErr> {
+        let draw = Canvas::draw_state(self);
+        let (fill_off, stroke_off) = (!draw.fill, !draw.stroke);
+
+        // processing has no general polygon, so fill
+        // with unstroked triangles and then stroke the outline
+        if !fill_off {
+            Screen::stroke_off(self);
+
+            let mut result = Ok(());
+            for [a, b, c] in triangulate(points) {
+                let (a, b, c) = (points[a], points[b], points[c]);
+                result = Canvas::triangle(self, a.x, a.y, b.x, b.y, c.x, c.y);
+                if result.is_err() {
+                    break;
+                }
+            }
+
+            if !stroke_off {
+                Screen::stroke_on(self);
+            }
+            result?;
+        }
+
+        if !stroke_off {
+            for edge in points.windows(2) {
+                Canvas::line(self, edge[0].x, edge[0].y, edge[1].x, edge[1].y)?;
+            }
+
+            if close && points.len() > 2 {
+                let (first, last) = (points[0], points[points.len() - 1]);
+                Canvas::line(self, last.x, last.y, first.x, first.y)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn text_size(&mut self, size: f64) {
+        SCREEN_STATE.with(|state| state.borrow_mut().text_style.size = size);
+    }
+
+    fn text_align(&mut self, align: TextAlign, baseline: TextBaseline) {
+        SCREEN_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.text_style.align = align;
+            state.text_style.baseline = baseline;
+        });
+    }
+
+    fn text_style(&self) -> TextStyle {
+        SCREEN_STATE.with(|state| state.borrow().text_style)
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64) -> Result<(), ProcessingErr> {
+        let (style, stroke_off) = SCREEN_STATE.with(|state| {
+            let state = state.borrow();
+            (state.text_style, !state.draw.stroke)
+        });
+
+        // glyphs are filled without an outline
+        // and drawn as quads so the rect mode doesn't move them
+        Screen::stroke_off(self);
+
+        let mut result = Ok(());
+        for [x, y, w, h] in glyph_runs(text, x, y, &style) {
+            let (x1, y1) = device_to_screen(self, x, y);
+            let (x2, y2) = device_to_screen(self, x + w, y + h);
+
+            result = Quad::new(
+                self,
+                &[x1],
+                &[y1],
+                &[0.0],
+                &[x2],
+                &[y1],
+                &[0.0],
+                &[x2],
+                &[y2],
+                &[0.0],
+                &[x1],
+                &[y2],
+                &[0.0],
+            )
+            .and_then(|quad| self.draw(&quad));
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        if !stroke_off {
+            Screen::stroke_on(self);
+        }
+
+        result
+    }
+
+    fn image(&mut self, x: f64, y: f64, image: &Self::Image) -> Result<(), ProcessingErr> {
+        let (x, y) = device_to_screen(self, x, y);
+        let (w, h) = device_to_screen_size(self, image.width() as f64, image.height() as f64);
+
+        let mut rect = Rect::new(self, &[x], &[y], &[0.0], &[w], &[h])?;
+        rect.attach_texture(image);
+        self.draw(&rect)
+    }
+
+    fn load_pixels(&mut self) -> Result<PixelBuffer, ProcessingErr> {
+        Ok(PixelBuffer::from_image(read_screen(self)?))
+    }
+
+    fn create_image(&mut self, pixels: &PixelBuffer) -> Result<Self::Image, ProcessingErr> {
+        create_texture(self, pixels)
+    }
+}
+
+// replaces the processing matrix, which works in normalized device coordinates,
+// with the pixel space transform
+fn load_matrix(screen: &mut Screen, matrix: DMat3) {
+    let w = Screen::width(screen) as f64;
+    let h = Screen::height(screen) as f64;
+
+    let to_device = DMat3::from_translation(DVec2::new(-1.0, 1.0))
+        * DMat3::from_scale(DVec2::new(2.0 / w, -2.0 / h));
+    let m = to_device * matrix * to_device.inverse();
+
+    let [a, b, _, c, d, _, tx, ty, _] = m.to_cols_array();
+
+    Screen::reset_matrix(screen);
+    #[rustfmt::skip]
+    Screen::apply_matrix(
+        screen,
+        a as f32, c as f32, 0.0, tx as f32,
+        b as f32, d as f32, 0.0, ty as f32,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    );
+}
@@ -0,0 +1,322 @@
+use crate::math::clampf;
+
+/// Color space used when building or interpolating colors
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ColorMode {
+    #[default]
+    Rgb,
+
+    /// Hue, saturation, brightness
+    Hsb,
+
+    /// Hue, saturation, lightness
+    Hsl,
+}
+
+/// RGBA color with each channel in the 0-255 range used by the core helpers
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::BLACK
+    }
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::rgb(255.0, 255.0, 255.0);
+    pub const RED: Self = Self::rgb(255.0, 0.0, 0.0);
+    pub const GREEN: Self = Self::rgb(0.0, 255.0, 0.0);
+    pub const BLUE: Self = Self::rgb(0.0, 0.0, 255.0);
+    pub const TRANSPARENT: Self = Self::rgba(0.0, 0.0, 0.0, 0.0);
+
+    /* rgb */
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::rgba(r, g, b, 255.0)
+    }
+
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn grayscale(v: f32) -> Self {
+        Self::rgb(v, v, v)
+    }
+
+    pub const fn grayscale_alpha(v: f32, a: f32) -> Self {
+        Self::rgba(v, v, v, a)
+    }
+
+    /* hsb */
+
+    /// Hue in degrees, saturation and brightness in [0..100]
+    pub fn hsb(h: f32, s: f32, b: f32) -> Self {
+        Self::hsba(h, s, b, 255.0)
+    }
+
+    /// Hue in degrees, saturation and brightness in [0..100], alpha in [0..255]
+    pub fn hsba(h: f32, s: f32, b: f32, a: f32) -> Self {
+        let s = clampf(s, 0.0, 100.0) / 100.0;
+        let v = clampf(b, 0.0, 100.0) / 100.0;
+
+        let c = v * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = v - c;
+
+        Self::rgba((r + m) * 255.0, (g + m) * 255.0, (b + m) * 255.0, a)
+    }
+
+    /// Hue in degrees, saturation and brightness in [0..100]
+    pub fn to_hsb(&self) -> (f32, f32, f32) {
+        let (max, min, h) = self.hue();
+
+        let s = if max > 0.0 { (max - min) / max } else { 0.0 };
+
+        (h, s * 100.0, max * 100.0)
+    }
+
+    /* hsl */
+
+    /// Hue in degrees, saturation and lightness in [0..100]
+    pub fn hsl(h: f32, s: f32, l: f32) -> Self {
+        Self::hsla(h, s, l, 255.0)
+    }
+
+    /// Hue in degrees, saturation and lightness in [0..100], alpha in [0..255]
+    pub fn hsla(h: f32, s: f32, l: f32, a: f32) -> Self {
+        let s = clampf(s, 0.0, 100.0) / 100.0;
+        let l = clampf(l, 0.0, 100.0) / 100.0;
+
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = l - c / 2.0;
+
+        Self::rgba((r + m) * 255.0, (g + m) * 255.0, (b + m) * 255.0, a)
+    }
+
+    /// Hue in degrees, saturation and lightness in [0..100]
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (max, min, h) = self.hue();
+
+        let l = (max + min) / 2.0;
+        let s = if max - min > 0.0 {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        } else {
+            0.0
+        };
+
+        (h, s * 100.0, l * 100.0)
+    }
+
+    /* utils */
+
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Interpolates towards another color in the given color space
+    ///
+    /// Hue takes the shortest way around the color wheel
+    pub fn lerp(self, other: Self, t: f32, mode: ColorMode) -> Self {
+        let t = clampf(t, 0.0, 1.0);
+        let a = lerp(self.a, other.a, t);
+
+        match mode {
+            ColorMode::Rgb => Self::rgba(
+                lerp(self.r, other.r, t),
+                lerp(self.g, other.g, t),
+                lerp(self.b, other.b, t),
+                a,
+            ),
+            ColorMode::Hsb => {
+                let (h1, s1, b1) = self.to_hsb();
+                let (h2, s2, b2) = other.to_hsb();
+                Self::hsba(lerp_hue(h1, h2, t), lerp(s1, s2, t), lerp(b1, b2, t), a)
+            }
+            ColorMode::Hsl => {
+                let (h1, s1, l1) = self.to_hsl();
+                let (h2, s2, l2) = other.to_hsl();
+                Self::hsla(lerp_hue(h1, h2, t), lerp(s1, s2, t), lerp(l1, l2, t), a)
+            }
+        }
+    }
+
+    // max and min channel in [0..1] and the hue in degrees
+    fn hue(&self) -> (f32, f32, f32) {
+        let r = clampf(self.r, 0.0, 255.0) / 255.0;
+        let g = clampf(self.g, 0.0, 255.0) / 255.0;
+        let b = clampf(self.b, 0.0, 255.0) / 255.0;
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let d = max - min;
+
+        let h = if d <= 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / d).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / d + 2.0)
+        } else {
+            60.0 * ((r - g) / d + 4.0)
+        };
+
+        (max, min, h)
+    }
+}
+
+/// Interpolates between two colors in RGB
+pub fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    a.lerp(b, t, ColorMode::Rgb)
+}
+
+/// Gradient through a list of colors
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colors: Vec<Color>,
+    mode: ColorMode,
+}
+
+impl Palette {
+    pub fn new(colors: impl Into<Vec<Color>>) -> Self {
+        let colors = colors.into();
+        assert!(!colors.is_empty(), "palette must have at least one color");
+
+        Self {
+            colors,
+            mode: ColorMode::Rgb,
+        }
+    }
+
+    /// Sets the color space stops are blended in
+    pub fn with_mode(mut self, mode: ColorMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Color stop by index, wrapping around
+    pub fn get(&self, idx: usize) -> Color {
+        self.colors[idx % self.colors.len()]
+    }
+
+    /// Color along the gradient with t in [0..1]
+    pub fn sample(&self, t: f64) -> Color {
+        if self.colors.len() == 1 {
+            return self.colors[0];
+        }
+
+        let t = clampf(t, 0.0, 1.0) * (self.colors.len() - 1) as f64;
+        let idx = (t.floor() as usize).min(self.colors.len() - 2);
+
+        self.colors[idx].lerp(self.colors[idx + 1], (t - idx as f64) as f32, self.mode)
+    }
+
+    /* named palettes */
+
+    pub fn grayscale() -> Self {
+        Self::new([Color::BLACK, Color::WHITE])
+    }
+
+    /// Red round the color wheel to magenta at full saturation and brightness
+    pub fn rainbow() -> Self {
+        // stops keep the interpolation from taking the short way round
+        Self::new([
+            Color::hsb(0.0, 100.0, 100.0),
+            Color::hsb(60.0, 100.0, 100.0),
+            Color::hsb(120.0, 100.0, 100.0),
+            Color::hsb(180.0, 100.0, 100.0),
+            Color::hsb(240.0, 100.0, 100.0),
+            Color::hsb(300.0, 100.0, 100.0),
+        ])
+        .with_mode(ColorMode::Hsb)
+    }
+
+    /// Black through red and yellow to white
+    pub fn fire() -> Self {
+        Self::new([
+            Color::BLACK,
+            Color::rgb(128.0, 0.0, 0.0),
+            Color::rgb(255.0, 96.0, 0.0),
+            Color::rgb(255.0, 220.0, 64.0),
+            Color::WHITE,
+        ])
+    }
+
+    /// Cold to hot
+    pub fn heat() -> Self {
+        Self::new([
+            Color::rgb(0.0, 0.0, 255.0),
+            Color::rgb(0.0, 255.0, 255.0),
+            Color::rgb(0.0, 255.0, 0.0),
+            Color::rgb(255.0, 255.0, 0.0),
+            Color::rgb(255.0, 0.0, 0.0),
+        ])
+    }
+
+    /// https://bids.github.io/colormap/
+    pub fn viridis() -> Self {
+        Self::new([
+            Color::rgb(68.0, 1.0, 84.0),
+            Color::rgb(59.0, 82.0, 139.0),
+            Color::rgb(33.0, 145.0, 140.0),
+            Color::rgb(94.0, 201.0, 98.0),
+            Color::rgb(253.0, 231.0, 37.0),
+        ])
+    }
+
+    /// Muted pastels, looks nice for categories
+    pub fn pastel() -> Self {
+        Self::new([
+            Color::rgb(251.0, 180.0, 174.0),
+            Color::rgb(179.0, 205.0, 227.0),
+            Color::rgb(204.0, 235.0, 197.0),
+            Color::rgb(222.0, 203.0, 228.0),
+            Color::rgb(254.0, 217.0, 166.0),
+        ])
+    }
+}
+
+/* internal utils */
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn lerp_hue(a: f32, b: f32, t: f32) -> f32 {
+    let mut d = (b - a).rem_euclid(360.0);
+    if d > 180.0 {
+        d -= 360.0;
+    }
+
+    (a + d * t).rem_euclid(360.0)
+}
+
+// rgb in [0..c] for the hue, before adding the lightness offset
+fn hue_to_rgb(h: f32, c: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+
+    match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    }
+}
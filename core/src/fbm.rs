@@ -0,0 +1,172 @@
+use glam::DVec2;
+use noise::{NoiseFn, Perlin, Seedable};
+
+use crate::random::PERLIN_NOISE;
+
+// shift between the samples of vector-valued noise so the components aren't correlated
+const COMPONENT_OFFSET: [f64; 3] = [317.3, 151.9, 73.1];
+
+/// Fractal Brownian motion, a sum of Perlin noise octaves
+///
+/// Each octave is sampled at `lacunarity` times the frequency
+/// and `persistence` times the amplitude of the previous one
+///
+/// Output range [-1..1]
+#[derive(Debug, Clone)]
+pub struct Fbm {
+    octaves: usize,
+    persistence: f64,
+    lacunarity: f64,
+    frequency: f64,
+    offset: [f64; 3],
+
+    // None samples the shared noise seeded by core::random
+    perlin: Option<Perlin>,
+}
+
+impl Default for Fbm {
+    fn default() -> Self {
+        Self {
+            octaves: 4,
+            persistence: 0.5,
+            lacunarity: 2.0,
+            frequency: 1.0,
+            offset: [0.0; 3],
+
+            perlin: None,
+        }
+    }
+}
+
+impl Fbm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn octaves(mut self, octaves: usize) -> Self {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    pub fn persistence(mut self, persistence: f64) -> Self {
+        self.persistence = persistence;
+        self
+    }
+
+    pub fn lacunarity(mut self, lacunarity: f64) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Frequency of the first octave
+    pub fn frequency(mut self, frequency: f64) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Uses a fixed seed instead of the run seed
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.perlin = Some(Perlin::default().set_seed(seed));
+        self
+    }
+
+    /// Added to every sample point before scaling
+    pub fn offset(mut self, x: f64, y: f64, z: f64) -> Self {
+        self.offset = [x, y, z];
+        self
+    }
+
+    /* scalar noise */
+
+    pub fn get(&self, x: f64) -> f64 {
+        self.sample([x, 0.0, 0.0], 1)
+    }
+
+    pub fn get2d(&self, point: [f64; 2]) -> f64 {
+        self.sample([point[0], point[1], 0.0], 2)
+    }
+
+    pub fn get3d(&self, point: [f64; 3]) -> f64 {
+        self.sample(point, 3)
+    }
+
+    /* vector noise */
+
+    /// Vector with each component in [-1..1], varying over time
+    pub fn vector2(&self, t: f64) -> DVec2 {
+        DVec2::new(self.get(t), self.get(t + COMPONENT_OFFSET[0]))
+    }
+
+    /// Vector with each component in [-1..1], varying over the plane
+    pub fn vector2_2d(&self, point: [f64; 2]) -> DVec2 {
+        DVec2::new(
+            self.get2d(point),
+            self.get2d([
+                point[0] + COMPONENT_OFFSET[0],
+                point[1] + COMPONENT_OFFSET[1],
+            ]),
+        )
+    }
+
+    /// Angle in [0..2PI] varying over the plane, for flow fields
+    pub fn angle2d(&self, point: [f64; 2]) -> f64 {
+        (self.get2d(point) + 1.0) * std::f64::consts::PI
+    }
+
+    /// Unit vector varying over the plane, for flow fields
+    pub fn direction2d(&self, point: [f64; 2]) -> DVec2 {
+        let theta = self.angle2d(point);
+        DVec2::new(theta.cos(), theta.sin())
+    }
+
+    /// Angle in [0..2PI] varying over space, for flow fields that change over time
+    pub fn angle3d(&self, point: [f64; 3]) -> f64 {
+        (self.get3d(point) + 1.0) * std::f64::consts::PI
+    }
+
+    /// Unit vector varying over space, for flow fields that change over time
+    pub fn direction3d(&self, point: [f64; 3]) -> DVec2 {
+        let theta = self.angle3d(point);
+        DVec2::new(theta.cos(), theta.sin())
+    }
+
+    /* internal utils */
+
+    fn sample(&self, point: [f64; 3], dimensions: usize) -> f64 {
+        match &self.perlin {
+            Some(perlin) => self.fractal(perlin, point, dimensions),
+            None => self.fractal(&PERLIN_NOISE.lock().unwrap(), point, dimensions),
+        }
+    }
+
+    fn fractal(&self, perlin: &Perlin, point: [f64; 3], dimensions: usize) -> f64 {
+        let mut frequency = self.frequency;
+        let mut amplitude = 1.0;
+
+        let mut total = 0.0;
+        let mut max = 0.0;
+        for _ in 0..self.octaves {
+            let x = (point[0] + self.offset[0]) * frequency;
+            let y = (point[1] + self.offset[1]) * frequency;
+            let z = (point[2] + self.offset[2]) * frequency;
+
+            let value = match dimensions {
+                1 | 2 => perlin.get([x, y]),
+                _ => perlin.get([x, y, z]),
+            };
+
+            total += value * amplitude;
+            max += amplitude;
+
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+
+        // keep the output in the single octave range
+        if max > 0.0 {
+            total / max
+        } else {
+            0.0
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use glam::DVec2;
+use processing::errors::ProcessingErr;
+
+use crate::fbm::Fbm;
+use crate::pixels::PixelBuffer;
+use crate::Canvas;
+
+/// Grid of directions covering an area, sampled at the center of each cell
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FlowField {
+    // column major
+    field: Vec<DVec2>,
+
+    cols: usize,
+    rows: usize,
+    resolution: f64,
+}
+
+impl FlowField {
+    /// Field with no flow covering `width` x `height` in cells `resolution` wide
+    ///
+    /// Panics if `resolution` isn't positive or the size isn't finite
+    pub fn new(width: f64, height: f64, resolution: f64) -> Self {
+        assert!(resolution > 0.0, "flow field resolution must be positive");
+        assert!(
+            width.is_finite() && height.is_finite(),
+            "flow field size must be finite"
+        );
+
+        let cols = ((width / resolution).ceil() as usize).max(1);
+        let rows = ((height / resolution).ceil() as usize).max(1);
+
+        Self {
+            field: vec![DVec2::ZERO; cols * rows],
+            cols,
+            rows,
+            resolution,
+        }
+    }
+
+    /// Field following noise, `scale` is the noise distance between cells
+    pub fn from_noise(width: f64, height: f64, resolution: f64, noise: &Fbm, scale: f64) -> Self {
+        let mut field = Self::new(width, height, resolution);
+        field.set_noise(noise, scale, 0.0);
+        field
+    }
+
+    /// Field flowing from dark to bright areas of the image
+    ///
+    /// The image is stretched over the field, flat areas have no flow
+    pub fn from_image(width: f64, height: f64, resolution: f64, pixels: &PixelBuffer) -> Self {
+        let mut field = Self::new(width, height, resolution);
+
+        let sx = pixels.width() as f64 / width;
+        let sy = pixels.height() as f64 / height;
+
+        // sample the gradient across about a cell
+        let dx = (resolution * sx).max(1.0);
+        let dy = (resolution * sy).max(1.0);
+
+        for col in 0..field.cols {
+            for row in 0..field.rows {
+                let center = field.center(col, row);
+                let (x, y) = (center.x * sx, center.y * sy);
+
+                let gradient = DVec2::new(
+                    brightness(pixels, x + dx, y) - brightness(pixels, x - dx, y),
+                    brightness(pixels, x, y + dy) - brightness(pixels, x, y - dy),
+                );
+                field.set(col, row, gradient.normalize_or_zero());
+            }
+        }
+
+        field
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn resolution(&self) -> f64 {
+        self.resolution
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> DVec2 {
+        self.field[col * self.rows + row]
+    }
+
+    pub fn set(&mut self, col: usize, row: usize, direction: DVec2) {
+        self.field[col * self.rows + row] = direction;
+    }
+
+    /// Center of a cell
+    pub fn center(&self, col: usize, row: usize) -> DVec2 {
+        DVec2::new(col as f64 + 0.5, row as f64 + 0.5) * self.resolution
+    }
+
+    /// Resamples every cell from 3D noise, stepping `z` over time animates the field
+    pub fn set_noise(&mut self, noise: &Fbm, scale: f64, z: f64) {
+        for col in 0..self.cols {
+            for row in 0..self.rows {
+                let direction = noise.direction3d([col as f64 * scale, row as f64 * scale, z]);
+                self.set(col, row, direction);
+            }
+        }
+    }
+
+    /// Bends the cells within `radius` of the stroke from `a` to `b` towards its direction
+    ///
+    /// `strength` in [0..1] is how far cells on the stroke turn, falling off towards the edge
+    pub fn stroke(&mut self, a: DVec2, b: DVec2, radius: f64, strength: f64) {
+        let ab = b - a;
+        let direction = ab.normalize_or_zero();
+        if direction == DVec2::ZERO || radius <= 0.0 {
+            return;
+        }
+
+        let length_squared = ab.length_squared();
+        for col in 0..self.cols {
+            for row in 0..self.rows {
+                let center = self.center(col, row);
+
+                // distance from the cell to the closest point on the stroke
+                let t = ((center - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+                let distance = center.distance(a + ab * t);
+                if distance >= radius {
+                    continue;
+                }
+
+                let amount = strength * (1.0 - distance / radius);
+                let current = self.get(col, row);
+                self.set(
+                    col,
+                    row,
+                    current.lerp(direction, amount).normalize_or_zero(),
+                );
+            }
+        }
+    }
+
+    /// Flow at a point, bilinearly interpolated between the nearest cell centers
+    ///
+    /// Shorter than unit length where neighboring cells disagree,
+    /// points outside the field get the flow at the nearest edge
+    pub fn lookup(&self, position: DVec2) -> DVec2 {
+        let x = (position.x / self.resolution - 0.5).clamp(0.0, (self.cols - 1) as f64);
+        let y = (position.y / self.resolution - 0.5).clamp(0.0, (self.rows - 1) as f64);
+
+        let (col, row) = (x.floor() as usize, y.floor() as usize);
+        let (next_col, next_row) = ((col + 1).min(self.cols - 1), (row + 1).min(self.rows - 1));
+        let (fx, fy) = (x.fract(), y.fract());
+
+        let top = self.get(col, row).lerp(self.get(next_col, row), fx);
+        let bottom = self
+            .get(col, next_row)
+            .lerp(self.get(next_col, next_row), fx);
+
+        top.lerp(bottom, fy)
+    }
+
+    /// Draws an arrow per cell using the current stroke
+    pub fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        let length = self.resolution * 0.8;
+        let head = length * 0.25;
+
+        for col in 0..self.cols {
+            for row in 0..self.rows {
+                let direction = self.get(col, row);
+                if direction == DVec2::ZERO {
+                    continue;
+                }
+
+                let center = self.center(col, row);
+                let tail = center - direction * length * 0.5;
+                let tip = center + direction * length * 0.5;
+
+                let back = tip - direction * head;
+                let side = direction.perp() * head * 0.5;
+
+                canvas.line(tail.x, tail.y, tip.x, tip.y)?;
+                canvas.line(tip.x, tip.y, back.x + side.x, back.y + side.y)?;
+                canvas.line(tip.x, tip.y, back.x - side.x, back.y - side.y)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// brightness in [0..1] of the pixel, clamped to the image
+fn brightness(pixels: &PixelBuffer, x: f64, y: f64) -> f64 {
+    let x = x.clamp(0.0, (pixels.width().max(1) - 1) as f64) as u32;
+    let y = y.clamp(0.0, (pixels.height().max(1) - 1) as f64) as u32;
+
+    let color = pixels.get(x, y);
+    (0.299 * color.r + 0.587 * color.g + 0.114 * color.b) as f64 / 255.0
+}
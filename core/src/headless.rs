@@ -0,0 +1,718 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use glam::{DMat3, DVec2};
+use image::{Rgba, RgbaImage};
+use processing::errors::ProcessingErr;
+
+use crate::pixels::PixelBuffer;
+use crate::record::{arg, Recorder};
+use crate::shapes::RectMode;
+use crate::text::{glyph_runs, TextAlign, TextBaseline, TextStyle};
+use crate::{BlendMode, Canvas, DrawState, Sketch};
+
+/// Environment variable holding the headless output PNG path
+pub const HEADLESS_ENV: &str = "NOC_HEADLESS";
+
+/// Command line flag holding the headless output PNG path, takes precedence over the environment
+pub const HEADLESS_FLAG: &str = "--headless";
+
+/// Environment variable holding the number of frames drawn headless
+pub const HEADLESS_FRAMES_ENV: &str = "NOC_HEADLESS_FRAMES";
+
+/// Command line flag holding the number of frames drawn headless
+pub const HEADLESS_FRAMES_FLAG: &str = "--headless-frames";
+
+/// Frames drawn headless when no count is given
+pub const DEFAULT_FRAMES: usize = 1;
+
+/// Environment variable that rewrites snapshots instead of comparing against them
+pub const UPDATE_SNAPSHOTS_ENV: &str = "NOC_UPDATE_SNAPSHOTS";
+
+// fixed frame time for headless runs so output is reproducible
+const HEADLESS_DT: f64 = 1.0 / 60.0;
+
+/// Where and for how long to run a sketch without a window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadlessOptions {
+    pub output: PathBuf,
+    pub frames: usize,
+}
+
+impl HeadlessOptions {
+    /// Reads the options from the command line or environment,
+    /// returns None if a headless run wasn't asked for
+    pub fn from_args() -> Option<Self> {
+        let output = arg(HEADLESS_FLAG).or_else(|| env::var(HEADLESS_ENV).ok())?;
+
+        let frames = arg(HEADLESS_FRAMES_FLAG)
+            .or_else(|| env::var(HEADLESS_FRAMES_ENV).ok())
+            .and_then(|frames| match frames.trim().parse() {
+                Ok(frames) => Some(frames),
+                Err(err) => {
+                    eprintln!("Ignoring invalid frame count '{}': {}", frames, err);
+                    None
+                }
+            })
+            .unwrap_or(DEFAULT_FRAMES);
+
+        Some(Self {
+            output: output.into(),
+            frames,
+        })
+    }
+}
+
+/// Runs the sketch without a window for the given number of frames
+/// and writes the last frame out as a PNG
+///
+/// Frames are also recorded if a recording was asked for, see `core::record`
+pub fn run<S, D>(
+    frames: usize,
+    output: impl AsRef<Path>,
+    setup: S,
+    mut draw: D,
+) -> Result<(), ProcessingErr>
+where
+    S: FnOnce() -> Result<Headless, ProcessingErr>,
+    D: FnMut(&mut Headless, f64) -> Result<(), ProcessingErr>,
+{
+    let mut canvas = setup()?;
+    let mut recorder = Recorder::from_args();
+
+    for _ in 0..frames {
+        draw_frame(&mut canvas, &mut draw)?;
+
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.capture_image(&canvas.pixels)?;
+        }
+    }
+
+    if let Some(recorder) = recorder.as_mut() {
+        recorder.finish()?;
+    }
+
+    canvas.save(output)
+}
+
+pub fn create_canvas(width: u32, height: u32) -> Result<Headless, ProcessingErr> {
+    let mut canvas = Headless::new(width, height);
+    canvas.background(255.0, 255.0, 255.0, 255.0);
+    Ok(canvas)
+}
+
+/// Draws the given number of frames to a new canvas and returns it, for snapshot tests
+pub fn render<D>(
+    width: u32,
+    height: u32,
+    frames: usize,
+    mut draw: D,
+) -> Result<Headless, ProcessingErr>
+where
+    D: FnMut(&mut Headless, f64) -> Result<(), ProcessingErr>,
+{
+    let mut canvas = create_canvas(width, height)?;
+    for _ in 0..frames {
+        draw_frame(&mut canvas, &mut draw)?;
+    }
+
+    Ok(canvas)
+}
+
+/// Sets the sketch up on a new canvas, draws the given number of frames and returns it,
+/// for snapshot tests
+pub fn render_sketch(
+    width: u32,
+    height: u32,
+    frames: usize,
+    sketch: &mut impl Sketch,
+) -> Result<Headless, ProcessingErr> {
+    let mut canvas = create_canvas(width, height)?;
+    sketch.setup(&mut canvas)?;
+
+    for _ in 0..frames {
+        draw_frame(&mut canvas, &mut |canvas: &mut Headless, dt| {
+            sketch.draw(canvas, dt)
+        })?;
+    }
+
+    Ok(canvas)
+}
+
+/// Panics if the canvas doesn't match the PNG snapshot
+///
+/// Missing snapshots are written out, as are all of them
+/// when `NOC_UPDATE_SNAPSHOTS` is set
+pub fn assert_snapshot(canvas: &Headless, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+
+    if env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() || !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("failed to create the snapshot directory");
+        }
+        canvas.save(path).expect("failed to write the snapshot");
+        return;
+    }
+
+    let expected = image::open(path)
+        .unwrap_or_else(|err| panic!("failed to read snapshot {}: {}", path.display(), err))
+        .to_rgba8();
+
+    assert_eq!(
+        expected.dimensions(),
+        canvas.pixels.dimensions(),
+        "snapshot {} is a different size",
+        path.display()
+    );
+
+    let different = expected
+        .pixels()
+        .zip(canvas.pixels.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    assert!(
+        different == 0,
+        "{} pixels differ from snapshot {}, set {} to update it",
+        different,
+        path.display(),
+        UPDATE_SNAPSHOTS_ENV
+    );
+}
+
+/// Warns that a headless run was asked for but the sketch can only run in a window
+pub(crate) fn warn_window_only() {
+    if HeadlessOptions::from_args().is_some() {
+        eprintln!("This sketch needs a window, ignoring the headless options");
+    }
+}
+
+fn draw_frame<D>(canvas: &mut Headless, draw: &mut D) -> Result<(), ProcessingErr>
+where
+    D: FnMut(&mut Headless, f64) -> Result<(), ProcessingErr>,
+{
+    canvas.reset_matrix();
+
+    draw(canvas, HEADLESS_DT)?;
+
+    canvas.frame_count += 1;
+
+    Ok(())
+}
+
+/// CPU rasterizer implementing the core drawing surface
+///
+/// Colors are in the 0-255 range used by the core helpers
+/// and coordinates are in pixels with the origin in the top left
+#[derive(Debug)]
+pub struct Headless {
+    pixels: RgbaImage,
+    frame_count: usize,
+
+    state: DrawState,
+    text_style: TextStyle,
+
+    matrix: DMat3,
+    matrix_stack: Vec<DMat3>,
+}
+
+impl Headless {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            pixels: RgbaImage::new(width, height),
+            frame_count: 0,
+
+            state: DrawState::default(),
+            text_style: TextStyle::default(),
+
+            matrix: DMat3::IDENTITY,
+            matrix_stack: vec![],
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn pixels(&self) -> &RgbaImage {
+        &self.pixels
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ProcessingErr> {
+        self.pixels
+            .save(path)
+            .map_err(|err| ProcessingErr::ImageNotSaved(io::Error::other(err)))
+    }
+
+    /* internal utils */
+
+    fn fill_color(&self) -> Option<[f32; 4]> {
+        self.state.fill.then_some(self.state.fill_color)
+    }
+
+    fn stroke_color(&self) -> Option<[f32; 4]> {
+        self.state.stroke.then_some(self.state.stroke_color)
+    }
+
+    // uniform scale of the current matrix, used to size strokes and curves
+    fn matrix_scale(&self) -> f64 {
+        self.matrix.determinant().abs().sqrt()
+    }
+
+    fn draw_shape(&mut self, points: &[DVec2], closed: bool) {
+        let points: Vec<DVec2> = points
+            .iter()
+            .map(|p| self.matrix.transform_point2(*p))
+            .collect();
+
+        if closed {
+            if let Some(fill) = self.fill_color() {
+                self.fill_paths(std::slice::from_ref(&points), fill);
+            }
+        }
+
+        if let Some(stroke) = self.stroke_color() {
+            let weight = (self.state.stroke_weight * self.matrix_scale()).max(1.0);
+            let outline = stroke_outline(&points, closed, weight);
+            self.fill_paths(&outline, stroke);
+        }
+    }
+
+    // scanline fill of a set of paths using the nonzero winding rule
+    fn fill_paths(&mut self, paths: &[Vec<DVec2>], color: [f32; 4]) {
+        let mut min_y = f64::MAX;
+        let mut max_y = f64::MIN;
+        for p in paths.iter().flatten() {
+            min_y = min_y.min(p.y);
+            max_y = max_y.max(p.y);
+        }
+
+        let height = self.height() as f64;
+        let width = self.width() as f64;
+        if paths.is_empty() || max_y < 0.0 || min_y >= height {
+            return;
+        }
+
+        let start = min_y.max(0.0).floor() as u32;
+        let end = max_y.min(height - 1.0).ceil() as u32;
+
+        let mut crossings = vec![];
+        for py in start..=end {
+            // sample at the pixel center
+            let y = py as f64 + 0.5;
+
+            crossings.clear();
+            for path in paths {
+                for i in 0..path.len() {
+                    let a = path[i];
+                    let b = path[(i + 1) % path.len()];
+                    if (a.y <= y && b.y > y) || (b.y <= y && a.y > y) {
+                        let t = (y - a.y) / (b.y - a.y);
+                        let x = a.x + t * (b.x - a.x);
+
+                        // degenerate transforms can leave edges at infinity or NaN
+                        if !x.is_finite() {
+                            continue;
+                        }
+
+                        let winding = if b.y > a.y { 1 } else { -1 };
+                        crossings.push((x, winding));
+                    }
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for i in 0..crossings.len() {
+                winding += crossings[i].1;
+                if winding == 0 || i + 1 == crossings.len() {
+                    continue;
+                }
+
+                // fill pixels whose centers lie inside the span
+                let x0 = (crossings[i].0 - 0.5).ceil().max(0.0);
+                let x1 = (crossings[i + 1].0 - 0.5).ceil().min(width);
+                if x1 <= x0 {
+                    continue;
+                }
+
+                for px in x0 as u32..x1 as u32 {
+                    self.blend_pixel(px, py, color);
+                }
+            }
+        }
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, color: [f32; 4]) {
+        let mode = self.state.blend_mode;
+        let pixel = self.pixels.get_pixel_mut(x, y);
+
+        if mode == BlendMode::Replace {
+            *pixel = Rgba(color.map(to_u8));
+            return;
+        }
+
+        // the blended color is mixed in by the source alpha, like processing
+        let a = (color[3] / 255.0).clamp(0.0, 1.0);
+        for (dst, src) in pixel.0.iter_mut().zip(color.iter()).take(3) {
+            let d = *dst as f32;
+            let s = src.clamp(0.0, 255.0);
+
+            let blended = match mode {
+                BlendMode::Replace | BlendMode::Blend => s,
+                BlendMode::Add => (d + s).min(255.0),
+                BlendMode::Subtract => (d - s).max(0.0),
+                BlendMode::Lightest => d.max(s),
+                BlendMode::Darkest => d.min(s),
+                BlendMode::Exclusion => d + s - 2.0 * d * s / 255.0,
+                BlendMode::Multiply => d * s / 255.0,
+                BlendMode::Screen => 255.0 - (255.0 - d) * (255.0 - s) / 255.0,
+            };
+
+            *dst = to_u8(d + (blended - d) * a);
+        }
+
+        let dst_a = pixel.0[3] as f32 / 255.0;
+        pixel.0[3] = to_u8((a + dst_a * (1.0 - a)) * 255.0);
+    }
+}
+
+impl Canvas for Headless {
+    type Image = RgbaImage;
+
+    fn width(&self) -> u32 {
+        self.pixels.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.pixels.height()
+    }
+
+    /* color */
+
+    fn background(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        let color = Rgba([to_u8(r), to_u8(g), to_u8(b), to_u8(a)]);
+        for pixel in self.pixels.pixels_mut() {
+            *pixel = color;
+        }
+    }
+
+    fn fill(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.state.fill_color = [r, g, b, a];
+        self.state.fill = true;
+    }
+
+    fn fill_off(&mut self) {
+        self.state.fill = false;
+    }
+
+    fn stroke(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.state.stroke_color = [r, g, b, a];
+        self.state.stroke = true;
+    }
+
+    fn stroke_off(&mut self) {
+        self.state.stroke = false;
+    }
+
+    fn stroke_weight(&mut self, weight: f64) {
+        self.state.stroke_weight = weight;
+    }
+
+    /* draw state */
+
+    fn draw_state(&self) -> DrawState {
+        self.state
+    }
+
+    fn blend_mode(&mut self, mode: BlendMode) {
+        self.state.blend_mode = mode;
+    }
+
+    fn rect_mode(&mut self, mode: RectMode) {
+        self.state.rect_mode = mode;
+    }
+
+    fn ellipse_mode(&mut self, mode: RectMode) {
+        self.state.ellipse_mode = mode;
+    }
+
+    /* transform */
+
+    fn push_matrix(&mut self) {
+        self.matrix_stack.push(self.matrix);
+    }
+
+    fn pop_matrix(&mut self) {
+        self.matrix = self.matrix_stack.pop().unwrap_or(DMat3::IDENTITY);
+    }
+
+    fn reset_matrix(&mut self) {
+        self.matrix = DMat3::IDENTITY;
+        self.matrix_stack.clear();
+    }
+
+    fn matrix(&self) -> DMat3 {
+        self.matrix
+    }
+
+    fn apply_matrix(&mut self, matrix: DMat3) {
+        self.matrix = self.matrix * matrix;
+    }
+
+    /* shapes */
+
+    fn point(&mut self, x: f64, y: f64) -> Result<(), ProcessingErr> {
+        let stroke = match self.stroke_color() {
+            Some(stroke) => stroke,
+            None => return Ok(()),
+        };
+
+        // points are a stroke-colored square centered on the point
+        let p = self.matrix.transform_point2(DVec2::new(x, y));
+        let hw = (self.state.stroke_weight * self.matrix_scale()).max(1.0) / 2.0;
+        let square = vec![
+            DVec2::new(p.x - hw, p.y - hw),
+            DVec2::new(p.x + hw, p.y - hw),
+            DVec2::new(p.x + hw, p.y + hw),
+            DVec2::new(p.x - hw, p.y + hw),
+        ];
+        self.fill_paths(&[square], stroke);
+
+        Ok(())
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> Result<(), ProcessingErr> {
+        self.draw_shape(&[DVec2::new(x1, y1), DVec2::new(x2, y2)], false);
+
+        Ok(())
+    }
+
+    fn triangle(
+        &mut self,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x3: f64,
+        y3: f64,
+    ) -> Result<(), ProcessingErr> {
+        self.draw_shape(
+            &[DVec2::new(x1, y1), DVec2::new(x2, y2), DVec2::new(x3, y3)],
+            true,
+        );
+
+        Ok(())
+    }
+
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
+        let (x, y, w, h) = self.state.rect_mode.to_corner(x, y, w, h);
+
+        self.draw_shape(
+            &[
+                DVec2::new(x, y),
+                DVec2::new(x + w, y),
+                DVec2::new(x + w, y + h),
+                DVec2::new(x, y + h),
+            ],
+            true,
+        );
+
+        Ok(())
+    }
+
+    fn ellipse(&mut self, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
+        let (x, y, w, h) = self.state.ellipse_mode.to_center(x, y, w, h);
+
+        let rx = w / 2.0;
+        let ry = h / 2.0;
+
+        // enough segments that the edges aren't visible at the transformed size
+        let r = rx.abs().max(ry.abs()) * self.matrix_scale();
+        let segments = ((r * std::f64::consts::TAU / 4.0) as usize).clamp(16, 128);
+
+        let mut points = Vec::with_capacity(segments);
+        for i in 0..segments {
+            let theta = i as f64 / segments as f64 * std::f64::consts::TAU;
+            points.push(DVec2::new(x + rx * theta.cos(), y + ry * theta.sin()));
+        }
+
+        self.draw_shape(&points, true);
+
+        Ok(())
+    }
+
+    fn polygon(&mut self, points: &[DVec2], close: bool) -> Result<(), ProcessingErr> {
+        let points: Vec<DVec2> = points
+            .iter()
+            .map(|p| self.matrix.transform_point2(*p))
+            .collect();
+
+        // open shapes are still filled, like processing
+        if let Some(fill) = self.fill_color() {
+            self.fill_paths(std::slice::from_ref(&points), fill);
+        }
+
+        if let Some(stroke) = self.stroke_color() {
+            let weight = (self.state.stroke_weight * self.matrix_scale()).max(1.0);
+            let outline = stroke_outline(&points, close, weight);
+            self.fill_paths(&outline, stroke);
+        }
+
+        Ok(())
+    }
+
+    /* text */
+
+    fn text_size(&mut self, size: f64) {
+        self.text_style.size = size;
+    }
+
+    fn text_align(&mut self, align: TextAlign, baseline: TextBaseline) {
+        self.text_style.align = align;
+        self.text_style.baseline = baseline;
+    }
+
+    fn text_style(&self) -> TextStyle {
+        self.text_style
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64) -> Result<(), ProcessingErr> {
+        let fill = match self.fill_color() {
+            Some(fill) => fill,
+            None => return Ok(()),
+        };
+
+        let glyphs: Vec<Vec<DVec2>> = glyph_runs(text, x, y, &self.text_style)
+            .iter()
+            .map(|[x, y, w, h]| {
+                vec![
+                    self.matrix.transform_point2(DVec2::new(*x, *y)),
+                    self.matrix.transform_point2(DVec2::new(x + w, *y)),
+                    self.matrix.transform_point2(DVec2::new(x + w, y + h)),
+                    self.matrix.transform_point2(DVec2::new(*x, y + h)),
+                ]
+            })
+            .collect();
+        self.fill_paths(&glyphs, fill);
+
+        Ok(())
+    }
+
+    /* image */
+
+    fn image(&mut self, x: f64, y: f64, image: &Self::Image) -> Result<(), ProcessingErr> {
+        let w = image.width() as f64;
+        let h = image.height() as f64;
+
+        let corners = [
+            DVec2::new(x, y),
+            DVec2::new(x + w, y),
+            DVec2::new(x + w, y + h),
+            DVec2::new(x, y + h),
+        ];
+
+        let mut min = DVec2::splat(f64::MAX);
+        let mut max = DVec2::splat(f64::MIN);
+        for corner in corners.iter() {
+            let p = self.matrix.transform_point2(*corner);
+            min = min.min(p);
+            max = max.max(p);
+        }
+
+        let x0 = min.x.max(0.0).floor() as u32;
+        let y0 = min.y.max(0.0).floor() as u32;
+        let x1 = max.x.min(self.width() as f64).ceil() as u32;
+        let y1 = max.y.min(self.height() as f64).ceil() as u32;
+
+        // map each covered pixel center back into the image
+        let inverse = self.matrix.inverse();
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let p = inverse.transform_point2(DVec2::new(px as f64 + 0.5, py as f64 + 0.5));
+                let u = p.x - x;
+                let v = p.y - y;
+                if u < 0.0 || v < 0.0 || u >= w || v >= h {
+                    continue;
+                }
+
+                let texel = image.get_pixel(u as u32, v as u32);
+                let color = [
+                    texel.0[0] as f32,
+                    texel.0[1] as f32,
+                    texel.0[2] as f32,
+                    texel.0[3] as f32,
+                ];
+                self.blend_pixel(px, py, color);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_pixels(&mut self) -> Result<PixelBuffer, ProcessingErr> {
+        Ok(PixelBuffer::from_image(self.pixels.clone()))
+    }
+
+    fn create_image(&mut self, pixels: &PixelBuffer) -> Result<Self::Image, ProcessingErr> {
+        Ok(pixels.as_image().clone())
+    }
+
+    fn update_pixels(&mut self, pixels: &PixelBuffer) -> Result<(), ProcessingErr> {
+        // same size buffers are copied straight over
+        if pixels.width() == self.width() && pixels.height() == self.height() {
+            self.pixels.clone_from(pixels.as_image());
+            return Ok(());
+        }
+
+        let matrix = self.matrix;
+        self.matrix = DMat3::IDENTITY;
+        let result = self.image(0.0, 0.0, pixels.as_image());
+        self.matrix = matrix;
+
+        result
+    }
+}
+
+fn to_u8(v: f32) -> u8 {
+    v.round().clamp(0.0, 255.0) as u8
+}
+
+// builds one counter-clockwise quad per segment so that
+// overlapping segments union under the nonzero rule
+fn stroke_outline(points: &[DVec2], closed: bool, weight: f64) -> Vec<Vec<DVec2>> {
+    let hw = weight / 2.0;
+
+    let segments = if closed {
+        points.len()
+    } else {
+        points.len().saturating_sub(1)
+    };
+
+    let mut outline = Vec::with_capacity(segments);
+    for i in 0..segments {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+
+        let dir = (b - a).normalize_or_zero();
+        let (dir, normal) = if dir == DVec2::ZERO {
+            (DVec2::X, DVec2::Y)
+        } else {
+            (dir, dir.perp())
+        };
+
+        // square off the ends so corners don't notch
+        let a = a - dir * hw;
+        let b = b + dir * hw;
+
+        outline.push(vec![
+            a - normal * hw,
+            b - normal * hw,
+            b + normal * hw,
+            a + normal * hw,
+        ]);
+    }
+
+    outline
+}
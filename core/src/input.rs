@@ -1,35 +1,203 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashSet;
+use std::sync::RwLock;
 
+use glam::DVec2;
 use once_cell::sync::Lazy;
-use processing::MouseButton;
-use processing::Screen;
+use processing::{Key, MouseButton, Screen};
 
-static MOUSE_LEFT_IS_PRESSED: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
-static MOUSE_RIGHT_IS_PRESSED: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
-static MOUSE_CENTER_IS_PRESSED: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
+// keys polled every frame, processing only lets us ask about one key at a time
+const KEYS: &[Key] = &[
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::Left,
+    Key::Right,
+    Key::Up,
+    Key::Down,
+    Key::Space,
+    Key::Return,
+    Key::Escape,
+    Key::Tab,
+    Key::Back,
+    Key::LShift,
+    Key::RShift,
+];
 
-pub(crate) fn update(screen: &mut Screen) {
-    if screen.mouse_press(MouseButton::Left) {
-        MOUSE_LEFT_IS_PRESSED.store(true, Ordering::Relaxed);
-    } else if screen.mouse_release(MouseButton::Left) {
-        MOUSE_LEFT_IS_PRESSED.store(false, Ordering::Relaxed);
+const MOUSE_BUTTONS: &[MouseButton] = &[MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+static INPUT: Lazy<RwLock<Input>> = Lazy::new(|| RwLock::new(Input::default()));
+
+/// Snapshot of the keyboard and mouse for the current frame
+///
+/// Pressed / released are only set on the frame the change happened,
+/// down stays set for as long as the key or button is held
+#[derive(Debug, Default, Clone)]
+pub struct Input {
+    keys_down: HashSet<Key>,
+    keys_pressed: HashSet<Key>,
+    keys_released: HashSet<Key>,
+
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+
+    mouse_position: DVec2,
+    prev_mouse_position: DVec2,
+    wheel: f64,
+}
+
+impl Input {
+    /* keyboard */
+
+    pub fn key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    pub fn key_released(&self, key: Key) -> bool {
+        self.keys_released.contains(&key)
     }
 
-    if screen.mouse_press(MouseButton::Right) {
-        MOUSE_RIGHT_IS_PRESSED.store(true, Ordering::Relaxed);
-    } else if screen.mouse_release(MouseButton::Right) {
-        MOUSE_RIGHT_IS_PRESSED.store(false, Ordering::Relaxed);
+    /* mouse */
+
+    pub fn mouse_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    pub fn mouse_released(&self, button: MouseButton) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    /// Cursor position in pixels with the origin in the top left
+    pub fn mouse_position(&self) -> DVec2 {
+        self.mouse_position
+    }
+
+    /// How far the cursor moved since the last frame
+    pub fn mouse_delta(&self) -> DVec2 {
+        self.mouse_position - self.prev_mouse_position
     }
 
-    if screen.mouse_press(MouseButton::Middle) {
-        MOUSE_CENTER_IS_PRESSED.store(true, Ordering::Relaxed);
-    } else if screen.mouse_release(MouseButton::Middle) {
-        MOUSE_CENTER_IS_PRESSED.store(false, Ordering::Relaxed);
+    /// Scroll since the last frame, positive is away from the user
+    pub fn wheel(&self) -> f64 {
+        self.wheel
+    }
+
+    /* internal utils */
+
+    fn update(&mut self, screen: &mut Screen) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        for &key in KEYS {
+            if screen.key_press(key) {
+                if self.keys_down.insert(key) {
+                    self.keys_pressed.insert(key);
+                }
+            } else if screen.key_release(key) && self.keys_down.remove(&key) {
+                self.keys_released.insert(key);
+            }
+        }
+
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        for &button in MOUSE_BUTTONS {
+            if screen.mouse_press(button) {
+                if self.buttons_down.insert(button) {
+                    self.buttons_pressed.insert(button);
+                }
+            } else if screen.mouse_release(button) && self.buttons_down.remove(&button) {
+                self.buttons_released.insert(button);
+            }
+        }
+
+        self.prev_mouse_position = self.mouse_position;
+        self.mouse_position = DVec2::new(screen.mouse_x(), screen.mouse_y());
+
+        self.wheel = screen.mouse_wheel();
     }
 }
 
+pub(crate) fn update(screen: &mut Screen) {
+    INPUT.write().unwrap().update(screen);
+}
+
+/// Returns the input snapshot for the current frame
+pub fn input() -> Input {
+    INPUT.read().unwrap().clone()
+}
+
+pub fn key_down(key: Key) -> bool {
+    INPUT.read().unwrap().key_down(key)
+}
+
+pub fn key_pressed(key: Key) -> bool {
+    INPUT.read().unwrap().key_pressed(key)
+}
+
+pub fn key_released(key: Key) -> bool {
+    INPUT.read().unwrap().key_released(key)
+}
+
+pub fn mouse_position() -> DVec2 {
+    INPUT.read().unwrap().mouse_position()
+}
+
+pub fn mouse_delta() -> DVec2 {
+    INPUT.read().unwrap().mouse_delta()
+}
+
+pub fn mouse_down(button: MouseButton) -> bool {
+    INPUT.read().unwrap().mouse_down(button)
+}
+
+pub fn mouse_pressed(button: MouseButton) -> bool {
+    INPUT.read().unwrap().mouse_pressed(button)
+}
+
+pub fn mouse_released(button: MouseButton) -> bool {
+    INPUT.read().unwrap().mouse_released(button)
+}
+
 pub fn mouse_is_pressed() -> bool {
-    MOUSE_LEFT_IS_PRESSED.load(Ordering::Relaxed)
-        || MOUSE_RIGHT_IS_PRESSED.load(Ordering::Relaxed)
-        || MOUSE_CENTER_IS_PRESSED.load(Ordering::Relaxed)
+    !INPUT.read().unwrap().buttons_down.is_empty()
 }
@@ -1,71 +1,275 @@
+pub mod box2d;
+pub mod canvas;
+pub mod color;
+pub mod fbm;
+pub mod flow;
+pub mod headless;
 pub mod input;
 pub mod math;
+pub mod mover;
+pub mod pixels;
+pub mod random;
+pub mod record;
 pub mod shapes;
+pub mod svg;
+pub mod text;
+pub mod timestep;
+pub mod transform;
+pub mod tweak;
 
 use std::fmt;
 use std::time::Instant;
 
 use glam::DVec2;
-use noise::{NoiseFn, Perlin, Seedable};
-use once_cell::sync::Lazy;
 use processing::errors::ProcessingErr;
 use processing::Screen;
-use rand::random;
-use wrapped2d::b2;
 use wrapped2d::user_data::UserDataTypes;
 
+use crate::box2d::Box2dWorld;
+pub use crate::canvas::{Canvas, DrawState};
+use crate::color::Color;
+use crate::fbm::Fbm;
+use crate::headless::HeadlessOptions;
 use crate::math::*;
+use crate::pixels::PixelBuffer;
+use crate::record::Recorder;
+use crate::text::{TextAlign, TextBaseline};
+use crate::timestep::FixedTimestep;
+
+/// Sketch that draws to any canvas, so it can run in a window or headless
+pub trait Sketch {
+    /// Called once before the first frame, for drawing that should only happen once
+    fn setup<C: Canvas>(&mut self, _canvas: &mut C) -> Result<(), ProcessingErr> {
+        Ok(())
+    }
+
+    /// Called once per frame, `dt` is fixed when running headless
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, dt: f64) -> Result<(), ProcessingErr>;
+}
+
+/// Sketch whose simulation runs at a fixed rate, independent of the frame rate
+pub trait FixedSketch {
+    /// Called once before the first frame, for drawing that should only happen once
+    fn setup<C: Canvas>(&mut self, _canvas: &mut C) -> Result<(), ProcessingErr> {
+        Ok(())
+    }
+
+    /// Advances the simulation by one fixed step
+    fn update(&mut self, dt: f64);
+
+    /// Called once per frame with the interpolation alpha
+    /// between the previous and current simulation step
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, alpha: f64) -> Result<(), ProcessingErr>;
+}
+
+/// Sketch that owns a Box2D world
+pub trait B2dSketch {
+    type UserData: UserDataTypes;
+
+    fn world(&mut self) -> &mut Box2dWorld<Self::UserData>;
+
+    /// Called once before the first frame, for drawing that should only happen once
+    fn setup<C: Canvas>(&mut self, _canvas: &mut C) -> Result<(), ProcessingErr> {
+        Ok(())
+    }
+
+    /// Called once per frame after the world has been stepped
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, dt: f64) -> Result<(), ProcessingErr>;
+}
+
+/// Runs a `FixedSketch` as a `Sketch`, splitting each frame's time into fixed steps
+#[derive(Debug)]
+pub struct FixedSteps<S> {
+    timestep: FixedTimestep,
+    sketch: S,
+}
+
+impl<S> FixedSteps<S> {
+    pub fn new(timestep: FixedTimestep, sketch: S) -> Self {
+        Self { timestep, sketch }
+    }
+
+    pub fn sketch(&self) -> &S {
+        &self.sketch
+    }
+}
+
+impl<S: FixedSketch> Sketch for FixedSteps<S> {
+    fn setup<C: Canvas>(&mut self, canvas: &mut C) -> Result<(), ProcessingErr> {
+        self.sketch.setup(canvas)
+    }
+
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, dt: f64) -> Result<(), ProcessingErr> {
+        for _ in 0..self.timestep.advance(dt) {
+            self.sketch.update(self.timestep.step());
+        }
+
+        self.sketch.draw(canvas, self.timestep.alpha())
+    }
+}
+
+/// Runs a `B2dSketch` as a `Sketch`, stepping the world at its fixed rate before each draw
+#[derive(Debug)]
+pub struct WorldSteps<S>(pub S);
+
+impl<S: B2dSketch> Sketch for WorldSteps<S> {
+    fn setup<C: Canvas>(&mut self, canvas: &mut C) -> Result<(), ProcessingErr> {
+        self.0.setup(canvas)
+    }
+
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, dt: f64) -> Result<(), ProcessingErr> {
+        self.0.world().step(dt);
+
+        self.0.draw(canvas, dt)
+    }
+}
+
+/// Runs the sketch in a window, or headless if that was asked for, see `core::headless`
+pub fn run_sketch(width: u32, height: u32, mut sketch: impl Sketch) -> Result<(), ProcessingErr> {
+    if let Some(options) = HeadlessOptions::from_args() {
+        let mut canvas = headless::create_canvas(width, height)?;
+        sketch.setup(&mut canvas)?;
+
+        return headless::run(
+            options.frames,
+            options.output,
+            || Ok(canvas),
+            |canvas, dt| sketch.draw(canvas, dt),
+        );
+    }
 
+    let mut screen = create_canvas(width, height)?;
+    sketch.setup(&mut screen)?;
+
+    run(|| Ok(screen), |screen, dt| sketch.draw(screen, dt))
+}
+
+/// Runs the simulation at a fixed rate in a window, or headless if that was asked for
+pub fn run_sketch_fixed(
+    width: u32,
+    height: u32,
+    timestep: FixedTimestep,
+    sketch: impl FixedSketch,
+) -> Result<(), ProcessingErr> {
+    run_sketch(width, height, FixedSteps::new(timestep, sketch))
+}
+
+/// Runs a Box2D sketch in a window, or headless if that was asked for
+pub fn b2d_run_sketch(
+    width: u32,
+    height: u32,
+    sketch: impl B2dSketch,
+) -> Result<(), ProcessingErr> {
+    run_sketch(width, height, WorldSteps(sketch))
+}
+
+/// Runs the sketch in a window only, see `run_sketch` for one that can also run headless
 pub fn run<'a, S, D>(setup: S, mut draw: D) -> Result<(), ProcessingErr>
 where
     S: FnOnce() -> Result<Screen<'a>, ProcessingErr>,
     D: FnMut(&mut Screen, f64) -> Result<(), ProcessingErr>,
 {
+    headless::warn_window_only();
+
     let mut screen = setup()?;
+    let mut recorder = Recorder::from_args();
 
     let mut prev = Instant::now();
     loop {
         input::update(&mut screen);
 
-        screen.reset_matrix();
+        Canvas::reset_matrix(&mut screen);
 
         let now = Instant::now();
         draw(&mut screen, (Instant::now() - prev).as_secs_f64())?;
         prev = now;
 
         screen.reveal()?;
+
+        if record_frame(&mut recorder, &mut screen)? {
+            return Ok(());
+        }
     }
 }
 
-pub fn b2d_run<'a, S, D, U>(setup: S, mut draw: D) -> Result<(), ProcessingErr>
+/// Runs the simulation at a fixed rate, independent of the frame rate
+///
+/// `update` is called zero or more times per frame with the fixed step
+/// and `draw` is called once per frame with the interpolation alpha
+/// between the previous and current simulation step
+///
+/// Runs in a window only, see `run_sketch_fixed` for one that can also run headless
+pub fn run_fixed<'a, S, U, D>(
+    mut timestep: FixedTimestep,
+    setup: S,
+    mut update: U,
+    mut draw: D,
+) -> Result<(), ProcessingErr>
 where
-    S: FnOnce() -> Result<(Screen<'a>, b2::World<U>), ProcessingErr>,
-    D: FnMut(&mut Screen, &mut b2::World<U>, f64) -> Result<(), ProcessingErr>,
-    U: UserDataTypes,
+    S: FnOnce() -> Result<Screen<'a>, ProcessingErr>,
+    U: FnMut(&Screen, f64),
+    D: FnMut(&mut Screen, f64) -> Result<(), ProcessingErr>,
 {
-    let (mut screen, mut world) = setup()?;
+    headless::warn_window_only();
+
+    let mut screen = setup()?;
+    let mut recorder = Recorder::from_args();
 
     let mut prev = Instant::now();
     loop {
         input::update(&mut screen);
 
-        {
-            let time_step = 1.0 / 60.0;
-            world.step(time_step, 10, 8);
-            world.clear_forces();
+        let now = Instant::now();
+        for _ in 0..timestep.advance((now - prev).as_secs_f64()) {
+            update(&screen, timestep.step());
         }
+        prev = now;
+
+        Canvas::reset_matrix(&mut screen);
+
+        draw(&mut screen, timestep.alpha())?;
+
+        screen.reveal()?;
 
-        screen.reset_matrix();
+        if record_frame(&mut recorder, &mut screen)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Runs a Box2D scene, stepping the world at its fixed rate before each draw
+///
+/// Runs in a window only, see `b2d_run_sketch` for one that can also run headless
+pub fn b2d_run<'a, S, D, U>(setup: S, mut draw: D) -> Result<(), ProcessingErr>
+where
+    S: FnOnce() -> Result<(Screen<'a>, Box2dWorld<U>), ProcessingErr>,
+    D: FnMut(&mut Screen, &mut Box2dWorld<U>, f64) -> Result<(), ProcessingErr>,
+    U: UserDataTypes,
+{
+    headless::warn_window_only();
+
+    let (mut screen, mut world) = setup()?;
+    let mut recorder = Recorder::from_args();
+
+    let mut prev = Instant::now();
+    loop {
+        input::update(&mut screen);
 
         let now = Instant::now();
-        draw(
-            &mut screen,
-            &mut world,
-            (Instant::now() - prev).as_secs_f64(),
-        )?;
+        let dt = (now - prev).as_secs_f64();
         prev = now;
 
+        world.step(dt);
+
+        Canvas::reset_matrix(&mut screen);
+
+        draw(&mut screen, &mut world, dt)?;
+
         screen.reveal()?;
+
+        if record_frame(&mut recorder, &mut screen)? {
+            return Ok(());
+        }
     }
 }
 
@@ -75,103 +279,162 @@ pub fn create_canvas<'a>(width: u32, height: u32) -> Result<Screen<'a>, Processi
     Ok(screen)
 }
 
-pub fn translate(screen: &mut Screen, x: f64, y: f64) {
-    let (x, y) = device_to_screen(screen, x, y);
+pub fn translate(canvas: &mut impl Canvas, x: f64, y: f64) {
+    canvas.translate(x, y);
+}
 
-    screen.translate(1.0 + x as f32, -1.0 + y as f32, 0.0);
+pub fn translatev(canvas: &mut impl Canvas, p: DVec2) {
+    canvas.translate(p.x, p.y);
 }
 
-pub fn translatev(screen: &mut Screen, p: DVec2) {
-    let (x, y) = device_to_screen(screen, p.x, p.y);
+pub fn rotate(canvas: &mut impl Canvas, angle: f64) {
+    canvas.rotate(angle);
+}
+
+pub fn scale(canvas: &mut impl Canvas, sx: f64, sy: f64) {
+    canvas.scale(sx, sy);
+}
 
-    screen.translate(1.0 + x as f32, -1.0 + y as f32, 0.0);
+pub fn shear_x(canvas: &mut impl Canvas, angle: f64) {
+    canvas.shear_x(angle);
 }
 
-pub fn rotate(screen: &mut Screen, angle: f64) {
-    screen.rotate_z(angle as f32);
+pub fn shear_y(canvas: &mut impl Canvas, angle: f64) {
+    canvas.shear_y(angle);
+}
+
+pub fn push_matrix(canvas: &mut impl Canvas) {
+    canvas.push_matrix();
+}
+
+pub fn pop_matrix(canvas: &mut impl Canvas) {
+    canvas.pop_matrix();
 }
 
 /* background */
 
-pub fn background_grayscale(screen: &mut Screen, v: f32) {
-    background_rgb(screen, v, v, v);
+pub fn background_grayscale(canvas: &mut impl Canvas, v: f32) {
+    background_rgb(canvas, v, v, v);
 }
 
-pub fn background_rgb(screen: &mut Screen, r: f32, g: f32, b: f32) {
-    screen.background(r / 255.0, g / 255.0, b / 255.0, 1.0);
+pub fn background_rgb(canvas: &mut impl Canvas, r: f32, g: f32, b: f32) {
+    canvas.background(r, g, b, 255.0);
+}
+
+pub fn background(canvas: &mut impl Canvas, color: Color) {
+    canvas.background(color.r, color.g, color.b, color.a);
 }
 
 /* stroke */
 
-pub fn stroke_grayscale(screen: &mut Screen, v: f32) {
-    stroke_rgb(screen, v, v, v);
+pub fn stroke_grayscale(canvas: &mut impl Canvas, v: f32) {
+    stroke_rgb(canvas, v, v, v);
+}
+
+pub fn stroke_grayscale_alpha(canvas: &mut impl Canvas, v: f32, a: f32) {
+    stroke_rgba(canvas, v, v, v, a);
+}
+
+pub fn stroke_rgb(canvas: &mut impl Canvas, r: f32, g: f32, b: f32) {
+    stroke_rgba(canvas, r, g, b, 255.0);
+}
+
+pub fn stroke_rgba(canvas: &mut impl Canvas, r: f32, g: f32, b: f32, a: f32) {
+    canvas.stroke(r, g, b, a);
 }
 
-pub fn stroke_grayscale_alpha(screen: &mut Screen, v: f32, a: f32) {
-    stroke_rgba(screen, v, v, v, a);
+pub fn stroke(canvas: &mut impl Canvas, color: Color) {
+    canvas.stroke(color.r, color.g, color.b, color.a);
 }
 
-pub fn stroke_rgb(screen: &mut Screen, r: f32, g: f32, b: f32) {
-    stroke_rgba(screen, r, g, b, 255.0);
+pub fn stroke_off(canvas: &mut impl Canvas) {
+    canvas.stroke_off();
 }
 
-pub fn stroke_rgba(screen: &mut Screen, r: f32, g: f32, b: f32, a: f32) {
-    screen.fill(&[r / 255.0], &[g / 255.0], &[b / 255.0], &[a / 255.0]);
+pub fn stroke_weight(canvas: &mut impl Canvas, weight: f64) {
+    canvas.stroke_weight(weight);
 }
 
 /* fill */
 
-pub fn fill_grayscale(screen: &mut Screen, v: f32) {
-    fill_rgb(screen, v, v, v);
+pub fn fill_grayscale(canvas: &mut impl Canvas, v: f32) {
+    fill_rgb(canvas, v, v, v);
+}
+
+pub fn fill_grayscale_alpha(canvas: &mut impl Canvas, v: f32, a: f32) {
+    fill_rgba(canvas, v, v, v, a);
 }
 
-pub fn fill_grayscale_alpha(screen: &mut Screen, v: f32, a: f32) {
-    fill_rgba(screen, v, v, v, a);
+pub fn fill_rgb(canvas: &mut impl Canvas, r: f32, g: f32, b: f32) {
+    fill_rgba(canvas, r, g, b, 255.0);
 }
 
-pub fn fill_rgb(screen: &mut Screen, r: f32, g: f32, b: f32) {
-    fill_rgba(screen, r, g, b, 255.0);
+pub fn fill_rgba(canvas: &mut impl Canvas, r: f32, g: f32, b: f32, a: f32) {
+    canvas.fill(r, g, b, a);
 }
 
-pub fn fill_rgba(screen: &mut Screen, r: f32, g: f32, b: f32, a: f32) {
-    screen.fill(&[r / 255.0], &[g / 255.0], &[b / 255.0], &[a / 255.0]);
+pub fn fill(canvas: &mut impl Canvas, color: Color) {
+    canvas.fill(color.r, color.g, color.b, color.a);
+}
+
+pub fn fill_off(canvas: &mut impl Canvas) {
+    canvas.fill_off();
 }
 
 /* text */
 
+pub fn text_size(canvas: &mut impl Canvas, size: f64) {
+    canvas.text_size(size);
+}
+
+pub fn text_align(canvas: &mut impl Canvas, align: TextAlign, baseline: TextBaseline) {
+    canvas.text_align(align, baseline);
+}
+
 pub fn text(
-    screen: &mut Screen,
+    canvas: &mut impl Canvas,
     text: impl AsRef<str>,
     x: f64,
     y: f64,
 ) -> Result<(), ProcessingErr> {
-    let (_x, _y) = device_to_screen(screen, x, y);
-
-    //screen.text(text.as_ref(), &[x], &[y])
-    println!("{}", text.as_ref());
-    Ok(())
+    canvas.text(text.as_ref(), x, y)
 }
 
 /* image */
 
-pub fn image(
-    screen: &mut Screen,
+pub fn image<C: Canvas>(
+    canvas: &mut C,
     x: f64,
     y: f64,
-    texture: &processing::Texture2d,
+    texture: &C::Image,
 ) -> Result<(), ProcessingErr> {
-    let (x, y) = device_to_screen(screen, x, y);
-    let (w, h) = device_to_screen_size(screen, texture.width() as f64, texture.height() as f64);
+    canvas.image(x, y, texture)
+}
 
-    let mut rect = processing::shapes::rect::Rect::new(screen, &[x], &[y], &[0.0], &[w], &[h])?;
-    rect.attach_texture(texture);
-    screen.draw(&rect)
+/* pixels */
+
+pub fn load_pixels(canvas: &mut impl Canvas) -> Result<PixelBuffer, ProcessingErr> {
+    canvas.load_pixels()
+}
+
+pub fn update_pixels(canvas: &mut impl Canvas, pixels: &PixelBuffer) -> Result<(), ProcessingErr> {
+    canvas.update_pixels(pixels)
+}
+
+pub fn create_image<C: Canvas>(
+    canvas: &mut C,
+    pixels: &PixelBuffer,
+) -> Result<C::Image, ProcessingErr> {
+    canvas.create_image(pixels)
 }
 
 /* blend mode */
 
+/// How drawn colors are combined with the pixels already on the canvas
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub enum BlendMode {
     Replace,
+    #[default]
     Blend,
     Add,
     Subtract,
@@ -198,35 +461,47 @@ impl fmt::Display for BlendMode {
     }
 }
 
+pub fn blend_mode(canvas: &mut impl Canvas, mode: BlendMode) {
+    canvas.blend_mode(mode);
+}
+
 /* noise */
 
-#[allow(dead_code)]
-static PERLIN_NOISE: Lazy<Perlin> = Lazy::new(|| Perlin::default().set_seed(random()));
+// single octave, see `fbm::Fbm` for fractal noise
 
 /// Output range [-1..1]
 pub fn noise(point: f64, frequency: f64) -> f64 {
-    let point = [point * frequency, 0.0];
-    PERLIN_NOISE.get(point)
+    Fbm::new().octaves(1).frequency(frequency).get(point)
 }
 
 /// Output range [-1..1]
 pub fn noise2d(point: [f64; 2], frequency: f64) -> f64 {
-    let point = [point[0] * frequency, point[1] * frequency];
-    PERLIN_NOISE.get(point)
+    Fbm::new().octaves(1).frequency(frequency).get2d(point)
 }
 
 /// Output range [-1..1]
 pub fn noise3d(point: [f64; 3], frequency: f64) -> f64 {
-    let point = [
-        point[0] * frequency,
-        point[1] * frequency,
-        point[2] * frequency,
-    ];
-    PERLIN_NOISE.get(point)
+    Fbm::new().octaves(1).frequency(frequency).get3d(point)
 }
 
 /* internal utils */
 
+// captures the revealed frame if recording, returns true once the recording is done
+fn record_frame(
+    recorder: &mut Option<Recorder>,
+    screen: &mut Screen,
+) -> Result<bool, ProcessingErr> {
+    if let Some(recorder) = recorder {
+        recorder.capture_screen(screen)?;
+        if recorder.is_finished() {
+            recorder.finish()?;
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
 pub(crate) fn device_to_screen(screen: &Screen, x: f64, y: f64) -> (f64, f64) {
     (
         map(x, 0.0, screen.width() as f64, -1.0, 1.0),
@@ -240,67 +515,3 @@ pub(crate) fn device_to_screen_size(screen: &Screen, w: f64, h: f64) -> (f64, f6
         h / (screen.height() as f64 / 2.0),
     )
 }
-
-/* Box2D utils */
-
-// https://github.com/shiffman/Box2D-for-Processing/blob/master/Box2D-for-Processing/src/shiffman/box2d/Box2DProcessing.java
-
-const SCALE_FACTOR: f64 = 10.0;
-const Y_FLIP: bool = true;
-
-pub fn coord_world_to_pixels(screen: &Screen, wx: f64, wy: f64) -> b2::Vec2 {
-    let hw = screen.width() as f64 / 2.0;
-    let hh = screen.height() as f64 / 2.0;
-
-    let px = map(wx, 0.0, 1.0, hw, hw + SCALE_FACTOR);
-
-    let mut py = map(wy, 0.0, 1.0, hh, hh + SCALE_FACTOR);
-    if Y_FLIP {
-        py = map(py, 0.0, screen.height() as f64, screen.height() as f64, 0.0);
-    }
-
-    b2::Vec2 {
-        x: px as f32,
-        y: py as f32,
-    }
-}
-
-pub fn vector_world_to_pixels(screen: &Screen, v: b2::Vec2) -> b2::Vec2 {
-    coord_world_to_pixels(screen, v.x as f64, v.y as f64)
-}
-
-pub fn scalar_world_to_pixels(val: f64) -> f64 {
-    val * SCALE_FACTOR
-}
-
-pub fn get_body_pixel_coord(screen: &Screen, b: &b2::Body) -> b2::Vec2 {
-    let xf = b.transform();
-    coord_world_to_pixels(screen, xf.pos.x as f64, xf.pos.y as f64)
-}
-
-pub fn coord_pixels_to_world(screen: &Screen, px: f64, py: f64) -> b2::Vec2 {
-    let hw = screen.width() as f64 / 2.0;
-    let hh = screen.height() as f64 / 2.0;
-
-    let wx = map(px, hw, hw + SCALE_FACTOR, 0.0, 1.0);
-
-    let mut wy = py;
-    if Y_FLIP {
-        wy = map(py, screen.height() as f64, 0.0, 0.0, screen.height() as f64);
-    }
-
-    wy = map(wy, hh, hh + SCALE_FACTOR, 0.0, 1.0);
-
-    b2::Vec2 {
-        x: wx as f32,
-        y: wy as f32,
-    }
-}
-
-pub fn vector_pixels_to_world(screen: &Screen, v: b2::Vec2) -> b2::Vec2 {
-    coord_pixels_to_world(screen, v.x as f64, v.y as f64)
-}
-
-pub fn scalar_pixels_to_world(val: f64) -> f64 {
-    val / SCALE_FACTOR
-}
@@ -0,0 +1,257 @@
+use glam::DVec2;
+
+use crate::math::clampf;
+use crate::Canvas;
+
+/// `dt` for sketches whose forces were tuned per update rather than per second
+pub const UNIT_STEP: f64 = 1.0;
+
+/// How a mover's velocity and location are advanced each update
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Integrator {
+    /// Location moves with the old velocity
+    ExplicitEuler,
+
+    /// Location moves with the updated velocity,
+    /// what the examples have always done and stable for springs and orbits
+    #[default]
+    SemiImplicitEuler,
+
+    /// Velocity Verlet, assuming the acceleration is constant over the step
+    Verlet,
+}
+
+/// What happens when a mover leaves the canvas
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EdgePolicy {
+    /// Leave the canvas freely
+    #[default]
+    Ignore,
+
+    /// Reappear on the opposite edge
+    Wrap,
+
+    /// Stop at the edge, keeping velocity
+    Stop,
+
+    /// Stop at the edge and reflect velocity
+    Bounce,
+}
+
+/// Point mass with linear and angular state
+///
+/// Forces are accumulated with `apply_force` and cleared by `update`
+#[derive(Debug, Default, Clone)]
+pub struct Mover {
+    pub location: DVec2,
+    pub velocity: DVec2,
+    pub acceleration: DVec2,
+    pub mass: f64,
+
+    pub angle: f64,
+    pub angular_velocity: f64,
+    pub angular_acceleration: f64,
+
+    /// Distance from the location to the edge of the body, used for edge checks
+    pub radius: f64,
+
+    pub max_speed: Option<f64>,
+    pub max_angular_speed: Option<f64>,
+
+    pub integrator: Integrator,
+    pub edge_policy: EdgePolicy,
+
+    prev_location: DVec2,
+    prev_angle: f64,
+}
+
+impl Mover {
+    pub fn new(mass: f64, x: f64, y: f64) -> Self {
+        let location = DVec2::new(x, y);
+
+        Self {
+            location,
+            mass,
+            prev_location: location,
+            ..Default::default()
+        }
+    }
+
+    /* forces */
+
+    /// Accumulates a force, scaled by mass
+    pub fn apply_force(&mut self, force: DVec2) {
+        let force = if self.mass > 0.0 {
+            force / self.mass
+        } else {
+            force
+        };
+        self.acceleration += force;
+    }
+
+    /// Accumulates a rotational force, scaled by mass
+    pub fn apply_torque(&mut self, torque: f64) {
+        let torque = if self.mass > 0.0 {
+            torque / self.mass
+        } else {
+            torque
+        };
+        self.angular_acceleration += torque;
+    }
+
+    /// Friction opposing the velocity with the given coefficient
+    pub fn friction(&self, c: f64) -> DVec2 {
+        -self.velocity.normalize_or_zero() * c
+    }
+
+    /// Fluid drag opposing the velocity, proportional to speed squared
+    pub fn drag(&self, c: f64) -> DVec2 {
+        let speed = self.velocity.length();
+        -self.velocity.normalize_or_zero() * c * speed * speed
+    }
+
+    /// Gravitational attraction this mover exerts on another
+    ///
+    /// The distance is clamped so close bodies don't fling each other away
+    pub fn attraction(&self, other: &Mover, g: f64, min_distance: f64, max_distance: f64) -> DVec2 {
+        let force = self.location - other.location;
+        let distance = clampf(force.length(), min_distance, max_distance);
+        let strength = (g * self.mass * other.mass) / (distance * distance);
+
+        force.normalize_or_zero() * strength
+    }
+
+    /* state */
+
+    /// Unit vector the mover is facing
+    pub fn heading(&self) -> DVec2 {
+        DVec2::new(self.angle.cos(), self.angle.sin())
+    }
+
+    /// Location between the previous and current update, for fixed-step rendering
+    pub fn interpolated_location(&self, alpha: f64) -> DVec2 {
+        self.prev_location.lerp(self.location, alpha)
+    }
+
+    /// Angle between the previous and current update, for fixed-step rendering
+    pub fn interpolated_angle(&self, alpha: f64) -> f64 {
+        self.prev_angle + (self.angle - self.prev_angle) * alpha
+    }
+
+    /// Integrates the accumulated forces over dt and clears them
+    ///
+    /// Sketches whose forces were tuned per update pass `UNIT_STEP`,
+    /// running on a fixed timestep keeps them independent of the frame rate
+    pub fn update(&mut self, dt: f64) {
+        self.prev_location = self.location;
+        self.prev_angle = self.angle;
+
+        match self.integrator {
+            Integrator::ExplicitEuler => {
+                self.location += self.velocity * dt;
+                self.velocity += self.acceleration * dt;
+            }
+            Integrator::SemiImplicitEuler => {
+                self.velocity += self.acceleration * dt;
+                self.location += self.velocity * dt;
+            }
+            Integrator::Verlet => {
+                self.location += self.velocity * dt + 0.5 * self.acceleration * dt * dt;
+                self.velocity += self.acceleration * dt;
+            }
+        }
+
+        if let Some(max_speed) = self.max_speed {
+            self.velocity = self.velocity.clamp_length_max(max_speed);
+        }
+
+        self.angular_velocity += self.angular_acceleration * dt;
+        if let Some(max_angular_speed) = self.max_angular_speed {
+            self.angular_velocity =
+                clampf(self.angular_velocity, -max_angular_speed, max_angular_speed);
+        }
+        self.angle += self.angular_velocity * dt;
+
+        self.acceleration = DVec2::default();
+        self.angular_acceleration = 0.0;
+    }
+
+    /* edges */
+
+    /// Applies the edge policy against the canvas bounds
+    pub fn check_edges(&mut self, canvas: &impl Canvas) {
+        self.check_bounds(
+            DVec2::default(),
+            DVec2::new(canvas.width() as f64, canvas.height() as f64),
+        );
+    }
+
+    /// Applies the edge policy against arbitrary bounds
+    pub fn check_bounds(&mut self, min: DVec2, max: DVec2) {
+        match self.edge_policy {
+            EdgePolicy::Ignore => (),
+            EdgePolicy::Wrap => self.wrap_edges(min, max),
+            EdgePolicy::Stop => {
+                self.stop_edges(min, max);
+            }
+            EdgePolicy::Bounce => {
+                let (x, y) = self.stop_edges(min, max);
+                if x {
+                    self.velocity.x *= -1.0;
+                }
+                if y {
+                    self.velocity.y *= -1.0;
+                }
+            }
+        }
+    }
+
+    fn wrap_edges(&mut self, min: DVec2, max: DVec2) {
+        let r = self.radius;
+        let location = self.location;
+
+        // wrap once the body is fully off screen
+        if self.location.x - r > max.x {
+            self.location.x = min.x - r;
+        } else if self.location.x + r < min.x {
+            self.location.x = max.x + r;
+        }
+
+        if self.location.y - r > max.y {
+            self.location.y = min.y - r;
+        } else if self.location.y + r < min.y {
+            self.location.y = max.y + r;
+        }
+
+        // don't interpolate across the screen
+        if self.location != location {
+            self.prev_location = self.location;
+        }
+    }
+
+    // returns which axes hit an edge
+    fn stop_edges(&mut self, min: DVec2, max: DVec2) -> (bool, bool) {
+        let lo = min + DVec2::splat(self.radius);
+        let hi = max - DVec2::splat(self.radius);
+
+        let mut hit = (false, false);
+
+        if self.location.x > hi.x {
+            self.location.x = hi.x;
+            hit.0 = true;
+        } else if self.location.x < lo.x {
+            self.location.x = lo.x;
+            hit.0 = true;
+        }
+
+        if self.location.y > hi.y {
+            self.location.y = hi.y;
+            hit.1 = true;
+        } else if self.location.y < lo.y {
+            self.location.y = lo.y;
+            hit.1 = true;
+        }
+
+        hit
+    }
+}
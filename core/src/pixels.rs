@@ -0,0 +1,151 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use image::{ImageError, Rgba, RgbaImage};
+use processing::errors::ProcessingErr;
+use processing::Screen;
+
+use crate::color::Color;
+
+/// CPU side RGBA image that can be written per pixel
+///
+/// Read from a canvas with `load_pixels` and drawn
+/// with `update_pixels` or `create_image` and `image`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PixelBuffer {
+    image: RgbaImage,
+}
+
+impl PixelBuffer {
+    /// Transparent buffer
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            image: RgbaImage::new(width, height),
+        }
+    }
+
+    pub fn from_image(image: RgbaImage) -> Self {
+        Self { image }
+    }
+
+    /// Reads an image file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ProcessingErr> {
+        let image = image::open(path)
+            .map_err(|err| ProcessingErr::ImageNotFound(io::Error::other(err)))?
+            .to_rgba8();
+
+        Ok(Self { image })
+    }
+
+    /// Buffer with each pixel set from its coordinates
+    pub fn from_fn(width: u32, height: u32, f: impl FnMut(u32, u32) -> Color) -> Self {
+        let mut pixels = Self::new(width, height);
+        pixels.fill_with(f);
+        pixels
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width() && y < self.height()
+    }
+
+    /// Color of the pixel, transparent if it's outside the buffer
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        if !self.contains(x, y) {
+            return Color::TRANSPARENT;
+        }
+
+        let [r, g, b, a] = self.image.get_pixel(x, y).0;
+        Color::rgba(r as f32, g as f32, b as f32, a as f32)
+    }
+
+    /// Sets the pixel, ignoring pixels outside the buffer
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        if self.contains(x, y) {
+            self.image.put_pixel(x, y, to_rgba(color));
+        }
+    }
+
+    /// Sets every pixel from its coordinates
+    pub fn fill_with(&mut self, mut f: impl FnMut(u32, u32) -> Color) {
+        for (x, y, pixel) in self.image.enumerate_pixels_mut() {
+            *pixel = to_rgba(f(x, y));
+        }
+    }
+
+    /// RGBA bytes in rows from the top left
+    pub fn raw(&self) -> &[u8] {
+        &self.image
+    }
+
+    pub fn raw_mut(&mut self) -> &mut [u8] {
+        &mut self.image
+    }
+
+    pub fn as_image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ProcessingErr> {
+        self.image.save(path).map_err(image_not_saved)
+    }
+}
+
+/* processing utils */
+
+// processing can only save screenshots and load textures through files,
+// so pixels go through a temporary PNG on the way in and out
+
+/// Reads back the revealed screen contents
+pub(crate) fn read_screen(screen: &mut Screen) -> Result<RgbaImage, ProcessingErr> {
+    let path = temp_path("screen");
+    screen.save(&path.to_string_lossy())?;
+
+    let image = image::open(&path)
+        .map_err(|err| ProcessingErr::ImageNotFound(io::Error::other(err)))?
+        .to_rgba8();
+    fs::remove_file(&path).ok();
+
+    Ok(image)
+}
+
+/// Uploads the pixels as a texture
+pub(crate) fn create_texture(
+    screen: &mut Screen,
+    pixels: &PixelBuffer,
+) -> Result<processing::Texture2d, ProcessingErr> {
+    let path = temp_path("pixels");
+    pixels.save(&path)?;
+
+    let image = processing::load_image(&path.to_string_lossy());
+    fs::remove_file(&path).ok();
+
+    let (texture, _, _) = screen.texture(&image?)?;
+
+    Ok(texture)
+}
+
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("noc-{}-{}.png", name, std::process::id()))
+}
+
+pub(crate) fn image_not_saved(err: ImageError) -> ProcessingErr {
+    ProcessingErr::ImageNotSaved(io::Error::other(err))
+}
+
+fn to_rgba(color: Color) -> Rgba<u8> {
+    Rgba([color.r, color.g, color.b, color.a].map(|v| v.round().clamp(0.0, 255.0) as u8))
+}
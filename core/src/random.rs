@@ -0,0 +1,101 @@
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use noise::{Perlin, Seedable};
+use once_cell::sync::Lazy;
+use rand::rngs::StdRng;
+use rand::{Error, RngCore, SeedableRng};
+
+/// Environment variable used to seed a run
+pub const SEED_ENV: &str = "NOC_SEED";
+
+/// Command line flag used to seed a run, takes precedence over the environment
+pub const SEED_FLAG: &str = "--seed";
+
+static SEED: Lazy<AtomicU64> = Lazy::new(|| AtomicU64::new(initial_seed()));
+
+static RNG: Lazy<Mutex<StdRng>> = Lazy::new(|| Mutex::new(StdRng::seed_from_u64(seed())));
+
+pub(crate) static PERLIN_NOISE: Lazy<Mutex<Perlin>> =
+    Lazy::new(|| Mutex::new(Perlin::default().set_seed(noise_seed(seed()))));
+
+/// Handle to the shared seeded RNG
+///
+/// Drop-in replacement for `rand::thread_rng()`,
+/// every handle draws from the same stream
+#[derive(Debug, Default, Copy, Clone)]
+pub struct CoreRng;
+
+impl RngCore for CoreRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.lock().unwrap().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.lock().unwrap().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.lock().unwrap().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        RNG.lock().unwrap().try_fill_bytes(dest)
+    }
+}
+
+/// Returns a handle to the shared seeded RNG
+pub fn rng() -> CoreRng {
+    CoreRng
+}
+
+/// The seed the current run was started with
+pub fn seed() -> u64 {
+    SEED.load(Ordering::Relaxed)
+}
+
+/// Reseeds both the RNG and the noise
+pub fn set_seed(seed: u64) {
+    SEED.store(seed, Ordering::Relaxed);
+
+    *RNG.lock().unwrap() = StdRng::seed_from_u64(seed);
+    *PERLIN_NOISE.lock().unwrap() = Perlin::default().set_seed(noise_seed(seed));
+}
+
+/* internal utils */
+
+fn initial_seed() -> u64 {
+    let mut args = env::args();
+    let arg = args.find(|arg| arg == SEED_FLAG).and_then(|_| args.next());
+
+    let seed = arg
+        .or_else(|| env::var(SEED_ENV).ok())
+        .and_then(|seed| match seed.trim().parse() {
+            Ok(seed) => Some(seed),
+            Err(err) => {
+                eprintln!("Ignoring invalid seed '{}': {}", seed, err);
+                None
+            }
+        });
+
+    match seed {
+        Some(seed) => seed,
+        None => {
+            let seed = rand::random();
+
+            // print it so the run can be reproduced
+            eprintln!(
+                "Random seed: {} (set {} or pass {} to reuse it)",
+                seed, SEED_ENV, SEED_FLAG
+            );
+
+            seed
+        }
+    }
+}
+
+// noise only takes a 32-bit seed
+fn noise_seed(seed: u64) -> u32 {
+    (seed ^ (seed >> 32)) as u32
+}
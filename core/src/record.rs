@@ -0,0 +1,211 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use image::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use processing::errors::ProcessingErr;
+use processing::Screen;
+
+use crate::pixels::{image_not_saved, read_screen};
+
+/// Environment variable holding the recording output path
+pub const RECORD_ENV: &str = "NOC_RECORD";
+
+/// Command line flag holding the recording output path, takes precedence over the environment
+pub const RECORD_FLAG: &str = "--record";
+
+/// Environment variable holding the recorded frame range as `start..end`
+pub const FRAMES_ENV: &str = "NOC_RECORD_FRAMES";
+
+/// Command line flag holding the recorded frame range as `start..end`
+pub const FRAMES_FLAG: &str = "--record-frames";
+
+/// Frames recorded when no range is given, 3 seconds at 60fps
+pub const DEFAULT_FRAMES: Range<usize> = 0..180;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Single looping animated GIF
+    Gif,
+
+    /// Directory of numbered PNG files
+    PngSequence,
+}
+
+impl RecordFormat {
+    /// Outputs ending in .gif are recorded as a GIF, anything else is a PNG directory
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("gif") => Self::Gif,
+            _ => Self::PngSequence,
+        }
+    }
+}
+
+/// Captures a range of frames to an animated GIF or a PNG sequence
+pub struct Recorder {
+    output: PathBuf,
+    format: RecordFormat,
+    frames: Range<usize>,
+    frame_delay: Delay,
+
+    frame: usize,
+    encoder: Option<GifEncoder<BufWriter<File>>>,
+}
+
+impl Recorder {
+    pub fn new(output: impl Into<PathBuf>, frames: Range<usize>) -> Self {
+        let output = output.into();
+
+        Self {
+            format: RecordFormat::from_path(&output),
+            output,
+            frames,
+            frame_delay: Delay::from_numer_denom_ms(1000, 60),
+
+            frame: 0,
+            encoder: None,
+        }
+    }
+
+    /// Creates a recorder from the command line or environment,
+    /// returns None if recording wasn't asked for
+    pub fn from_args() -> Option<Self> {
+        let output = arg(RECORD_FLAG).or_else(|| env::var(RECORD_ENV).ok())?;
+
+        let frames = arg(FRAMES_FLAG)
+            .or_else(|| env::var(FRAMES_ENV).ok())
+            .and_then(|frames| match parse_range(&frames) {
+                Some(frames) => Some(frames),
+                None => {
+                    eprintln!("Ignoring invalid frame range '{}'", frames);
+                    None
+                }
+            })
+            .unwrap_or(DEFAULT_FRAMES);
+
+        Some(Self::new(output, frames))
+    }
+
+    /// Sets how long each GIF frame is shown for, defaults to 60fps
+    pub fn with_frame_delay(mut self, numerator_ms: u32, denominator: u32) -> Self {
+        self.frame_delay = Delay::from_numer_denom_ms(numerator_ms, denominator);
+        self
+    }
+
+    pub fn format(&self) -> RecordFormat {
+        self.format
+    }
+
+    /// True once every frame in the range has been captured
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.frames.end
+    }
+
+    /// Captures the current screen contents
+    ///
+    /// Should be called once per frame after the frame is revealed
+    pub fn capture_screen(&mut self, screen: &mut Screen) -> Result<(), ProcessingErr> {
+        if !self.frames.contains(&self.frame) {
+            self.frame += 1;
+            return Ok(());
+        }
+
+        match self.format {
+            RecordFormat::Gif => {
+                let image = read_screen(screen)?;
+                self.encode_gif_frame(image)?;
+            }
+            RecordFormat::PngSequence => {
+                let path = self.frame_path()?;
+                screen.save(&path.to_string_lossy())?;
+            }
+        }
+
+        self.frame += 1;
+
+        Ok(())
+    }
+
+    /// Captures an already rendered frame, such as a headless canvas
+    pub fn capture_image(&mut self, image: &RgbaImage) -> Result<(), ProcessingErr> {
+        if !self.frames.contains(&self.frame) {
+            self.frame += 1;
+            return Ok(());
+        }
+
+        match self.format {
+            RecordFormat::Gif => self.encode_gif_frame(image.clone())?,
+            RecordFormat::PngSequence => {
+                let path = self.frame_path()?;
+                image.save(path).map_err(image_not_saved)?;
+            }
+        }
+
+        self.frame += 1;
+
+        Ok(())
+    }
+
+    /// Flushes the recording to disk
+    pub fn finish(&mut self) -> Result<(), ProcessingErr> {
+        // the GIF trailer is written when the encoder is dropped
+        self.encoder.take();
+
+        eprintln!("Recorded {}", self.output.display());
+
+        Ok(())
+    }
+
+    /* internal utils */
+
+    fn frame_path(&self) -> Result<PathBuf, ProcessingErr> {
+        fs::create_dir_all(&self.output).map_err(ProcessingErr::ImageNotSaved)?;
+
+        Ok(self.output.join(format!("{:05}.png", self.frame)))
+    }
+
+    fn encode_gif_frame(&mut self, image: RgbaImage) -> Result<(), ProcessingErr> {
+        if self.encoder.is_none() {
+            if let Some(parent) = self.output.parent() {
+                fs::create_dir_all(parent).map_err(ProcessingErr::ImageNotSaved)?;
+            }
+
+            let file = File::create(&self.output).map_err(ProcessingErr::ImageNotSaved)?;
+
+            let mut encoder = GifEncoder::new(BufWriter::new(file));
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .map_err(image_not_saved)?;
+            self.encoder = Some(encoder);
+        }
+
+        let frame = Frame::from_parts(image, 0, 0, self.frame_delay);
+        self.encoder
+            .as_mut()
+            .unwrap()
+            .encode_frame(frame)
+            .map_err(image_not_saved)
+    }
+}
+
+/// Value following a command line flag
+pub(crate) fn arg(flag: &str) -> Option<String> {
+    let mut args = env::args();
+    args.find(|arg| arg == flag).and_then(|_| args.next())
+}
+
+fn parse_range(range: &str) -> Option<Range<usize>> {
+    let (start, end) = range.split_once("..")?;
+    let start = start.trim().parse().ok()?;
+    let end = end.trim().parse().ok()?;
+
+    if start < end {
+        Some(start..end)
+    } else {
+        None
+    }
+}
@@ -2,19 +2,42 @@ use std::fmt;
 
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::shapes::ellipse::Ellipse;
-use processing::shapes::line::Line;
-use processing::shapes::point::Point;
-use processing::shapes::rect::Rect;
-use processing::shapes::triangle::Triangle;
-use processing::Screen;
 
-use crate::{device_to_screen, device_to_screen_size};
+use crate::Canvas;
 
+/// How the arguments of `rect` and `ellipse` are interpreted
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub enum RectMode {
+    /// Center point, width and height
     Center,
+
+    /// Center point and half the width and height
     Radius,
+
+    /// Top left corner, width and height
+    #[default]
     Corner,
+
+    /// Top left and bottom right corners
+    Corners,
+}
+
+impl RectMode {
+    /// Top left corner, width and height of the shape the arguments describe
+    pub fn to_corner(self, a: f64, b: f64, c: f64, d: f64) -> (f64, f64, f64, f64) {
+        match self {
+            Self::Center => (a - c / 2.0, b - d / 2.0, c, d),
+            Self::Radius => (a - c, b - d, c * 2.0, d * 2.0),
+            Self::Corner => (a, b, c, d),
+            Self::Corners => (a, b, c - a, d - b),
+        }
+    }
+
+    /// Center point, width and height of the shape the arguments describe
+    pub fn to_center(self, a: f64, b: f64, c: f64, d: f64) -> (f64, f64, f64, f64) {
+        let (x, y, w, h) = self.to_corner(a, b, c, d);
+        (x + w / 2.0, y + h / 2.0, w, h)
+    }
 }
 
 impl fmt::Display for RectMode {
@@ -22,36 +45,40 @@ impl fmt::Display for RectMode {
         match self {
             Self::Center => write!(f, "CENTER"),
             Self::Radius => write!(f, "RADIUS"),
-            Self::Corner => write!(f, "CORNERS"),
+            Self::Corner => write!(f, "CORNER"),
+            Self::Corners => write!(f, "CORNERS"),
         }
     }
 }
 
-pub fn point(screen: &mut Screen, x: f64, y: f64) -> Result<(), ProcessingErr> {
-    let (x, y) = device_to_screen(screen, x, y);
-
-    let point = Point::new(screen, &[x], &[y], &[0.0])?;
-    screen.draw(&point)
+pub fn rect_mode(canvas: &mut impl Canvas, mode: RectMode) {
+    canvas.rect_mode(mode);
 }
 
-pub fn line(screen: &mut Screen, x1: f64, y1: f64, x2: f64, y2: f64) -> Result<(), ProcessingErr> {
-    let (x1, y1) = device_to_screen(screen, x1, y1);
-    let (x2, y2) = device_to_screen(screen, x2, y2);
+pub fn ellipse_mode(canvas: &mut impl Canvas, mode: RectMode) {
+    canvas.ellipse_mode(mode);
+}
 
-    let line = Line::new(screen, &[x1], &[y1], &[0.0], &[x2], &[y2], &[0.0])?;
-    screen.draw(&line)
+pub fn point(canvas: &mut impl Canvas, x: f64, y: f64) -> Result<(), ProcessingErr> {
+    canvas.point(x, y)
 }
 
-pub fn linev(screen: &mut Screen, v1: DVec2, v2: DVec2) -> Result<(), ProcessingErr> {
-    let (x1, y1) = device_to_screen(screen, v1.x, v1.y);
-    let (x2, y2) = device_to_screen(screen, v2.x, v2.y);
+pub fn line(
+    canvas: &mut impl Canvas,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+) -> Result<(), ProcessingErr> {
+    canvas.line(x1, y1, x2, y2)
+}
 
-    let line = Line::new(screen, &[x1], &[y1], &[0.0], &[x2], &[y2], &[0.0])?;
-    screen.draw(&line)
+pub fn linev(canvas: &mut impl Canvas, v1: DVec2, v2: DVec2) -> Result<(), ProcessingErr> {
+    canvas.line(v1.x, v1.y, v2.x, v2.y)
 }
 
 pub fn triangle(
-    screen: &mut Screen,
+    canvas: &mut impl Canvas,
     x1: f64,
     y1: f64,
     x2: f64,
@@ -59,37 +86,335 @@ pub fn triangle(
     x3: f64,
     y3: f64,
 ) -> Result<(), ProcessingErr> {
-    let (x1, y1) = device_to_screen(screen, x1, y1);
-    let (x2, y2) = device_to_screen(screen, x2, y2);
-    let (x3, y3) = device_to_screen(screen, x3, y3);
-
-    let triangle = Triangle::new(
-        screen,
-        &[x1],
-        &[y1],
-        &[0.0],
-        &[x2],
-        &[y2],
-        &[0.0],
-        &[x3],
-        &[y3],
-        &[0.0],
-    )?;
-    screen.draw(&triangle)
-}
-
-pub fn rect(screen: &mut Screen, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
-    let (x, y) = device_to_screen(screen, x, y);
-    let (w, h) = device_to_screen_size(screen, w, h);
-
-    let rect = Rect::new(screen, &[x], &[y], &[0.0], &[w], &[h])?;
-    screen.draw(&rect)
-}
-
-pub fn ellipse(screen: &mut Screen, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
-    let (x, y) = device_to_screen(screen, x, y);
-    let (w, h) = device_to_screen_size(screen, w, h);
-
-    let ellipse = Ellipse::new(screen, &[x], &[y], &[0.0], &[w], &[h])?;
-    screen.draw(&ellipse)
+    canvas.triangle(x1, y1, x2, y2, x3, y3)
+}
+
+pub fn rect(canvas: &mut impl Canvas, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
+    canvas.rect(x, y, w, h)
+}
+
+pub fn ellipse(
+    canvas: &mut impl Canvas,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+) -> Result<(), ProcessingErr> {
+    canvas.ellipse(x, y, w, h)
+}
+
+pub fn polygon(
+    canvas: &mut impl Canvas,
+    points: &[DVec2],
+    mode: EndMode,
+) -> Result<(), ProcessingErr> {
+    canvas.polygon(points, mode == EndMode::Close)
+}
+
+/* shape builder */
+
+// segments each curve and bezier span is split into
+const CURVE_DETAIL: usize = 20;
+
+/// How `end_shape` connects the vertices
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ShapeKind {
+    /// Single polygon through every vertex
+    #[default]
+    Polygon,
+    Points,
+
+    /// Every 2 vertices are a line
+    Lines,
+
+    /// Every 3 vertices are a triangle
+    Triangles,
+
+    /// Every vertex after the first 2 makes a triangle with the previous 2
+    TriangleStrip,
+
+    /// Every vertex after the first 2 makes a triangle with the first and previous
+    TriangleFan,
+
+    /// Every 4 vertices are a quad
+    Quads,
+
+    /// Every 2 vertices after the first 2 make a quad with the previous 2
+    QuadStrip,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EndMode {
+    #[default]
+    Open,
+    Close,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Vertex {
+    Point(DVec2),
+
+    /// Catmull-Rom spline control point
+    Curve(DVec2),
+
+    /// Cubic bezier from the previous vertex
+    Bezier(DVec2, DVec2, DVec2),
+}
+
+/// Collects vertices between `begin_shape` and `end_shape`
+///
+/// Curve and bezier vertices are only used by polygons,
+/// the other kinds take the plain vertices as they are
+#[derive(Debug, Default, Clone)]
+pub struct Shape {
+    kind: ShapeKind,
+    vertices: Vec<Vertex>,
+}
+
+pub fn begin_shape(kind: ShapeKind) -> Shape {
+    Shape {
+        kind,
+        vertices: vec![],
+    }
+}
+
+impl Shape {
+    pub fn vertex(&mut self, x: f64, y: f64) -> &mut Self {
+        self.vertices.push(Vertex::Point(DVec2::new(x, y)));
+        self
+    }
+
+    pub fn vertexv(&mut self, v: DVec2) -> &mut Self {
+        self.vertices.push(Vertex::Point(v));
+        self
+    }
+
+    /// Catmull-Rom spline vertex
+    ///
+    /// The first and last curve vertex in a run only steer the curve,
+    /// it passes through the ones in between
+    pub fn curve_vertex(&mut self, x: f64, y: f64) -> &mut Self {
+        self.vertices.push(Vertex::Curve(DVec2::new(x, y)));
+        self
+    }
+
+    /// Cubic bezier from the previous vertex to (x, y)
+    pub fn bezier_vertex(
+        &mut self,
+        cx1: f64,
+        cy1: f64,
+        cx2: f64,
+        cy2: f64,
+        x: f64,
+        y: f64,
+    ) -> &mut Self {
+        self.vertices.push(Vertex::Bezier(
+            DVec2::new(cx1, cy1),
+            DVec2::new(cx2, cy2),
+            DVec2::new(x, y),
+        ));
+        self
+    }
+
+    /// Draws the shape with the current fill, stroke and transform
+    pub fn end_shape(&self, canvas: &mut impl Canvas, mode: EndMode) -> Result<(), ProcessingErr> {
+        match self.kind {
+            ShapeKind::Polygon => {
+                let points = self.path();
+                if points.len() > 1 {
+                    canvas.polygon(&points, mode == EndMode::Close)?;
+                }
+            }
+            ShapeKind::Points => {
+                for p in self.points() {
+                    canvas.point(p.x, p.y)?;
+                }
+            }
+            ShapeKind::Lines => {
+                for l in self.points().chunks_exact(2) {
+                    canvas.line(l[0].x, l[0].y, l[1].x, l[1].y)?;
+                }
+            }
+            ShapeKind::Triangles => {
+                for t in self.points().chunks_exact(3) {
+                    canvas.triangle(t[0].x, t[0].y, t[1].x, t[1].y, t[2].x, t[2].y)?;
+                }
+            }
+            ShapeKind::TriangleStrip => {
+                for t in self.points().windows(3) {
+                    canvas.triangle(t[0].x, t[0].y, t[1].x, t[1].y, t[2].x, t[2].y)?;
+                }
+            }
+            ShapeKind::TriangleFan => {
+                let points = self.points();
+                if let Some((center, rest)) = points.split_first() {
+                    for t in rest.windows(2) {
+                        canvas.triangle(center.x, center.y, t[0].x, t[0].y, t[1].x, t[1].y)?;
+                    }
+                }
+            }
+            ShapeKind::Quads => {
+                for q in self.points().chunks_exact(4) {
+                    canvas.polygon(q, true)?;
+                }
+            }
+            ShapeKind::QuadStrip => {
+                let points = self.points();
+                for i in (0..points.len().saturating_sub(3)).step_by(2) {
+                    // strip vertices zig-zag, so swap the second pair to go round the quad
+                    let quad = [points[i], points[i + 1], points[i + 3], points[i + 2]];
+                    canvas.polygon(&quad, true)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /* internal utils */
+
+    // plain vertices, ignoring curves
+    fn points(&self) -> Vec<DVec2> {
+        self.vertices
+            .iter()
+            .map(|v| match v {
+                Vertex::Point(p) | Vertex::Curve(p) => *p,
+                Vertex::Bezier(_, _, p) => *p,
+            })
+            .collect()
+    }
+
+    // vertices with curves and beziers flattened into line segments
+    fn path(&self) -> Vec<DVec2> {
+        let mut path = vec![];
+        let mut curve = vec![];
+
+        for v in self.vertices.iter() {
+            if let Vertex::Curve(p) = v {
+                curve.push(*p);
+                continue;
+            }
+
+            flatten_curve(&curve, &mut path);
+            curve.clear();
+
+            match v {
+                Vertex::Point(p) => path.push(*p),
+                Vertex::Bezier(c1, c2, p) => {
+                    let start = path.last().copied().unwrap_or(*c1);
+                    for i in 1..=CURVE_DETAIL {
+                        let t = i as f64 / CURVE_DETAIL as f64;
+                        path.push(bezier_point(start, *c1, *c2, *p, t));
+                    }
+                }
+                Vertex::Curve(_) => unreachable!(),
+            }
+        }
+        flatten_curve(&curve, &mut path);
+
+        path
+    }
+}
+
+/// Point on a cubic bezier with t in [0..1]
+pub fn bezier_point(a: DVec2, c1: DVec2, c2: DVec2, b: DVec2, t: f64) -> DVec2 {
+    let u = 1.0 - t;
+    a * (u * u * u) + c1 * (3.0 * u * u * t) + c2 * (3.0 * u * t * t) + b * (t * t * t)
+}
+
+/// Point on a Catmull-Rom spline between p1 and p2 with t in [0..1]
+pub fn curve_point(p0: DVec2, p1: DVec2, p2: DVec2, p3: DVec2, t: f64) -> DVec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+// appends the spline through a run of curve vertices,
+// runs shorter than 4 vertices don't draw anything like processing
+fn flatten_curve(curve: &[DVec2], path: &mut Vec<DVec2>) {
+    if curve.len() < 4 {
+        return;
+    }
+
+    path.push(curve[1]);
+    for c in curve.windows(4) {
+        for i in 1..=CURVE_DETAIL {
+            let t = i as f64 / CURVE_DETAIL as f64;
+            path.push(curve_point(c[0], c[1], c[2], c[3], t));
+        }
+    }
+}
+
+/// Splits a simple polygon into triangles by ear clipping
+///
+/// Self-intersecting polygons fall back to a triangle fan
+pub(crate) fn triangulate(points: &[DVec2]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n < 3 {
+        return vec![];
+    }
+
+    // ears are found assuming counter-clockwise winding
+    let area: f64 = (0..n)
+        .map(|i| points[i].perp_dot(points[(i + 1) % n]))
+        .sum();
+    let mut remaining: Vec<usize> = if area >= 0.0 {
+        (0..n).collect()
+    } else {
+        (0..n).rev().collect()
+    };
+
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let len = remaining.len();
+
+        let ear = (0..len).find(|&i| {
+            let a = points[remaining[(i + len - 1) % len]];
+            let b = points[remaining[i]];
+            let c = points[remaining[(i + 1) % len]];
+
+            // reflex corners can't be ears
+            if (b - a).perp_dot(c - b) <= 0.0 {
+                return false;
+            }
+
+            !remaining
+                .iter()
+                .map(|&j| points[j])
+                .any(|p| p != a && p != b && p != c && in_triangle(p, a, b, c))
+        });
+
+        let i = match ear {
+            Some(i) => i,
+            None => {
+                // not a simple polygon, fan what's left
+                for i in 1..len - 1 {
+                    triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+                }
+                return triangles;
+            }
+        };
+
+        triangles.push([
+            remaining[(i + len - 1) % len],
+            remaining[i],
+            remaining[(i + 1) % len],
+        ]);
+        remaining.remove(i);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+
+    triangles
+}
+
+fn in_triangle(p: DVec2, a: DVec2, b: DVec2, c: DVec2) -> bool {
+    let d1 = (b - a).perp_dot(p - a);
+    let d2 = (c - b).perp_dot(p - b);
+    let d3 = (a - c).perp_dot(p - c);
+
+    d1 >= 0.0 && d2 >= 0.0 && d3 >= 0.0
 }
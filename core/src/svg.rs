@@ -0,0 +1,511 @@
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use image::codecs::png::PngEncoder;
+use image::ColorType;
+
+use glam::{DMat3, DVec2};
+use processing::errors::ProcessingErr;
+
+use crate::pixels::{image_not_saved, PixelBuffer};
+use crate::record::arg;
+use crate::shapes::RectMode;
+use crate::text::{glyph_runs, TextAlign, TextBaseline, TextStyle};
+use crate::{BlendMode, Canvas, DrawState};
+
+/// Environment variable holding the SVG output path
+pub const SVG_ENV: &str = "NOC_SVG";
+
+/// Command line flag holding the SVG output path, takes precedence over the environment
+pub const SVG_FLAG: &str = "--svg";
+
+/// Where to write a sketch as SVG from the command line or environment,
+/// returns None if SVG output wasn't asked for
+pub fn output_from_args() -> Option<PathBuf> {
+    arg(SVG_FLAG)
+        .or_else(|| env::var(SVG_ENV).ok())
+        .map(PathBuf::from)
+}
+
+/// Vector backend that records drawing calls and serializes them to an SVG document
+///
+/// Each element keeps the transform, fill and stroke
+/// that were current when it was drawn
+#[derive(Debug)]
+pub struct Svg {
+    width: u32,
+    height: u32,
+
+    background: Option<[f32; 4]>,
+    elements: Vec<String>,
+
+    state: DrawState,
+    text_style: TextStyle,
+
+    matrix: DMat3,
+    matrix_stack: Vec<DMat3>,
+}
+
+/// PNG encoded pixels, embedded in the document each time they're drawn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgImage {
+    pub width: u32,
+    pub height: u32,
+    pub png: Vec<u8>,
+}
+
+impl Svg {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+
+            background: None,
+            elements: vec![],
+
+            state: DrawState::default(),
+            text_style: TextStyle::default(),
+
+            matrix: DMat3::IDENTITY,
+            matrix_stack: vec![],
+        }
+    }
+
+    /// Number of recorded elements, not counting the background
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn to_document(&self) -> String {
+        let mut document = String::new();
+
+        writeln!(
+            document,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            self.width, self.height
+        )
+        .unwrap();
+
+        if let Some(background) = self.background {
+            writeln!(
+                document,
+                r#"  <rect x="0" y="0" width="{}" height="{}" fill="{}" fill-opacity="{}"/>"#,
+                self.width,
+                self.height,
+                rgb(background),
+                opacity(background)
+            )
+            .unwrap();
+        }
+
+        for element in self.elements.iter() {
+            writeln!(document, "  {}", element).unwrap();
+        }
+
+        document.push_str("</svg>\n");
+
+        document
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ProcessingErr> {
+        fs::write(path, self.to_document()).map_err(ProcessingErr::ImageNotSaved)
+    }
+
+    /* internal utils */
+
+    fn fill_color(&self) -> Option<[f32; 4]> {
+        self.state.fill.then_some(self.state.fill_color)
+    }
+
+    fn stroke_color(&self) -> Option<[f32; 4]> {
+        self.state.stroke.then_some(self.state.stroke_color)
+    }
+
+    fn transform(&self) -> String {
+        let m = self.matrix;
+        format!(
+            r#"transform="matrix({} {} {} {} {} {})""#,
+            m.x_axis.x, m.x_axis.y, m.y_axis.x, m.y_axis.y, m.z_axis.x, m.z_axis.y
+        )
+    }
+
+    fn style(&self, filled: bool) -> String {
+        let mut style = String::new();
+
+        match self.fill_color() {
+            Some(fill) if filled => write!(
+                style,
+                r#"fill="{}" fill-opacity="{}""#,
+                rgb(fill),
+                opacity(fill)
+            )
+            .unwrap(),
+            _ => style.push_str(r#"fill="none""#),
+        }
+
+        match self.stroke_color() {
+            Some(stroke) => write!(
+                style,
+                r#" stroke="{}" stroke-opacity="{}" stroke-width="{}""#,
+                rgb(stroke),
+                opacity(stroke),
+                self.state.stroke_weight
+            )
+            .unwrap(),
+            None => style.push_str(r#" stroke="none""#),
+        }
+
+        if let Some(mode) = css_blend_mode(self.state.blend_mode) {
+            write!(style, r#" style="mix-blend-mode:{}""#, mode).unwrap();
+        }
+
+        style
+    }
+
+    fn push_element(&mut self, element: impl AsRef<str>, filled: bool) {
+        let element = format!(
+            "<{} {} {}/>",
+            element.as_ref(),
+            self.style(filled),
+            self.transform()
+        );
+        self.elements.push(element);
+    }
+}
+
+impl Canvas for Svg {
+    type Image = SvgImage;
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    /* color */
+
+    fn background(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        // the background covers everything drawn so far
+        self.elements.clear();
+        self.background = Some([r, g, b, a]);
+    }
+
+    fn fill(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.state.fill_color = [r, g, b, a];
+        self.state.fill = true;
+    }
+
+    fn fill_off(&mut self) {
+        self.state.fill = false;
+    }
+
+    fn stroke(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.state.stroke_color = [r, g, b, a];
+        self.state.stroke = true;
+    }
+
+    fn stroke_off(&mut self) {
+        self.state.stroke = false;
+    }
+
+    fn stroke_weight(&mut self, weight: f64) {
+        self.state.stroke_weight = weight;
+    }
+
+    /* draw state */
+
+    fn draw_state(&self) -> DrawState {
+        self.state
+    }
+
+    fn blend_mode(&mut self, mode: BlendMode) {
+        self.state.blend_mode = mode;
+    }
+
+    fn rect_mode(&mut self, mode: RectMode) {
+        self.state.rect_mode = mode;
+    }
+
+    fn ellipse_mode(&mut self, mode: RectMode) {
+        self.state.ellipse_mode = mode;
+    }
+
+    /* transform */
+
+    fn push_matrix(&mut self) {
+        self.matrix_stack.push(self.matrix);
+    }
+
+    fn pop_matrix(&mut self) {
+        self.matrix = self.matrix_stack.pop().unwrap_or(DMat3::IDENTITY);
+    }
+
+    fn reset_matrix(&mut self) {
+        self.matrix = DMat3::IDENTITY;
+        self.matrix_stack.clear();
+    }
+
+    fn matrix(&self) -> DMat3 {
+        self.matrix
+    }
+
+    fn apply_matrix(&mut self, matrix: DMat3) {
+        self.matrix = self.matrix * matrix;
+    }
+
+    /* shapes */
+
+    fn point(&mut self, x: f64, y: f64) -> Result<(), ProcessingErr> {
+        let stroke = match self.stroke_color() {
+            Some(stroke) => stroke,
+            None => return Ok(()),
+        };
+
+        // points are a stroke-colored square centered on the point
+        let hw = self.state.stroke_weight.max(1.0) / 2.0;
+        let element = format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="{}" stroke="none" {}/>"#,
+            x - hw,
+            y - hw,
+            hw * 2.0,
+            hw * 2.0,
+            rgb(stroke),
+            opacity(stroke),
+            self.transform()
+        );
+        self.elements.push(element);
+
+        Ok(())
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> Result<(), ProcessingErr> {
+        self.push_element(
+            format!(r#"line x1="{}" y1="{}" x2="{}" y2="{}""#, x1, y1, x2, y2),
+            false,
+        );
+
+        Ok(())
+    }
+
+    fn triangle(
+        &mut self,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x3: f64,
+        y3: f64,
+    ) -> Result<(), ProcessingErr> {
+        self.push_element(
+            format!(
+                r#"polygon points="{},{} {},{} {},{}""#,
+                x1, y1, x2, y2, x3, y3
+            ),
+            true,
+        );
+
+        Ok(())
+    }
+
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
+        let (x, y, w, h) = self.state.rect_mode.to_corner(x, y, w, h);
+
+        // svg doesn't allow negative sizes
+        self.push_element(
+            format!(
+                r#"rect x="{}" y="{}" width="{}" height="{}""#,
+                x.min(x + w),
+                y.min(y + h),
+                w.abs(),
+                h.abs()
+            ),
+            true,
+        );
+
+        Ok(())
+    }
+
+    fn ellipse(&mut self, x: f64, y: f64, w: f64, h: f64) -> Result<(), ProcessingErr> {
+        let (x, y, w, h) = self.state.ellipse_mode.to_center(x, y, w, h);
+
+        self.push_element(
+            format!(
+                r#"ellipse cx="{}" cy="{}" rx="{}" ry="{}""#,
+                x,
+                y,
+                w.abs() / 2.0,
+                h.abs() / 2.0
+            ),
+            true,
+        );
+
+        Ok(())
+    }
+
+    fn polygon(&mut self, points: &[DVec2], close: bool) -> Result<(), ProcessingErr> {
+        let mut coords = String::new();
+        for p in points {
+            write!(coords, "{},{} ", p.x, p.y).unwrap();
+        }
+
+        // polylines are filled too, they just aren't stroked closed
+        let element = if close { "polygon" } else { "polyline" };
+        self.push_element(
+            format!(
+                r#"{} points="{}" fill-rule="nonzero""#,
+                element,
+                coords.trim_end()
+            ),
+            true,
+        );
+
+        Ok(())
+    }
+
+    /* text */
+
+    fn text_size(&mut self, size: f64) {
+        self.text_style.size = size;
+    }
+
+    fn text_align(&mut self, align: TextAlign, baseline: TextBaseline) {
+        self.text_style.align = align;
+        self.text_style.baseline = baseline;
+    }
+
+    fn text_style(&self) -> TextStyle {
+        self.text_style
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64) -> Result<(), ProcessingErr> {
+        let fill = match self.fill_color() {
+            Some(fill) => fill,
+            None => return Ok(()),
+        };
+
+        // glyphs are written out as a path so the text
+        // looks the same as on the other backends
+        let mut path = String::new();
+        for [x, y, w, h] in glyph_runs(text, x, y, &self.text_style) {
+            write!(path, "M{} {}h{}v{}h{}z", x, y, w, h, -w).unwrap();
+        }
+
+        if path.is_empty() {
+            return Ok(());
+        }
+
+        let element = format!(
+            r#"<path d="{}" fill="{}" fill-opacity="{}" stroke="none" {}/>"#,
+            path,
+            rgb(fill),
+            opacity(fill),
+            self.transform()
+        );
+        self.elements.push(element);
+
+        Ok(())
+    }
+
+    /* image */
+
+    fn image(&mut self, x: f64, y: f64, image: &Self::Image) -> Result<(), ProcessingErr> {
+        let element = format!(
+            r#"<image x="{}" y="{}" width="{}" height="{}" href="data:image/png;base64,{}" {}/>"#,
+            x,
+            y,
+            image.width,
+            image.height,
+            base64(&image.png),
+            self.transform()
+        );
+        self.elements.push(element);
+
+        Ok(())
+    }
+
+    /// Recorded drawing calls can't be read back
+    fn load_pixels(&mut self) -> Result<PixelBuffer, ProcessingErr> {
+        Err(ProcessingErr::ImageNotFound(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "svg canvases have no pixels to load",
+        )))
+    }
+
+    fn create_image(&mut self, pixels: &PixelBuffer) -> Result<Self::Image, ProcessingErr> {
+        let mut png = vec![];
+        PngEncoder::new(&mut png)
+            .encode(
+                pixels.raw(),
+                pixels.width(),
+                pixels.height(),
+                ColorType::Rgba8,
+            )
+            .map_err(image_not_saved)?;
+
+        Ok(SvgImage {
+            width: pixels.width(),
+            height: pixels.height(),
+            png,
+        })
+    }
+}
+
+fn rgb(color: [f32; 4]) -> String {
+    format!(
+        "rgb({}, {}, {})",
+        color[0].round().clamp(0.0, 255.0),
+        color[1].round().clamp(0.0, 255.0),
+        color[2].round().clamp(0.0, 255.0)
+    )
+}
+
+fn opacity(color: [f32; 4]) -> f32 {
+    (color[3] / 255.0).clamp(0.0, 1.0)
+}
+
+// standard base64 with padding, for data URIs
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+// replace and subtract have no css equivalent and are drawn normally
+fn css_blend_mode(mode: BlendMode) -> Option<&'static str> {
+    match mode {
+        BlendMode::Replace | BlendMode::Blend | BlendMode::Subtract => None,
+        BlendMode::Add => Some("plus-lighter"),
+        BlendMode::Lightest => Some("lighten"),
+        BlendMode::Darkest => Some("darken"),
+        BlendMode::Exclusion => Some("exclusion"),
+        BlendMode::Multiply => Some("multiply"),
+        BlendMode::Screen => Some("screen"),
+    }
+}
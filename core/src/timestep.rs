@@ -0,0 +1,67 @@
+/// Default simulation step, matches the rate most sketches were tuned at
+pub const DEFAULT_STEP: f64 = 1.0 / 60.0;
+
+/// Default cap on simulation steps per frame
+pub const DEFAULT_MAX_SUBSTEPS: usize = 8;
+
+/// Accumulates frame time and splits it into fixed simulation steps
+///
+/// https://gafferongames.com/post/fix_your_timestep/
+#[derive(Debug, Copy, Clone)]
+pub struct FixedTimestep {
+    step: f64,
+    max_substeps: usize,
+    accumulator: f64,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(DEFAULT_STEP, DEFAULT_MAX_SUBSTEPS)
+    }
+}
+
+impl FixedTimestep {
+    pub fn new(step: f64, max_substeps: usize) -> Self {
+        assert!(step > 0.0, "timestep must be positive");
+
+        Self {
+            step,
+            max_substeps: max_substeps.max(1),
+            accumulator: 0.0,
+        }
+    }
+
+    /// Seconds simulated by each step
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    pub fn max_substeps(&self) -> usize {
+        self.max_substeps
+    }
+
+    /// Adds the frame time and returns how many steps to simulate this frame
+    ///
+    /// Time beyond the substep cap is dropped so a slow frame
+    /// doesn't make the following frames slower still
+    pub fn advance(&mut self, dt: f64) -> usize {
+        self.accumulator += dt.max(0.0);
+
+        let mut substeps = (self.accumulator / self.step) as usize;
+        if substeps > self.max_substeps {
+            substeps = self.max_substeps;
+            self.accumulator %= self.step;
+        } else {
+            self.accumulator = (self.accumulator - substeps as f64 * self.step).max(0.0);
+        }
+
+        substeps
+    }
+
+    /// How far between the previous and current step the frame is, in [0..1)
+    ///
+    /// Used to interpolate rendered state between steps
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.step
+    }
+}
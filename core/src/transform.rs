@@ -0,0 +1,74 @@
+use std::ops::{Deref, DerefMut};
+
+use glam::DVec2;
+
+use crate::Canvas;
+
+/// Restores the transform it was created with when dropped
+///
+/// Derefs to the canvas so drawing can continue through the guard
+pub struct MatrixGuard<'a, C: Canvas> {
+    canvas: &'a mut C,
+}
+
+impl<'a, C: Canvas> Deref for MatrixGuard<'a, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.canvas
+    }
+}
+
+impl<'a, C: Canvas> DerefMut for MatrixGuard<'a, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.canvas
+    }
+}
+
+impl<'a, C: Canvas> Drop for MatrixGuard<'a, C> {
+    fn drop(&mut self) {
+        self.canvas.pop_matrix();
+    }
+}
+
+/// Saves the current transform until the guard goes out of scope
+#[must_use = "the transform is restored as soon as the guard is dropped"]
+pub fn push<C: Canvas>(canvas: &mut C) -> MatrixGuard<'_, C> {
+    canvas.push_matrix();
+    MatrixGuard { canvas }
+}
+
+/* coordinate conversion */
+
+/// Maps a point in the current local coordinates to pixels
+pub fn screen_point(canvas: &impl Canvas, p: DVec2) -> DVec2 {
+    canvas.matrix().transform_point2(p)
+}
+
+pub fn screen_x(canvas: &impl Canvas, x: f64, y: f64) -> f64 {
+    screen_point(canvas, DVec2::new(x, y)).x
+}
+
+pub fn screen_y(canvas: &impl Canvas, x: f64, y: f64) -> f64 {
+    screen_point(canvas, DVec2::new(x, y)).y
+}
+
+/// Maps a point in pixels to the current local coordinates, such as the mouse position
+///
+/// Returns the point unchanged if the transform can't be inverted
+pub fn local_point(canvas: &impl Canvas, p: DVec2) -> DVec2 {
+    let matrix = canvas.matrix();
+    if matrix.determinant().abs() <= f64::EPSILON {
+        return p;
+    }
+
+    matrix.inverse().transform_point2(p)
+}
+
+pub fn local_x(canvas: &impl Canvas, x: f64, y: f64) -> f64 {
+    local_point(canvas, DVec2::new(x, y)).x
+}
+
+pub fn local_y(canvas: &impl Canvas, x: f64, y: f64) -> f64 {
+    local_point(canvas, DVec2::new(x, y)).y
+}
@@ -0,0 +1,185 @@
+use processing::errors::ProcessingErr;
+use processing::Key;
+
+use crate::shapes::RectMode;
+use crate::text::{TextAlign, TextBaseline};
+use crate::Canvas;
+
+// panel layout in pixels
+const TEXT_SIZE: f64 = 8.0;
+const LINE_HEIGHT: f64 = 12.0;
+const PADDING: f64 = 6.0;
+const WIDTH: f64 = 200.0;
+
+/// Value adjusted from a tweak panel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tweak {
+    pub name: String,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+
+    /// Change per key press, shift changes it ten times as much
+    pub step: f64,
+}
+
+/// Keyboard driven list of values to adjust while a sketch runs
+///
+/// Tab shows and hides the panel, up and down pick a value and left and right change it
+#[derive(Debug, Default, Clone)]
+pub struct TweakPanel {
+    tweaks: Vec<Tweak>,
+    selected: usize,
+    visible: bool,
+}
+
+impl TweakPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tweak(
+        mut self,
+        name: impl Into<String>,
+        value: f64,
+        min: f64,
+        max: f64,
+        step: f64,
+    ) -> Self {
+        self.tweaks.push(Tweak {
+            name: name.into(),
+            value: value.clamp(min, max),
+            min,
+            max,
+            step,
+        });
+        self
+    }
+
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn tweaks(&self) -> &[Tweak] {
+        &self.tweaks
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Current value of a tweak, panics if there isn't one with the name
+    pub fn get(&self, name: &str) -> f64 {
+        self.tweak(name).value
+    }
+
+    /// Sets a tweak within its range, panics if there isn't one with the name
+    pub fn set(&mut self, name: &str, value: f64) {
+        let tweak = self
+            .tweaks
+            .iter_mut()
+            .find(|tweak| tweak.name == name)
+            .unwrap_or_else(|| panic!("no tweak named {}", name));
+        tweak.value = value.clamp(tweak.min, tweak.max);
+    }
+
+    /// Applies this frame's key presses, returns true if a value changed
+    pub fn handle_input(&mut self) -> bool {
+        if crate::input::key_pressed(Key::Tab) {
+            self.visible = !self.visible;
+        }
+
+        if !self.visible || self.tweaks.is_empty() {
+            return false;
+        }
+
+        let count = self.tweaks.len();
+        if crate::input::key_pressed(Key::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if crate::input::key_pressed(Key::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+
+        let mut direction = 0.0;
+        if crate::input::key_pressed(Key::Left) {
+            direction -= 1.0;
+        }
+        if crate::input::key_pressed(Key::Right) {
+            direction += 1.0;
+        }
+        if direction == 0.0 {
+            return false;
+        }
+
+        if crate::input::key_down(Key::LShift) || crate::input::key_down(Key::RShift) {
+            direction *= 10.0;
+        }
+
+        let tweak = &mut self.tweaks[self.selected];
+        tweak.value = (tweak.value + tweak.step * direction).clamp(tweak.min, tweak.max);
+
+        true
+    }
+
+    /// Draws the panel with its top left corner at x, y if it's visible
+    pub fn display(&self, canvas: &mut impl Canvas, x: f64, y: f64) -> Result<(), ProcessingErr> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        // leave the sketch's own colors and text settings as they were, even on errors
+        let state = canvas.draw_state();
+        let text_style = canvas.text_style();
+
+        let result = self.draw_panel(canvas, x, y);
+
+        canvas.set_draw_state(state);
+        canvas.set_text_style(text_style);
+
+        result
+    }
+
+    fn draw_panel(&self, canvas: &mut impl Canvas, x: f64, y: f64) -> Result<(), ProcessingErr> {
+        let height = self.tweaks.len() as f64 * LINE_HEIGHT + PADDING * 2.0;
+
+        crate::shapes::rect_mode(canvas, RectMode::Corner);
+
+        crate::stroke_off(canvas);
+        crate::fill_grayscale_alpha(canvas, 0.0, 180.0);
+        crate::shapes::rect(canvas, x, y, WIDTH, height)?;
+
+        crate::text_size(canvas, TEXT_SIZE);
+        for (idx, tweak) in self.tweaks.iter().enumerate() {
+            let line_y = y + PADDING + idx as f64 * LINE_HEIGHT;
+
+            // selected value is highlighted
+            if idx == self.selected {
+                crate::fill_grayscale(canvas, 255.0);
+            } else {
+                crate::fill_grayscale(canvas, 160.0);
+            }
+
+            crate::text_align(canvas, TextAlign::Left, TextBaseline::Top);
+            crate::text(canvas, &tweak.name, x + PADDING, line_y)?;
+
+            crate::text_align(canvas, TextAlign::Right, TextBaseline::Top);
+            crate::text(
+                canvas,
+                format!("{:.2}", tweak.value),
+                x + WIDTH - PADDING,
+                line_y,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn tweak(&self, name: &str) -> &Tweak {
+        self.tweaks
+            .iter()
+            .find(|tweak| tweak.name == name)
+            .unwrap_or_else(|| panic!("no tweak named {}", name))
+    }
+}
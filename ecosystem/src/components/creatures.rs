@@ -3,6 +3,7 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
 use bevy_prototype_lyon::prelude::*;
+use steering::Wander;
 
 use crate::bundles::creatures::*;
 use crate::bundles::physics::*;
@@ -32,6 +33,9 @@ pub const FISH_WIDTH: f32 = 0.3 / FISH_MASS;
 pub const FISH_LENGTH: f32 = 0.6 / FISH_MASS;
 const FISH_REPEL_ACCEL: f32 = 0.01;
 const FISH_ACCEL: f32 = 1.0;
+const FISH_WANDER_DISTANCE: f64 = 1.0;
+const FISH_WANDER_RADIUS: f64 = 0.5;
+const FISH_WANDER_JITTER: f64 = 0.3;
 
 const SNAKE_BODY_COLOR: Color = Color::MAROON;
 const SNAKE_HEAD_COLOR: Color = Color::ORANGE_RED;
@@ -155,6 +159,9 @@ pub struct Firefly;
 pub struct Fish {
     pub acceleration: f32,
     pub repel_acceleration: f32,
+
+    #[inspectable(ignore)]
+    pub wander: Wander,
 }
 
 impl Fish {
@@ -189,6 +196,11 @@ impl Fish {
                 fish: Fish {
                     acceleration: FISH_ACCEL,
                     repel_acceleration: FISH_REPEL_ACCEL,
+                    wander: Wander::new(
+                        FISH_WANDER_DISTANCE,
+                        FISH_WANDER_RADIUS,
+                        FISH_WANDER_JITTER,
+                    ),
                 },
                 physical: DynamicPhysicsBundle {
                     rigidbody: Rigidbody {
@@ -124,6 +124,24 @@ impl Random {
     }
 }
 
+impl RngCore for Random {
+    fn next_u32(&mut self) -> u32 {
+        self.random.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.random.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.random.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.random.try_fill_bytes(dest)
+    }
+}
+
 /// Perlin noies wrapper
 pub struct PerlinNoise {
     perlin: Perlin,
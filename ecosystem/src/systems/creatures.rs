@@ -1,6 +1,7 @@
 //! Creature systems
 
 use bevy::prelude::*;
+use steering::{Agent, DVec2};
 
 use crate::components::creatures::*;
 use crate::components::physics::*;
@@ -97,21 +98,31 @@ pub fn fish_physics(
     time: Res<Time>,
     mut random: ResMut<Random>,
     noise: Res<PerlinNoise>,
-    mut query: Query<(&mut Rigidbody, &Fish, &mut Creature)>,
+    mut query: Query<(&Transform, &mut Rigidbody, &mut Fish, &mut Creature)>,
 ) {
-    for (mut rigidbody, fish, mut creature) in query.iter_mut() {
+    for (transform, mut rigidbody, mut fish, mut creature) in query.iter_mut() {
         let t = time.seconds_since_startup() + random.random_range(0.0..0.5);
         let _modifier = noise.get(t, random.random_range(0.5..0.75)) as f32;
 
-        creature.acceleration_direction = if rigidbody.velocity.length_squared() == 0.0 {
-            random.direction()
-        } else {
-            rigidbody
-                .velocity
-                .truncate()
-                .lerp(random.direction(), PHYSICS_STEP)
-                .normalize()
+        let agent = Agent {
+            position: DVec2::new(
+                transform.translation.x as f64,
+                transform.translation.y as f64,
+            ),
+            velocity: DVec2::new(rigidbody.velocity.x as f64, rigidbody.velocity.y as f64),
+            ..Default::default()
         };
+
+        // swim towards a point wandering around in front of the fish
+        let target = fish.wander.target(&agent, &mut *random);
+        let direction = target - agent.position;
+
+        creature.acceleration_direction =
+            Vec2::new(direction.x as f32, direction.y as f32).normalize_or_zero();
+        if creature.acceleration_direction == Vec2::ZERO {
+            creature.acceleration_direction = random.direction();
+        }
+
         let magnitude = fish.acceleration * rigidbody.mass; // * _modifier;
         rigidbody.apply_force(creature.acceleration_direction * magnitude);
     }
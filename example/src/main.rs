@@ -1,31 +1,47 @@
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(400, 400)
-}
+const WIDTH: u32 = 400;
+const HEIGHT: u32 = 400;
 
-fn draw(screen: &mut Screen, _: f64) -> Result<(), ProcessingErr> {
-    //core::background_grayscale(screen, 220.0);
+#[derive(Debug, Default)]
+struct MouseEllipse;
 
-    if core::input::mouse_is_pressed() {
-        core::fill_grayscale(screen, 0.0)
-    } else {
-        core::fill_grayscale(screen, 255.0)
-    }
+impl Sketch for MouseEllipse {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        //core::background_grayscale(canvas, 220.0);
 
-    //core::ellipse(screen, 50.0, 50.0, 80.0, 80.0)?;
+        if core::input::mouse_is_pressed() {
+            core::fill_grayscale(canvas, 0.0)
+        } else {
+            core::fill_grayscale(canvas, 255.0)
+        }
 
-    let x = screen.mouse_x();
-    let y = screen.mouse_y();
-    println!("drawing ellipse at {}, {}", x, y);
-    core::shapes::ellipse(screen, x, y, 80.0, 80.0)?;
+        //core::ellipse(canvas, 50.0, 50.0, 80.0, 80.0)?;
 
-    Ok(())
+        let mouse = core::input::mouse_position();
+        println!("drawing ellipse at {}, {}", mouse.x, mouse.y);
+        core::shapes::ellipse(canvas, mouse.x, mouse.y, 80.0, 80.0)?;
+
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    core::run(setup, draw)?;
+    core::run_sketch(WIDTH, HEIGHT, MouseEllipse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn mouse_ellipse_snapshot() {
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 1, &mut MouseEllipse).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/mouse-ellipse.png"),
+        );
+    }
 }
@@ -1,9 +1,11 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+const GENERATIONS: usize = 5;
 
 #[derive(Debug)]
 struct KochLine {
@@ -45,10 +47,10 @@ impl KochLine {
         self.end
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
 
-        core::shapes::linev(screen, self.start, self.end)?;
+        core::shapes::linev(canvas, self.start, self.end)?;
 
         Ok(())
     }
@@ -60,6 +62,21 @@ struct KochLines {
 }
 
 impl KochLines {
+    fn new(width: f64, generations: usize) -> Self {
+        let start = DVec2::new(0.0, 200.0);
+        let end = DVec2::new(width, 200.0);
+
+        let mut lines = Self {
+            lines: vec![KochLine::new(start, end)],
+        };
+
+        for _ in 0..generations {
+            lines.generate();
+        }
+
+        lines
+    }
+
     #[allow(clippy::many_single_char_names)]
     fn generate(&mut self) {
         let mut lines = vec![];
@@ -78,54 +95,50 @@ impl KochLines {
         self.lines = lines;
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
         for line in self.lines.iter() {
-            line.display(screen)?;
+            line.display(canvas)?;
         }
 
         Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
-
-fn draw(screen: &mut Screen, _: f64, lines: &mut KochLines) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
-
-    lines.display(screen)?;
+impl core::Sketch for KochLines {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    Ok(())
+        self.display(canvas)
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let lines = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
+    // --svg <path> writes the curve out as SVG instead of opening a window
+    if let Some(path) = core::svg::output_from_args() {
+        let mut svg = core::svg::Svg::new(WIDTH, HEIGHT);
 
-            let mut l = vec![];
+        let mut lines = KochLines::new(WIDTH as f64, GENERATIONS);
+        lines.draw(&mut svg, 0.0)?;
 
-            let start = DVec2::new(0.0, 200.0);
-            let end = DVec2::new(screen.width() as f64, 200.0);
-
-            l.push(KochLine::new(start, end));
+        return svg.save(path);
+    }
 
-            let mut k = KochLines { lines: l };
-            k.generate();
-            k.generate();
-            k.generate();
-            k.generate();
-            k.generate();
+    core::run_sketch(WIDTH, HEIGHT, KochLines::new(WIDTH as f64, GENERATIONS))
+}
 
-            *lines.borrow_mut() = Some(k);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            Ok(screen)
-        },
-        |screen, dt| draw(screen, dt, lines.borrow_mut().as_mut().unwrap()),
-    )?;
+    #[test]
+    fn koch_snapshot() {
+        let mut lines = KochLines::new(WIDTH as f64, GENERATIONS);
+        let canvas =
+            core::headless::render(WIDTH, HEIGHT, 1, |canvas, dt| lines.draw(canvas, dt)).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/koch.png"),
+        );
+    }
 }
@@ -1,25 +1,33 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::color::{lerp_color, Color, Palette};
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+// age at which live cells reach the end of the palette
+const MAX_AGE: usize = 20;
+
 #[derive(Debug, Default, Copy, Clone)]
 struct LifeCell {
     state: i8,
     previous_state: i8,
+
+    // generations alive
+    age: usize,
 }
 
 impl LifeCell {
     fn new() -> Self {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         let state = rng.gen_range(0..=1);
 
         Self {
             state,
             previous_state: state,
+            age: 0,
         }
     }
 
@@ -46,14 +54,16 @@ struct GameOfLife {
 
     board: Vec<Vec<LifeCell>>,
     cell_size: f64,
+
+    palette: Palette,
 }
 
 impl GameOfLife {
-    fn new(screen: &Screen) -> Self {
+    fn new(width: u32, height: u32) -> Self {
         let cell_size = 10;
 
-        let columns = (screen.width() / cell_size) as usize;
-        let rows = (screen.height() / cell_size) as usize;
+        let columns = (width / cell_size) as usize;
+        let rows = (height / cell_size) as usize;
 
         // TODO: is there a better way to init a 2d vec?
         let mut board = vec![vec![LifeCell::default(); rows]; columns];
@@ -68,6 +78,7 @@ impl GameOfLife {
             rows,
             board,
             cell_size: cell_size as f64,
+            palette: Palette::viridis(),
         }
     }
 
@@ -108,25 +119,31 @@ impl GameOfLife {
                     // stasis
                     self.board[x][y].previous_state
                 };
+
+                let cell = &mut self.board[x][y];
+                cell.age = if cell.is_alive() { cell.age + 1 } else { 0 };
             }
         }
     }
 
-    fn draw(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
         for x in 0..self.columns {
             for y in 0..self.rows {
-                if self.board[x][y].was_born() {
-                    core::fill_rgb(screen, 0.0, 0.0, 255.0);
-                } else if self.board[x][y].is_alive() {
-                    core::fill_grayscale(screen, 0.0);
-                } else if self.board[x][y].died() {
-                    core::fill_rgb(screen, 255.0, 0.0, 0.0);
+                let cell = &self.board[x][y];
+                if cell.was_born() {
+                    core::fill(canvas, Color::BLUE);
+                } else if cell.is_alive() {
+                    // older cells cool down the palette
+                    let t = cell.age.min(MAX_AGE) as f64 / MAX_AGE as f64;
+                    core::fill(canvas, self.palette.sample(t));
+                } else if cell.died() {
+                    core::fill(canvas, lerp_color(Color::RED, Color::WHITE, 0.25));
                 } else {
-                    core::fill_grayscale(screen, 255.0);
+                    core::fill(canvas, Color::WHITE);
                 }
 
                 core::shapes::rect(
-                    screen,
+                    canvas,
                     x as f64 * self.cell_size,
                     y as f64 * self.cell_size,
                     self.cell_size,
@@ -139,32 +156,33 @@ impl GameOfLife {
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
-
-fn draw(screen: &mut Screen, _dt: f64, game_of_life: &mut GameOfLife) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
-
-    game_of_life.generate();
-    game_of_life.draw(screen)?;
+impl Sketch for GameOfLife {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    Ok(())
+        self.generate();
+        self.display(canvas)
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let game_of_life = Rc::new(RefCell::new(None));
+    core::run_sketch(WIDTH, HEIGHT, GameOfLife::new(WIDTH, HEIGHT))
+}
 
-    core::run(
-        || {
-            let screen = setup()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            *game_of_life.borrow_mut() = Some(GameOfLife::new(&screen));
+    #[test]
+    fn game_of_life_snapshot() {
+        core::random::set_seed(1);
 
-            Ok(screen)
-        },
-        |screen, dt| draw(screen, dt, game_of_life.borrow_mut().as_mut().unwrap()),
-    )?;
+        let mut game_of_life = GameOfLife::new(WIDTH, HEIGHT);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 10, &mut game_of_life).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/game-of-life.png"),
+        );
+    }
 }
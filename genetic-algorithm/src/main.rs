@@ -1,10 +1,10 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 const TARGET: &str = "to be or not to be";
 
 // key variables to tweak for a GA
@@ -45,7 +45,7 @@ impl Dna {
     }
 
     fn random(target: &'static str) -> Self {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         let mut genes = Vec::with_capacity(target.len());
         for _ in 0..genes.capacity() {
@@ -82,7 +82,7 @@ impl Dna {
     }
 
     fn crossover(&self, partner: &Dna, method: CrossoverMethod) -> Dna {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         assert_eq!(self.target, partner.target);
         let mut child = Dna::new(self.target);
@@ -116,7 +116,7 @@ impl Dna {
     }
 
     fn mutate(&mut self, mutation_rate: f32) {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         for i in 0..self.genes.len() {
             if rng.gen_range(0.0..1.0) < mutation_rate {
@@ -127,99 +127,121 @@ impl Dna {
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+#[derive(Debug)]
+struct Population(Vec<Dna>);
+
+impl Population {
+    fn random(target: &'static str, size: usize) -> Self {
+        let mut population = vec![];
+        for _ in 0..size {
+            population.push(Dna::random(target));
+        }
+
+        Self(population)
+    }
 }
 
-fn draw(screen: &mut Screen, _: f64, population: &mut Vec<Dna>) -> Result<(), ProcessingErr> {
-    let mut rng = rand::thread_rng();
+impl Sketch for Population {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        let mut rng = core::random::rng();
 
-    core::background_grayscale(screen, 255.0);
+        core::background_grayscale(canvas, 255.0);
 
-    let mut mating_pool = vec![];
+        let mut mating_pool = vec![];
 
-    // wheel of fortune probability
-    // have to clone parents here
-    // (tho probably not as much as we are)
-    // since we overwrite their population entries
-    // when they reproduce
-    for dna in population.iter_mut() {
-        dna.fitness();
+        // wheel of fortune probability
+        // have to clone parents here
+        // (tho probably not as much as we are)
+        // since we overwrite their population entries
+        // when they reproduce
+        for dna in self.0.iter_mut() {
+            dna.fitness();
 
-        let n = (dna.fitness * 100.0) as usize;
-        for _ in 0..n {
-            mating_pool.push(dna.clone());
+            let n = (dna.fitness * 100.0) as usize;
+            for _ in 0..n {
+                mating_pool.push(dna.clone());
+            }
         }
-    }
 
-    if mating_pool.is_empty() {
-        println!("population unfit for mating!");
-        return Ok(());
-    }
-
-    for dna in population.iter_mut() {
-        // select the parents
-        let a = rng.gen_range(0..mating_pool.len());
-        let mut b = rng.gen_range(0..mating_pool.len());
-        while a == b {
-            b = rng.gen_range(0..mating_pool.len());
+        if mating_pool.is_empty() {
+            println!("population unfit for mating!");
+            return Ok(());
         }
 
-        let parent_a = &mating_pool[a];
-        let parent_b = &mating_pool[b];
+        for dna in self.0.iter_mut() {
+            // select the parents
+            let a = rng.gen_range(0..mating_pool.len());
+            let mut b = rng.gen_range(0..mating_pool.len());
+            while a == b {
+                b = rng.gen_range(0..mating_pool.len());
+            }
 
-        let mut child = parent_a.crossover(parent_b, CrossoverMethod::Coin);
-        child.mutate(MUTATION_RATE);
+            let parent_a = &mating_pool[a];
+            let parent_b = &mating_pool[b];
 
-        *dna = child;
+            let mut child = parent_a.crossover(parent_b, CrossoverMethod::Coin);
+            child.mutate(MUTATION_RATE);
 
-        // calculate the new fitness for the best phrase check
-        dna.fitness();
-    }
+            *dna = child;
 
-    let mut best_phrase = 0;
-    for i in 1..population.len() {
-        if population[i].fitness > population[best_phrase].fitness {
-            best_phrase = i;
+            // calculate the new fitness for the best phrase check
+            dna.fitness();
         }
-    }
 
-    let fittest = &population[best_phrase];
+        let mut best_phrase = 0;
+        for i in 1..self.0.len() {
+            if self.0[i].fitness > self.0[best_phrase].fitness {
+                best_phrase = i;
+            }
+        }
 
-    core::text(
-        screen,
-        format!("Best phrase: {}", fittest.phrase()),
-        0.0,
-        0.0,
-    )?;
+        let fittest = &self.0[best_phrase];
+
+        core::fill_grayscale(canvas, 0.0);
+
+        core::text_size(canvas, 24.0);
+        core::text(
+            canvas,
+            format!("Best phrase: {}", fittest.phrase()),
+            10.0,
+            40.0,
+        )?;
+
+        core::text_size(canvas, 16.0);
+        core::text(
+            canvas,
+            format!("Generation: {}", fittest.generation),
+            10.0,
+            70.0,
+        )?;
+
+        if (fittest.fitness - 1.0).abs() < 0.01 {
+            println!("Generated target in {} generations", fittest.generation);
+            std::process::exit(0);
+        }
 
-    if (fittest.fitness - 1.0).abs() < 0.01 {
-        println!("Generated target in {} generations", fittest.generation);
-        std::process::exit(0);
+        Ok(())
     }
-
-    Ok(())
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let population = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
+    core::run_sketch(WIDTH, HEIGHT, Population::random(TARGET, TOTAL_POPULATION))
+}
 
-            let mut p: Vec<Dna> = vec![];
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            for _ in 0..TOTAL_POPULATION {
-                p.push(Dna::random(TARGET));
-            }
+    #[test]
+    fn population_snapshot() {
+        core::random::set_seed(1);
 
-            *population.borrow_mut() = Some(p);
+        let mut population = Population::random(TARGET, TOTAL_POPULATION);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 10, &mut population).unwrap();
 
-            Ok(screen)
-        },
-        |screen, dt| draw(screen, dt, population.borrow_mut().as_mut().unwrap()),
-    )?;
-
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/population.png"),
+        );
+    }
 }
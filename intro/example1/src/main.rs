@@ -1,10 +1,10 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 const EXERCISE_1_1: bool = false;
 const EXERCISE_1_3: bool = true;
 
@@ -15,20 +15,20 @@ struct Walker {
 }
 
 impl Walker {
-    fn new(screen: &Screen) -> Self {
+    fn new(width: u32, height: u32) -> Self {
         Self {
-            x: (screen.width() / 2) as i32,
-            y: (screen.height() / 2) as i32,
+            x: (width / 2) as i32,
+            y: (height / 2) as i32,
         }
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::shapes::point(screen, self.x as f64, self.y as f64)
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::shapes::point(canvas, self.x as f64, self.y as f64)
     }
 
-    fn step(&mut self, screen: &mut Screen) {
-        let mut rng = rand::thread_rng();
+    fn step(&mut self) {
+        let mut rng = core::random::rng();
 
         let (stepx, stepy) = if EXERCISE_1_1 {
             // bias down (+y) and to the right (+x)
@@ -41,11 +41,9 @@ impl Walker {
             let towards_mouse = rng.gen_bool(0.5);
             if towards_mouse {
                 // this suffers from https://github.com/rennis250/processing-rs/issues/7
-                println!("mouse pos: {}, {}", screen.mouse_x(), screen.mouse_y());
-                (
-                    screen.mouse_x() as i32 - self.x,
-                    screen.mouse_y() as i32 - self.y,
-                )
+                let mouse = core::input::mouse_position();
+                println!("mouse pos: {}, {}", mouse.x, mouse.y);
+                (mouse.x as i32 - self.x, mouse.y as i32 - self.y)
             } else {
                 println!("random");
                 (rng.gen_range(0..3) - 1, rng.gen_range(0..3) - 1)
@@ -60,31 +58,37 @@ impl Walker {
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    let mut screen = core::create_canvas(640, 360)?;
-    core::background_grayscale(&mut screen, 255.0);
-
-    Ok(screen)
-}
+impl Sketch for Walker {
+    fn setup<C: Canvas>(&mut self, canvas: &mut C) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-fn draw(screen: &mut Screen, walker: &mut Walker) -> Result<(), ProcessingErr> {
-    walker.step(screen);
-    walker.display(screen)?;
+        Ok(())
+    }
 
-    Ok(())
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        self.step();
+        self.display(canvas)
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let walker = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
-            *walker.borrow_mut() = Some(Walker::new(&screen));
-            Ok(screen)
-        },
-        |screen, _| draw(screen, walker.borrow_mut().as_mut().unwrap()),
-    )?;
-
-    Ok(())
+    core::run_sketch(WIDTH, HEIGHT, Walker::new(WIDTH, HEIGHT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walker_snapshot() {
+        core::random::set_seed(1);
+
+        let mut walker = Walker::new(WIDTH, HEIGHT);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 500, &mut walker).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/walker.png"),
+        );
+    }
 }
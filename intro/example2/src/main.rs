@@ -1,39 +1,59 @@
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 240)
-}
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 240;
 
-fn draw(screen: &mut Screen, random_counts: &mut Vec<u32>) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
-
-    let mut rng = rand::thread_rng();
-    let index = rng.gen_range(0..random_counts.len());
-    random_counts[index] += 1;
-
-    core::stroke_grayscale(screen, 0.0);
-    core::fill_grayscale(screen, 175.0);
-
-    let w = screen.width() / random_counts.len() as u32;
-    for (x, v) in random_counts.iter().enumerate() {
-        core::shapes::rect(
-            screen,
-            (x as u32 * w) as f64,
-            (screen.height() - v) as f64,
-            (w - 1) as f64,
-            *v as f64,
-        )?;
-    }
+#[derive(Debug)]
+struct RandomCounts(Vec<u32>);
+
+impl Sketch for RandomCounts {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        let random_counts = &mut self.0;
+
+        core::background_grayscale(canvas, 255.0);
+
+        let mut rng = core::random::rng();
+        let index = rng.gen_range(0..random_counts.len());
+        random_counts[index] += 1;
+
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 175.0);
 
-    Ok(())
+        let w = canvas.width() / random_counts.len() as u32;
+        for (x, v) in random_counts.iter().enumerate() {
+            core::shapes::rect(
+                canvas,
+                (x as u32 * w) as f64,
+                (canvas.height() - v) as f64,
+                (w - 1) as f64,
+                *v as f64,
+            )?;
+        }
+
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let mut random_counts = vec![0; 20];
+    core::run_sketch(WIDTH, HEIGHT, RandomCounts(vec![0; 20]))
+}
 
-    core::run(setup, |screen, _| draw(screen, &mut random_counts))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn random_counts_snapshot() {
+        core::random::set_seed(1);
+
+        let mut random_counts = RandomCounts(vec![0; 20]);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 500, &mut random_counts).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/random-counts.png"),
+        );
+    }
 }
@@ -1,26 +1,51 @@
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand_distr::{Distribution, Normal};
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+#[derive(Debug)]
+struct Gaussian(Normal<f64>);
+
+impl Default for Gaussian {
+    fn default() -> Self {
+        Self(Normal::new(320.0, 60.0).unwrap())
+    }
 }
 
-fn draw(screen: &mut Screen, _: f64) -> Result<(), ProcessingErr> {
-    let mut rng = rand::thread_rng();
-    let normal = Normal::new(320.0, 60.0).unwrap();
+impl Sketch for Gaussian {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        let mut rng = core::random::rng();
 
-    let x = normal.sample(&mut rng);
+        let x = self.0.sample(&mut rng);
 
-    screen.stroke_off();
-    core::fill_grayscale_alpha(screen, 0.0, 10.0);
-    core::shapes::ellipse(screen, x, 180.0, 16.0, 16.0)?;
+        core::stroke_off(canvas);
+        core::fill_grayscale_alpha(canvas, 0.0, 10.0);
+        core::shapes::ellipse(canvas, x, 180.0, 16.0, 16.0)?;
 
-    Ok(())
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    core::run(setup, draw)?;
+    core::run_sketch(WIDTH, HEIGHT, Gaussian::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaussian_snapshot() {
+        core::random::set_seed(1);
+
+        let mut gaussian = Gaussian::default();
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 200, &mut gaussian).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/gaussian.png"),
+        );
+    }
 }
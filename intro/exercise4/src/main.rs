@@ -1,35 +1,61 @@
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand_distr::{Distribution, Normal};
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+#[derive(Debug)]
+struct Dots(Vec<(f64, f64)>);
+
+impl Dots {
+    fn random(count: usize) -> Self {
+        let mut dots = vec![(0.0, 0.0); count];
 
-fn draw(screen: &mut Screen, dots: impl AsRef<[(f64, f64)]>) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+        let mut rng = core::random::rng();
+        let normal_x = Normal::new(320.0, 60.0).unwrap();
+        let normal_y = Normal::new(180.0, 30.0).unwrap();
 
-    screen.stroke_off();
-    core::fill_rgba(screen, 255.0, 0.0, 0.0, 50.0);
-    for dot in dots.as_ref() {
-        core::shapes::ellipse(screen, dot.0, dot.1, 16.0, 16.0)?;
+        for dot in &mut dots {
+            *dot = (normal_x.sample(&mut rng), normal_y.sample(&mut rng));
+        }
+
+        Self(dots)
     }
+}
+
+impl Sketch for Dots {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    Ok(())
+        core::stroke_off(canvas);
+        core::fill_rgba(canvas, 255.0, 0.0, 0.0, 50.0);
+        for dot in self.0.iter() {
+            core::shapes::ellipse(canvas, dot.0, dot.1, 16.0, 16.0)?;
+        }
+
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let mut dots = vec![(0.0, 0.0); 1000];
+    core::run_sketch(WIDTH, HEIGHT, Dots::random(1000))
+}
 
-    let mut rng = rand::thread_rng();
-    let normal_x = Normal::new(320.0, 60.0).unwrap();
-    let normal_y = Normal::new(180.0, 30.0).unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for dot in &mut dots {
-        *dot = (normal_x.sample(&mut rng), normal_y.sample(&mut rng));
-    }
+    #[test]
+    fn dots_snapshot() {
+        core::random::set_seed(1);
 
-    core::run(setup, |screen, _| draw(screen, &dots))?;
+        let mut dots = Dots::random(1000);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 1, &mut dots).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/dots.png"),
+        );
+    }
 }
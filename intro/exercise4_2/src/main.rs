@@ -1,8 +1,11 @@
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 use rand_distr::{Distribution, Normal};
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 // TODO: this is not right :(
 
 #[derive(Debug, Default, Clone)]
@@ -25,33 +28,56 @@ impl Color {
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
+#[derive(Debug)]
+struct Dots(Vec<Color>);
+
+impl Dots {
+    fn random(count: usize) -> Self {
+        let mut dots = vec![Color::default(); count];
 
-fn draw(screen: &mut Screen, dots: impl AsRef<[Color]>) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+        let mut rng = core::random::rng();
+        let normal = Normal::new(128.0, 65.0).unwrap();
 
-    screen.stroke_off();
-    for dot in dots.as_ref() {
-        core::fill_rgba(screen, dot.r, dot.g, dot.b, 50.0);
-        core::shapes::ellipse(screen, dot.x, dot.y, 16.0, 16.0)?;
+        for dot in &mut dots {
+            dot.randomize(&mut rng, &normal);
+        }
+
+        Self(dots)
     }
+}
+
+impl Sketch for Dots {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    Ok(())
+        core::stroke_off(canvas);
+        for dot in self.0.iter() {
+            core::fill_rgba(canvas, dot.r, dot.g, dot.b, 50.0);
+            core::shapes::ellipse(canvas, dot.x, dot.y, 16.0, 16.0)?;
+        }
+
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let mut dots = vec![Color::default(); 1000];
+    core::run_sketch(WIDTH, HEIGHT, Dots::random(1000))
+}
 
-    let mut rng = rand::thread_rng();
-    let normal = Normal::new(128.0, 65.0).unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for dot in &mut dots {
-        dot.randomize(&mut rng, &normal);
-    }
+    #[test]
+    fn dots_snapshot() {
+        core::random::set_seed(1);
 
-    core::run(setup, |screen, _| draw(screen, &dots))?;
+        let mut dots = Dots::random(1000);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 1, &mut dots).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/dots.png"),
+        );
+    }
 }
@@ -1,11 +1,11 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 use rand_distr::{Distribution, Normal};
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 #[derive(Debug, Default)]
 struct Walker {
     x: i32,
@@ -13,20 +13,20 @@ struct Walker {
 }
 
 impl Walker {
-    fn new(screen: &mut Screen) -> Self {
+    fn new(width: u32, height: u32) -> Self {
         Self {
-            x: (screen.width() / 2) as i32,
-            y: (screen.height() / 2) as i32,
+            x: (width / 2) as i32,
+            y: (height / 2) as i32,
         }
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::shapes::point(screen, self.x as f64, self.y as f64)
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::shapes::point(canvas, self.x as f64, self.y as f64)
     }
 
-    fn step(&mut self, screen: &Screen) {
-        let mut rng = rand::thread_rng();
+    fn step(&mut self, canvas: &impl Canvas) {
+        let mut rng = core::random::rng();
 
         let normal_x = Normal::new(5.0, 2.0).unwrap();
         let normal_y = Normal::new(5.0, 2.0).unwrap();
@@ -37,36 +37,42 @@ impl Walker {
         let dirx: i32 = rng.gen_range(0..3) - 1;
         let diry: i32 = rng.gen_range(0..3) - 1;
 
-        self.x = core::math::clamp(self.x + dirx.signum() * stepx, 0, screen.width() as i32);
-        self.y = core::math::clamp(self.y + diry.signum() * stepy, 0, screen.height() as i32);
+        self.x = core::math::clamp(self.x + dirx.signum() * stepx, 0, canvas.width() as i32);
+        self.y = core::math::clamp(self.y + diry.signum() * stepy, 0, canvas.height() as i32);
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    let mut screen = core::create_canvas(640, 360)?;
-    core::background_grayscale(&mut screen, 255.0);
-
-    Ok(screen)
-}
+impl Sketch for Walker {
+    fn setup<C: Canvas>(&mut self, canvas: &mut C) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-fn draw(screen: &mut Screen, walker: &mut Walker) -> Result<(), ProcessingErr> {
-    walker.step(screen);
-    walker.display(screen)?;
+        Ok(())
+    }
 
-    Ok(())
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        self.step(canvas);
+        self.display(canvas)
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let walker = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let mut screen = setup()?;
-            *walker.borrow_mut() = Some(Walker::new(&mut screen));
-            Ok(screen)
-        },
-        |screen, _| draw(screen, walker.borrow_mut().as_mut().unwrap()),
-    )?;
-
-    Ok(())
+    core::run_sketch(WIDTH, HEIGHT, Walker::new(WIDTH, HEIGHT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walker_snapshot() {
+        core::random::set_seed(1);
+
+        let mut walker = Walker::new(WIDTH, HEIGHT);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 500, &mut walker).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/walker.png"),
+        );
+    }
 }
@@ -1,8 +1,12 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
+use processing::MouseButton;
+
+const WIDTH: u32 = 600;
+const HEIGHT: u32 = 600;
+
+// generations grown before the plant is written out as SVG
+const SVG_GENERATIONS: usize = 3;
 
 #[derive(Debug)]
 struct Rule {
@@ -32,29 +36,29 @@ impl Turtle {
         }
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale_alpha(screen, 0.0, 175.0);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale_alpha(canvas, 0.0, 175.0);
 
         for c in self.todo.chars() {
             match c {
                 'F' | 'f' => {
-                    core::shapes::line(screen, 0.0, 0.0, self.len, 0.0)?;
-                    core::translate(screen, self.len, 0.0);
+                    core::shapes::line(canvas, 0.0, 0.0, self.len, 0.0)?;
+                    core::translate(canvas, self.len, 0.0);
                 }
                 'G' | 'g' => {
-                    core::translate(screen, self.len, 0.0);
+                    core::translate(canvas, self.len, 0.0);
                 }
                 '+' => {
-                    core::rotate(screen, self.theta);
+                    core::rotate(canvas, self.theta);
                 }
                 '-' => {
-                    core::rotate(screen, -self.theta);
+                    core::rotate(canvas, -self.theta);
                 }
                 '[' => {
-                    screen.push_matrix();
+                    canvas.push_matrix();
                 }
                 ']' => {
-                    screen.pop_matrix();
+                    canvas.pop_matrix();
                 }
                 _ => {
                     panic!("Invalid alphabet {}", c);
@@ -99,60 +103,80 @@ impl LSystem {
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(600, 600)
+#[derive(Debug)]
+struct Plant {
+    lsys: LSystem,
+    turtle: Turtle,
 }
 
-fn draw(
-    screen: &mut Screen,
-    _: f64,
-    lsys: &mut LSystem,
-    turtle: &mut Turtle,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+impl Plant {
+    fn new(width: f64) -> Self {
+        let ruleset = vec![Rule::new('F', "FF+[+F-F-F]-[-F+F+F]")];
+        let lsys = LSystem::new("F", ruleset);
 
-    if core::input::mouse_is_pressed() {
-        lsys.generate();
+        let turtle = Turtle::new(lsys.sentence.clone(), width / 4.0, 25.0f64.to_radians());
 
-        turtle.todo = lsys.sentence.clone();
-        turtle.len *= 0.5;
+        Self { lsys, turtle }
     }
 
-    core::translate(screen, screen.width() as f64 / 2.0, screen.height() as f64);
-    turtle.display(screen)?;
+    fn grow(&mut self) {
+        self.lsys.generate();
+
+        self.turtle.todo = self.lsys.sentence.clone();
+        self.turtle.len *= 0.5;
+    }
+}
 
-    Ok(())
+impl Sketch for Plant {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
+
+        // one generation per click
+        if core::input::mouse_pressed(MouseButton::Left) {
+            self.grow();
+        }
+
+        core::translate(canvas, canvas.width() as f64 / 2.0, canvas.height() as f64);
+        self.turtle.display(canvas)?;
+
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let lsys = Rc::new(RefCell::new(None));
-    let turtle = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
-
-            let ruleset = vec![Rule::new('F', "FF+[+F-F-F]-[-F+F+F]")];
-
-            *lsys.borrow_mut() = Some(LSystem::new("F", ruleset));
-
-            *turtle.borrow_mut() = Some(Turtle::new(
-                lsys.borrow().as_ref().unwrap().sentence.clone(),
-                screen.width() as f64 / 4.0,
-                25.0f64.to_radians(),
-            ));
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                lsys.borrow_mut().as_mut().unwrap(),
-                turtle.borrow_mut().as_mut().unwrap(),
-            )
-        },
-    )?;
-
-    Ok(())
+    // --svg <path> writes a grown plant out as SVG instead of opening a window
+    if let Some(path) = core::svg::output_from_args() {
+        let mut svg = core::svg::Svg::new(WIDTH, HEIGHT);
+
+        let mut plant = Plant::new(WIDTH as f64);
+        for _ in 0..SVG_GENERATIONS {
+            plant.grow();
+        }
+        plant.draw(&mut svg, 0.0)?;
+
+        return svg.save(path);
+    }
+
+    core::run_sketch(WIDTH, HEIGHT, Plant::new(WIDTH as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plant_snapshot() {
+        let mut plant = Plant::new(WIDTH as f64);
+        for _ in 0..3 {
+            plant.grow();
+        }
+
+        let canvas =
+            core::headless::render(WIDTH, HEIGHT, 1, |canvas, dt| plant.draw(canvas, dt)).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/plant.png"),
+        );
+    }
 }
@@ -1,11 +1,14 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::fbm::Fbm;
+use core::mover::{EdgePolicy, Mover};
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 #[derive(Debug, Default)]
 struct Liquid {
     location: DVec2,
@@ -30,12 +33,12 @@ impl Liquid {
             && mover.location.y < self.location.y + self.size.y
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        screen.stroke_off();
-        core::fill_grayscale(screen, 175.0);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_off(canvas);
+        core::fill_grayscale(canvas, 175.0);
 
         core::shapes::rect(
-            screen,
+            canvas,
             self.location.x,
             self.location.y,
             self.size.x,
@@ -44,195 +47,126 @@ impl Liquid {
     }
 }
 
-#[derive(Debug, Default)]
-struct Mover {
-    location: DVec2,
-    velocity: DVec2,
-    acceleration: DVec2,
-    mass: f64,
+#[allow(dead_code)]
+fn noise_force(t: f64, frequency: f64) -> DVec2 {
+    let mut rng = core::random::rng();
+
+    let noise = Fbm::new().frequency(frequency);
+    noise.vector2(t) * rng.gen_range(0.1..0.5)
 }
 
-impl Mover {
-    fn new(mass: f64, x: f64, y: f64) -> Self {
-        Self {
-            location: DVec2::new(x, y),
-            mass,
-            ..Default::default()
-        }
-    }
+fn display_mover(mover: &Mover, canvas: &mut impl Canvas, alpha: f64) -> Result<(), ProcessingErr> {
+    core::stroke_grayscale(canvas, 0.0);
+    core::fill_grayscale(canvas, 0.0);
+
+    let location = mover.interpolated_location(alpha);
+    core::shapes::ellipse(
+        canvas,
+        location.x,
+        location.y,
+        mover.mass * 16.0,
+        mover.mass * 16.0,
+    )
+}
 
-    #[allow(dead_code)]
-    fn wrap_edges(&mut self, screen: &Screen) {
-        if self.location.x > screen.width() as f64 {
-            self.location.x = 0.0;
-        } else if self.location.x < 0.0 {
-            self.location.x = screen.width() as f64;
-        }
+#[derive(Debug)]
+struct Pool {
+    movers: Vec<Mover>,
+    liquid: Liquid,
+    size: DVec2,
+}
 
-        if self.location.y > screen.height() as f64 {
-            self.location.y = 0.0;
-        } else if self.location.y < 0.0 {
-            self.location.y = screen.height() as f64;
-        }
-    }
+impl Pool {
+    fn new(width: u32, height: u32) -> Self {
+        let mut rng = core::random::rng();
 
-    #[allow(dead_code)]
-    fn stop_edges(&mut self, screen: &Screen) {
-        if self.location.x > screen.width() as f64 {
-            self.location.x = screen.width() as f64;
-        } else if self.location.x < 0.0 {
-            self.location.x = 0.0;
-        }
+        let mut movers = vec![];
+        for _ in 0..100 {
+            let x = rng.gen_range(0..width) as f64;
+            let y = rng.gen_range(0..height / 4) as f64;
 
-        if self.location.y > screen.height() as f64 {
-            self.location.y = screen.height() as f64;
-        } else if self.location.y < 0.0 {
-            self.location.y = 0.0;
+            let mut mover = Mover::new(rng.gen_range(0.1..5.0), x, y);
+            mover.edge_policy = EdgePolicy::Bounce;
+            movers.push(mover);
         }
-    }
 
-    #[allow(dead_code)]
-    fn bounce_edges(&mut self, screen: &Screen) {
-        if self.location.x > screen.width() as f64 {
-            self.location.x = screen.width() as f64;
-            self.velocity.x *= -1.0;
-        } else if self.location.x < 0.0 {
-            self.location.x = 0.0;
-            self.velocity.x *= -1.0;
-        }
+        let liquid = Liquid::new(
+            0.0,
+            height as f64 / 2.0,
+            width as f64,
+            height as f64 / 2.0,
+            0.1,
+        );
 
-        if self.location.y > screen.height() as f64 {
-            self.location.y = screen.height() as f64;
-            self.velocity.y *= -1.0;
-        } else if self.location.y < 0.0 {
-            self.location.y = 0.0;
-            self.velocity.y *= -1.0;
+        Self {
+            movers,
+            liquid,
+            size: DVec2::new(width as f64, height as f64),
         }
     }
+}
 
-    #[allow(dead_code)]
-    fn apply_force(&mut self, force: DVec2) {
-        let force = if self.mass > 0.0 {
-            force / self.mass
-        } else {
-            force
-        };
-        self.acceleration += force;
-    }
+impl FixedSketch for Pool {
+    fn update(&mut self, dt: f64) {
+        //let wind = Vector2::new(0.01, 0.0);
+        let gravity = DVec2::new(0.0, 100.0);
 
-    #[allow(dead_code)]
-    fn apply_noise_force(&mut self, t: f64, frequency: f64) {
-        let mut rng = rand::thread_rng();
+        //let c = 0.01;
 
-        self.apply_force(
-            core::math::vector2_random() * core::noise(t, frequency) * rng.gen_range(0.1..0.5),
-        );
-    }
+        for mover in self.movers.iter_mut() {
+            /*let friction = (mover.velocity * -1.0).normalize_or_zero() * c;
+            mover.apply_force(friction);*/
+
+            if self.liquid.contains(mover) {
+                mover.apply_force(mover.drag(self.liquid.c));
+            }
 
-    fn drag(&mut self, liquid: &Liquid) {
-        let speed = self.velocity.length();
-        let drag_magnitude = liquid.c * speed * speed;
+            //mover.apply_force(wind);
+            mover.apply_force(gravity * mover.mass);
 
-        let drag = (self.velocity * -1.0).normalize_or_zero() * drag_magnitude;
-        self.apply_force(drag);
+            mover.update(dt);
+            mover.check_bounds(DVec2::ZERO, self.size);
+        }
     }
 
-    fn update(&mut self, dt: f64) {
-        self.velocity += self.acceleration * dt;
-        self.location += self.velocity * dt;
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-        self.acceleration = DVec2::default();
-    }
+        self.liquid.display(canvas)?;
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 0.0);
+        for mover in self.movers.iter() {
+            display_mover(mover, canvas, alpha)?;
+        }
 
-        core::shapes::ellipse(
-            screen,
-            self.location.x,
-            self.location.y,
-            self.mass * 16.0,
-            self.mass * 16.0,
-        )
+        Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+fn main() -> Result<(), ProcessingErr> {
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Pool::new(WIDTH, HEIGHT),
+    )
 }
 
-fn draw(
-    screen: &mut Screen,
-    dt: f64,
-    mut movers: impl AsMut<[Mover]>,
-    liquid: &Liquid,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
-
-    liquid.display(screen)?;
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
 
-    //let wind = Vector2::new(0.01, 0.0);
-    let gravity = DVec2::new(0.0, 100.0);
+    use super::*;
 
-    //let c = 0.01;
+    #[test]
+    fn pool_snapshot() {
+        core::random::set_seed(1);
 
-    for mover in movers.as_mut() {
-        /*let friction = (mover.velocity * -1.0).normalize_or_zero() * c;
-        mover.apply_force(friction);*/
+        let mut pool = FixedSteps::new(FixedTimestep::default(), Pool::new(WIDTH, HEIGHT));
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 60, &mut pool).unwrap();
 
-        if liquid.contains(mover) {
-            mover.drag(liquid);
-        }
-
-        //mover.apply_force(wind);
-        mover.apply_force(gravity * mover.mass);
-
-        mover.update(dt);
-        mover.bounce_edges(screen);
-        mover.display(screen)?;
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/pool.png"),
+        );
     }
-
-    Ok(())
-}
-
-fn main() -> Result<(), ProcessingErr> {
-    let movers = Rc::new(RefCell::new(None));
-    let liquid = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let mut rng = rand::thread_rng();
-
-            let screen = setup()?;
-
-            let mut mvrs = vec![];
-            for _ in 0..100 {
-                let x = rng.gen_range(0..screen.width()) as f64;
-                let y = rng.gen_range(0..screen.height() / 4) as f64;
-                mvrs.push(Mover::new(rng.gen_range(0.1..5.0), x, y));
-            }
-            *movers.borrow_mut() = Some(mvrs);
-
-            *liquid.borrow_mut() = Some(Liquid::new(
-                0.0,
-                screen.height() as f64 / 2.0,
-                screen.width() as f64,
-                screen.height() as f64 / 2.0,
-                0.1,
-            ));
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                movers.borrow_mut().as_mut().unwrap(),
-                liquid.borrow().as_ref().unwrap(),
-            )
-        },
-    )?;
-
-    Ok(())
 }
@@ -1,118 +1,98 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
-use glam::DVec2;
+use core::mover::{Mover, UNIT_STEP};
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 const G: f64 = 1.0;
 
-#[derive(Debug, Default)]
-struct Mover {
-    location: DVec2,
-    velocity: DVec2,
-    acceleration: DVec2,
-    mass: f64,
+fn display_mover(mover: &Mover, canvas: &mut impl Canvas, alpha: f64) -> Result<(), ProcessingErr> {
+    core::stroke_grayscale(canvas, 0.0);
+    core::fill_grayscale(canvas, 0.0);
+
+    let location = mover.interpolated_location(alpha);
+    core::shapes::ellipse(
+        canvas,
+        location.x,
+        location.y,
+        mover.mass * 16.0,
+        mover.mass * 16.0,
+    )
 }
 
-impl Mover {
-    fn new(mass: f64, x: f64, y: f64) -> Self {
-        Self {
-            location: DVec2::new(x, y),
-            mass,
-            ..Default::default()
-        }
-    }
+#[derive(Debug)]
+struct Bodies(Vec<Mover>);
 
-    fn attract(&self, mover: &Mover) -> DVec2 {
-        let force = self.location - mover.location;
-        let distance = core::math::clampf(force.length(), 5.0, 20.0);
-        let force = force.normalize_or_zero();
-        let strength = (G * self.mass * mover.mass) / (distance * distance);
+impl Bodies {
+    fn random(width: u32, height: u32) -> Self {
+        let mut rng = core::random::rng();
 
-        force * strength
-    }
+        let mut movers = vec![];
+        for _ in 0..10 {
+            let x = rng.gen_range(0..width) as f64;
+            let y = rng.gen_range(0..height) as f64;
+            movers.push(Mover::new(rng.gen_range(0.1..2.0), x, y));
+        }
 
-    fn apply_force(&mut self, force: DVec2) {
-        let force = if self.mass > 0.0 {
-            force / self.mass
-        } else {
-            force
-        };
-        self.acceleration += force;
+        Self(movers)
     }
+}
 
+impl FixedSketch for Bodies {
     fn update(&mut self, _dt: f64) {
-        self.velocity += self.acceleration; // * dt;
-        self.location += self.velocity; // * dt;
-
-        self.acceleration = DVec2::default();
-    }
+        let movers = &mut self.0;
+
+        for i in 0..movers.len() {
+            for j in 0..movers.len() {
+                if i != j {
+                    let f = movers[j].attraction(&movers[i], G, 5.0, 20.0);
+                    movers[i].apply_force(f);
+                }
+            }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 0.0);
-
-        core::shapes::ellipse(
-            screen,
-            self.location.x,
-            self.location.y,
-            self.mass * 16.0,
-            self.mass * 16.0,
-        )
+            movers[i].update(UNIT_STEP);
+        }
     }
-}
-
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
-
-fn draw(
-    screen: &mut Screen,
-    dt: f64,
-    mut movers: impl AsMut<[Mover]>,
-) -> Result<(), ProcessingErr> {
-    let movers = movers.as_mut();
 
-    core::background_grayscale(screen, 255.0);
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    for i in 0..movers.len() {
-        for j in 0..movers.len() {
-            if i != j {
-                let f = movers[j].attract(&movers[i]);
-                movers[i].apply_force(f);
-            }
+        for mover in self.0.iter() {
+            display_mover(mover, canvas, alpha)?;
         }
 
-        movers[i].update(dt);
-        movers[i].display(screen)?;
+        Ok(())
     }
-
-    Ok(())
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let movers = Rc::new(RefCell::new(None));
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Bodies::random(WIDTH, HEIGHT),
+    )
+}
 
-    core::run(
-        || {
-            let mut rng = rand::thread_rng();
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
 
-            let screen = setup()?;
+    use super::*;
 
-            let mut mvrs = vec![];
-            for _ in 0..10 {
-                let x = rng.gen_range(0..screen.width()) as f64;
-                let y = rng.gen_range(0..screen.height()) as f64;
-                mvrs.push(Mover::new(rng.gen_range(0.1..2.0), x, y));
-            }
-            *movers.borrow_mut() = Some(mvrs);
+    #[test]
+    fn bodies_snapshot() {
+        core::random::set_seed(1);
 
-            Ok(screen)
-        },
-        |screen, dt| draw(screen, dt, movers.borrow_mut().as_mut().unwrap()),
-    )?;
+        let mut bodies = FixedSteps::new(FixedTimestep::default(), Bodies::random(WIDTH, HEIGHT));
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 60, &mut bodies).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/bodies.png"),
+        );
+    }
 }
@@ -1,11 +1,14 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use core::{Canvas, Sketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 #[derive(Debug)]
 struct Neuron {
     location: DVec2,
@@ -48,13 +51,13 @@ impl Neuron {
         }
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 0.0);
-        core::shapes::ellipse(screen, self.location.x, self.location.y, 16.0, 16.0)?;
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 0.0);
+        core::shapes::ellipse(canvas, self.location.x, self.location.y, 16.0, 16.0)?;
 
         for connection in self.connections.iter() {
-            connection.display(screen)?;
+            connection.display(canvas)?;
         }
 
         Ok(())
@@ -107,18 +110,18 @@ impl Connection {
         }
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        screen.stroke_weight(1.0 + self.weight * 4.0);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::stroke_weight(canvas, 1.0 + self.weight as f64 * 4.0);
 
         let a = &self.a.borrow().location;
         let b = &self.b.borrow().location;
-        core::shapes::line(screen, a.x, a.y, b.x, b.y)?;
+        core::shapes::line(canvas, a.x, a.y, b.x, b.y)?;
 
         if self.sending {
-            core::fill_grayscale(screen, 0.0);
-            screen.stroke_weight(1.0);
-            core::shapes::ellipse(screen, self.sender.x, self.sender.y, 16.0, 16.0)?;
+            core::fill_grayscale(canvas, 0.0);
+            core::stroke_weight(canvas, 1.0);
+            core::shapes::ellipse(canvas, self.sender.x, self.sender.y, 16.0, 16.0)?;
         }
 
         Ok(())
@@ -146,7 +149,7 @@ impl Network {
     }
 
     fn connect(&self, a: Rc<RefCell<Neuron>>, b: Rc<RefCell<Neuron>>) {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         a.borrow_mut()
             .add_connection(Connection::new(a.clone(), b, rng.gen_range(0.0..1.0)));
@@ -162,68 +165,83 @@ impl Network {
         }
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        screen.push_matrix();
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::push_matrix(canvas);
 
-        core::translatev(screen, self.location);
+        core::translatev(canvas, self.location);
 
         for neuron in self.neurons.iter() {
-            neuron.borrow().display(screen)?;
+            neuron.borrow().display(canvas)?;
         }
 
-        screen.pop_matrix();
+        core::pop_matrix(canvas);
 
         Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+#[derive(Debug)]
+struct Signals {
+    network: Network,
+    frame_count: usize,
 }
 
-fn draw(screen: &mut Screen, _: f64, network: &mut Network) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+impl Signals {
+    fn new(width: u32, height: u32) -> Self {
+        let mut network = Network::new(width as f64 / 2.0, height as f64 / 2.0);
 
-    network.update();
-    network.display(screen)?;
+        let a = network.add_neuron(Neuron::new(-200.0, 0.0));
+        let b = network.add_neuron(Neuron::new(0.0, 100.0));
+        let c = network.add_neuron(Neuron::new(0.0, -100.0));
+        let d = network.add_neuron(Neuron::new(200.0, 0.0));
 
-    if screen.frame_count() % 30 == 0 {
-        let mut rng = rand::thread_rng();
-        network.feedforward(rng.gen_range(0.0..1.0));
-    }
+        network.connect(a.clone(), b.clone());
+        network.connect(a, c.clone());
+        network.connect(b, d.clone());
+        network.connect(c, d);
 
-    Ok(())
+        Self {
+            network,
+            frame_count: 0,
+        }
+    }
 }
 
-fn main() -> Result<(), ProcessingErr> {
-    let network = Rc::new(RefCell::new(None));
+impl Sketch for Signals {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    core::run(
-        || {
-            let screen = setup()?;
+        self.network.update();
+        self.network.display(canvas)?;
 
-            let mut n = Network::new(screen.width() as f64 / 2.0, screen.height() as f64 / 2.0);
+        if self.frame_count.is_multiple_of(30) {
+            let mut rng = core::random::rng();
+            self.network.feedforward(rng.gen_range(0.0..1.0));
+        }
+        self.frame_count += 1;
 
-            let a = n.add_neuron(Neuron::new(-200.0, 0.0));
-            let b = n.add_neuron(Neuron::new(0.0, 100.0));
-            let c = n.add_neuron(Neuron::new(0.0, -100.0));
-            let d = n.add_neuron(Neuron::new(200.0, 0.0));
+        Ok(())
+    }
+}
 
-            n.connect(a.clone(), b.clone());
-            n.connect(a, c.clone());
-            n.connect(b, d.clone());
-            n.connect(c, d);
+fn main() -> Result<(), ProcessingErr> {
+    core::run_sketch(WIDTH, HEIGHT, Signals::new(WIDTH, HEIGHT))
+}
 
-            *network.borrow_mut() = Some(n);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(screen, dt, network.borrow_mut().as_mut().unwrap())?;
+    #[test]
+    fn signals_snapshot() {
+        core::random::set_seed(1);
 
-            Ok(())
-        },
-    )?;
+        let mut signals = Signals::new(WIDTH, HEIGHT);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 100, &mut signals).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/signals.png"),
+        );
+    }
 }
@@ -1,11 +1,11 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 #[derive(Debug, Default)]
 struct Oscillator {
     angle: DVec2,
@@ -14,13 +14,13 @@ struct Oscillator {
 }
 
 impl Oscillator {
-    fn random(screen: &Screen) -> Self {
-        let mut rand = rand::thread_rng();
+    fn random(width: u32, height: u32) -> Self {
+        let mut rand = core::random::rng();
 
         Self {
             angle: DVec2::default(),
             velocity: DVec2::new(rand.gen_range(-0.05..0.05), rand.gen_range(-0.05..0.05)),
-            amplitude: DVec2::new(screen.width() as f64 / 2.0, screen.height() as f64 / 2.0),
+            amplitude: DVec2::new(width as f64 / 2.0, height as f64 / 2.0),
         }
     }
 
@@ -28,53 +28,56 @@ impl Oscillator {
         self.angle += self.velocity;
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
         let x = self.angle.x.sin() * self.amplitude.x;
         let y = self.angle.y.sin() * self.amplitude.y;
 
-        screen.push_matrix();
+        core::push_matrix(canvas);
 
         core::translate(
-            screen,
-            screen.width() as f64 / 2.0,
-            screen.height() as f64 / 2.0,
+            canvas,
+            canvas.width() as f64 / 2.0,
+            canvas.height() as f64 / 2.0,
         );
 
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 175.0);
-        core::shapes::line(screen, 0.0, 0.0, x, y)?;
-        core::shapes::ellipse(screen, x, y, 16.0, 16.0)?;
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 175.0);
+        core::shapes::line(canvas, 0.0, 0.0, x, y)?;
+        core::shapes::ellipse(canvas, x, y, 16.0, 16.0)?;
 
-        screen.pop_matrix();
+        core::pop_matrix(canvas);
 
         Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+impl Sketch for Oscillator {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
+
+        self.oscillate();
+        self.display(canvas)
+    }
 }
 
-fn draw(screen: &mut Screen, oscillator: &mut Oscillator) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+fn main() -> Result<(), ProcessingErr> {
+    core::run_sketch(WIDTH, HEIGHT, Oscillator::random(WIDTH, HEIGHT))
+}
 
-    oscillator.oscillate();
-    oscillator.display(screen)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
-}
+    #[test]
+    fn oscillator_snapshot() {
+        core::random::set_seed(1);
 
-fn main() -> Result<(), ProcessingErr> {
-    let oscillator = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
-            *oscillator.borrow_mut() = Some(Oscillator::random(&screen));
-            Ok(screen)
-        },
-        |screen, _| draw(screen, oscillator.borrow_mut().as_mut().unwrap()),
-    )?;
-
-    Ok(())
+        let mut oscillator = Oscillator::random(WIDTH, HEIGHT);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 30, &mut oscillator).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/oscillator.png"),
+        );
+    }
 }
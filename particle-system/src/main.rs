@@ -1,12 +1,17 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::color::Palette;
+use core::pixels::PixelBuffer;
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 use rand_distr::{Distribution, Normal};
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+const SMOKE_PATH: &str = "data/smoke.png";
+
 // design decision - using a tagged enum type
 // instead of a Trait to avoid having to Box individual particles
 
@@ -36,12 +41,12 @@ impl Repeller {
     }
 
     #[allow(dead_code)]
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 255.0);
-        core::fill_grayscale(screen, 255.0);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 255.0);
+        core::fill_grayscale(canvas, 255.0);
 
         core::shapes::ellipse(
-            screen,
+            canvas,
             self.location.x,
             self.location.y,
             self.r * 2.0,
@@ -55,50 +60,64 @@ enum ParticleType {
     Basic,
     Confetti,
 
-    // TODO: is there a way to make this a reference
-    // rather than having to allocate into an Rc ?
-    Smoke(Rc<processing::Texture2d>),
+    // drawn with the system's smoke image
+    Smoke,
 }
 
 impl ParticleType {
-    fn display(&self, screen: &mut Screen, core: &ParticleCore) -> Result<(), ProcessingErr> {
-        screen.push_matrix();
+    fn display<C: Canvas>(
+        &self,
+        canvas: &mut C,
+        core: &ParticleCore,
+        smoke: &C::Image,
+    ) -> Result<(), ProcessingErr> {
+        core::push_matrix(canvas);
 
-        core::translate(screen, core.location.x, core.location.y);
+        core::translate(canvas, core.location.x, core.location.y);
 
         match self {
             ParticleType::Basic => {
-                core::stroke_grayscale_alpha(screen, 0.0, core.lifespan as f32);
-                core::fill_grayscale_alpha(screen, 0.0, core.lifespan as f32);
+                // cool down as the particle ages
+                let color = Palette::fire().sample(core.lifespan / 255.0);
 
-                core::shapes::ellipse(screen, 0.0, 0.0, 8.0, 8.0)?;
+                core::stroke(canvas, color.with_alpha(core.lifespan as f32));
+                core::fill(canvas, color.with_alpha(core.lifespan as f32));
+
+                core::shapes::ellipse(canvas, 0.0, 0.0, 8.0, 8.0)?;
             }
             ParticleType::Confetti => {
-                let _theta = core::math::map(
+                let theta = core::math::map(
                     core.location.x,
                     0.0,
-                    screen.width() as f64,
+                    canvas.width() as f64,
                     0.0,
                     4.0 * std::f64::consts::PI,
                 );
 
-                core::stroke_grayscale_alpha(screen, 0.0, core.lifespan as f32);
-                core::fill_grayscale_alpha(screen, 175.0, core.lifespan as f32);
+                // hue follows the position across the canvas
+                let color = Palette::rainbow().sample(core.location.x / canvas.width() as f64);
+
+                core::stroke_grayscale_alpha(canvas, 0.0, core.lifespan as f32);
+                core::fill(canvas, color.with_alpha(core.lifespan as f32));
 
-                //core::rotate(screen, theta);
+                core::rotate(canvas, theta);
 
-                screen.rect_mode(&core::shapes::RectMode::Center.to_string());
-                core::shapes::rect(screen, 0.0, 0.0, 8.0, 8.0)?;
+                let state = canvas.draw_state();
+                core::shapes::rect_mode(canvas, core::shapes::RectMode::Center);
+                core::shapes::rect(canvas, 0.0, 0.0, 8.0, 8.0)?;
+                canvas.set_draw_state(state);
             }
-            ParticleType::Smoke(texture) => {
-                screen.blend_mode(&core::BlendMode::Add.to_string());
-                core::fill_grayscale_alpha(screen, 255.0, core.lifespan as f32);
+            ParticleType::Smoke => {
+                core::blend_mode(canvas, core::BlendMode::Add);
+                core::fill_grayscale_alpha(canvas, 255.0, core.lifespan as f32);
+
+                core::image(canvas, 0.0, 0.0, smoke)?;
 
-                core::image(screen, 0.0, 0.0, texture)?;
+                core::blend_mode(canvas, core::BlendMode::Blend);
             }
         }
 
-        screen.pop_matrix();
+        core::pop_matrix(canvas);
 
         Ok(())
     }
@@ -157,7 +176,7 @@ struct Particle {
 
 impl Particle {
     fn basic(location: DVec2) -> Self {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         let mut core = ParticleCore::new(location);
         core.velocity = DVec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-2.0..0.0));
@@ -169,7 +188,7 @@ impl Particle {
     }
 
     fn confetti(location: DVec2) -> Self {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         let mut core = ParticleCore::new(location);
         core.velocity = DVec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-2.0..0.0));
@@ -180,8 +199,8 @@ impl Particle {
         }
     }
 
-    fn smoke(location: DVec2, texture: Rc<processing::Texture2d>) -> Self {
-        let mut rng = rand::thread_rng();
+    fn smoke(location: DVec2) -> Self {
+        let mut rng = core::random::rng();
 
         let mut core = ParticleCore::new(location);
 
@@ -194,7 +213,7 @@ impl Particle {
 
         Self {
             core,
-            r#type: ParticleType::Smoke(texture),
+            r#type: ParticleType::Smoke,
         }
     }
 
@@ -210,15 +229,8 @@ impl Particle {
         self.core.update(dt);
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        self.r#type.display(screen, &self.core)?;
-
-        Ok(())
-    }
-
-    fn run(&mut self, screen: &mut Screen, dt: f64) -> Result<(), ProcessingErr> {
-        self.update(dt);
-        self.display(screen)?;
+    fn display<C: Canvas>(&self, canvas: &mut C, smoke: &C::Image) -> Result<(), ProcessingErr> {
+        self.r#type.display(canvas, &self.core, smoke)?;
 
         Ok(())
     }
@@ -228,24 +240,22 @@ struct ParticleSystem {
     origin: DVec2,
     particles: Vec<Particle>,
 
-    smoke: Rc<processing::Texture2d>,
+    // kept as pixels since the image type depends on the canvas
+    smoke: PixelBuffer,
 }
 
 impl ParticleSystem {
-    fn new(screen: &mut Screen, x: f64, y: f64) -> Result<Self, ProcessingErr> {
-        let image = processing::load_image("data/smoke.png")?;
-        let (smoke, _, _) = screen.texture(&image)?;
-
-        Ok(Self {
+    fn new(x: f64, y: f64, smoke: PixelBuffer) -> Self {
+        Self {
             origin: DVec2::new(x, y),
-            smoke: Rc::new(smoke),
+            smoke,
             particles: Vec::default(),
-        })
+        }
     }
 
     #[allow(dead_code)]
     fn add_particle(&mut self) {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         let c = rng.gen_range(0.0..1.0);
         if c < 0.5 {
@@ -257,14 +267,13 @@ impl ParticleSystem {
 
     #[allow(dead_code)]
     fn add_smoke(&mut self) {
-        self.particles
-            .push(Particle::smoke(self.origin, self.smoke.clone()));
+        self.particles.push(Particle::smoke(self.origin));
     }
 
     fn apply_force(&mut self, force: DVec2) {
         for particle in self.particles.iter_mut() {
             match particle.r#type {
-                ParticleType::Smoke(_) => {}
+                ParticleType::Smoke => {}
                 _ => particle.apply_force(force),
             }
         }
@@ -278,80 +287,105 @@ impl ParticleSystem {
         }
     }
 
-    fn run(&mut self, screen: &mut Screen, dt: f64) -> Result<(), ProcessingErr> {
-        // drain_filter() equivalent
-        let mut i = 0;
-        while i != self.particles.len() {
-            let particle = &mut self.particles[i];
-            particle.run(screen, dt)?;
-
-            if particle.is_dead() {
-                self.particles.remove(i);
-            } else {
-                i += 1;
-            }
+    fn update(&mut self, dt: f64) {
+        for particle in self.particles.iter_mut() {
+            particle.update(dt);
+        }
+
+        self.particles.retain(|particle| !particle.is_dead());
+    }
+
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        let smoke = canvas.create_image(&self.smoke)?;
+
+        for particle in self.particles.iter() {
+            particle.display(canvas, &smoke)?;
         }
 
         Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+struct Particles {
+    particle_system: ParticleSystem,
+
+    #[allow(dead_code)]
+    repeller: Repeller,
 }
 
-fn draw(
-    screen: &mut Screen,
-    dt: f64,
-    particle_system: &mut ParticleSystem,
-    _repeller: &Repeller,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 100.0);
+impl Particles {
+    fn new(width: u32, height: u32, smoke: PixelBuffer) -> Self {
+        let hw = width as f64 / 2.0;
+        let hh = height as f64 / 2.0;
 
-    //particle_system.add_particle();
-    particle_system.add_smoke();
+        Self {
+            particle_system: ParticleSystem::new(hw, hh /*50.0*/, smoke),
+            repeller: Repeller::new(hw - 20.0, hh),
+        }
+    }
+}
 
-    let gravity = DVec2::new(0.0, 0.1);
-    particle_system.apply_force(gravity);
+impl FixedSketch for Particles {
+    fn update(&mut self, dt: f64) {
+        //self.particle_system.add_particle();
+        self.particle_system.add_smoke();
+
+        let gravity = DVec2::new(0.0, 0.1);
+        self.particle_system.apply_force(gravity);
+
+        //self.particle_system.apply_repeller(&self.repeller);
+
+        self.particle_system.update(dt);
+    }
 
-    //particle_system.apply_repeller(repeller);
-    //repeller.display(screen)?;
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 100.0);
 
-    particle_system.run(screen, dt)?;
+        //self.repeller.display(canvas)?;
 
-    Ok(())
+        self.particle_system.display(canvas)
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let particle_system = Rc::new(RefCell::new(None));
-    let repeller = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let mut screen = setup()?;
-
-            let hw = screen.width() as f64 / 2.0;
-            let hh = screen.height() as f64 / 2.0;
-
-            *particle_system.borrow_mut() = Some(ParticleSystem::new(
-                &mut screen,
-                hw,
-                hh, //50.0,
-            )?);
-
-            *repeller.borrow_mut() = Some(Repeller::new(hw - 20.0, screen.height() as f64 / 2.0));
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                particle_system.borrow_mut().as_mut().unwrap(),
-                repeller.borrow().as_ref().unwrap(),
-            )
-        },
-    )?;
-
-    Ok(())
+    let smoke = PixelBuffer::load(SMOKE_PATH)?;
+
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Particles::new(WIDTH, HEIGHT, smoke),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use core::color::Color;
+    use core::FixedSteps;
+
+    use super::*;
+
+    // soft white puff standing in for the smoke image
+    fn puff() -> PixelBuffer {
+        PixelBuffer::from_fn(32, 32, |x, y| {
+            let d = DVec2::new(x as f64 - 16.0, y as f64 - 16.0).length() / 16.0;
+            Color::grayscale(255.0).with_alpha((1.0 - d).max(0.0) as f32 * 64.0)
+        })
+    }
+
+    #[test]
+    fn particles_snapshot() {
+        core::random::set_seed(1);
+
+        let mut particles = FixedSteps::new(
+            FixedTimestep::default(),
+            Particles::new(WIDTH, HEIGHT, puff()),
+        );
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 60, &mut particles).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/particles.png"),
+        );
+    }
 }
@@ -1,9 +1,9 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
 
 #[derive(Debug)]
 struct Pendulum {
@@ -46,49 +46,51 @@ impl Pendulum {
         self.angular_velocity *= 0.99;
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
         // location from polar coords
         // sin / cos swapped here because x is the y-axis and y is the x-axis
         // when looking at the graph of a pendulum
         let location =
             self.origin + DVec2::new(self.r * self.angle.sin(), self.r * self.angle.cos());
 
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 175.0);
-        core::shapes::line(screen, self.origin.x, self.origin.y, location.x, location.y)?;
-        core::shapes::ellipse(screen, location.x, location.y, 16.0, 16.0)?;
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 175.0);
+        core::shapes::line(canvas, self.origin.x, self.origin.y, location.x, location.y)?;
+        core::shapes::ellipse(canvas, location.x, location.y, 16.0, 16.0)?;
 
         Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+impl Sketch for Pendulum {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
+
+        self.update(0.4);
+        self.display(canvas)
+    }
 }
 
-fn draw(screen: &mut Screen, pendulum: &mut Pendulum) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+fn main() -> Result<(), ProcessingErr> {
+    core::run_sketch(
+        WIDTH,
+        HEIGHT,
+        Pendulum::new(DVec2::new(WIDTH as f64 / 2.0, 10.0), 125.0),
+    )
+}
 
-    pendulum.update(0.4);
-    pendulum.display(screen)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
-}
+    #[test]
+    fn pendulum_snapshot() {
+        let mut pendulum = Pendulum::new(DVec2::new(WIDTH as f64 / 2.0, 10.0), 125.0);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 30, &mut pendulum).unwrap();
 
-fn main() -> Result<(), ProcessingErr> {
-    let pendulum = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
-            *pendulum.borrow_mut() = Some(Pendulum::new(
-                DVec2::new(screen.width() as f64 / 2.0, 10.0),
-                125.0,
-            ));
-            Ok(screen)
-        },
-        |screen, _| draw(screen, pendulum.borrow_mut().as_mut().unwrap()),
-    )?;
-
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/pendulum.png"),
+        );
+    }
 }
@@ -1,10 +1,10 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 // lower learning constant produces a slower,
 // more visually interesting solution
 // (default is 0.01)
@@ -18,7 +18,7 @@ struct Perceptron {
 
 impl Perceptron {
     fn new(n: usize) -> Self {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         // start with random weights
         let mut weights = Vec::with_capacity(n);
@@ -57,6 +57,7 @@ impl Perceptron {
     }
 }
 
+#[derive(Debug)]
 struct Trainer {
     inputs: [f32; 3],
     answer: isize,
@@ -76,101 +77,107 @@ fn f(x: f32) -> f32 {
     2.0 * x + 1.0
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+#[derive(Debug)]
+struct Training {
+    ptron: Perceptron,
+    training: Vec<Trainer>,
+    current: usize,
 }
 
-fn draw(
-    screen: &mut Screen,
-    _: f64,
-    ptron: &mut Perceptron,
-    training: impl AsRef<[Trainer]>,
-    current: &mut usize,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
-
-    let hw = screen.width() as f64 / 2.0;
-    let hh = screen.width() as f64 / 2.0;
-
-    core::translate(screen, hw, hh);
-
-    // draw the target line
-    screen.stroke_weight(4.0);
-    core::stroke_grayscale(screen, 127.0);
-    core::shapes::line(screen, -hw, f(-hw as f32) as f64, hw, f(hw as f32) as f64)?;
-
-    // draw the line based on the current weights
-    // formula is weights[0]*x + weights[1]*y + weights[2] = 0
-    screen.stroke_weight(1.0);
-    core::stroke_grayscale(screen, 0.0);
-    core::shapes::line(
-        screen,
-        -hw,
-        ((-ptron.weights[2] - ptron.weights[0] * -hw as f32) / ptron.weights[1]) as f64,
-        hw,
-        ((-ptron.weights[2] - ptron.weights[0] * hw as f32) / ptron.weights[1]) as f64,
-    )?;
-
-    let training = training.as_ref();
-    ptron.train(training[*current].inputs, training[*current].answer);
-    *current = (*current + 1) % training.len();
-
-    for trainer in training.iter().take(*current) {
-        core::stroke_grayscale(screen, 0.0);
-        let guess = ptron.feedforward(trainer.inputs);
-        if guess > 0 {
-            screen.fill_off();
-        } else {
-            core::fill_grayscale(screen, 0.0);
+impl Training {
+    fn new(width: u32, height: u32) -> Self {
+        let ptron = Perceptron::new(3);
+
+        let mut rng = core::random::rng();
+
+        let mut training = vec![];
+        for _ in 0..2000 {
+            let x = rng.gen_range(-(width as f32) / 2.0..width as f32 / 2.0);
+            let y = rng.gen_range(-(height as f32) / 2.0..height as f32 / 2.0);
+            let answer = if y < f(x) { -1 } else { 1 };
+            training.push(Trainer::new(x, y, answer));
         }
 
-        core::shapes::ellipse(
-            screen,
-            trainer.inputs[0] as f64,
-            trainer.inputs[1] as f64,
-            8.0,
-            8.0,
-        )?;
+        Self {
+            ptron,
+            training,
+            current: 0,
+        }
     }
-
-    Ok(())
 }
 
-fn main() -> Result<(), ProcessingErr> {
-    let mut ptron = Perceptron::new(3);
-    let training = Rc::new(RefCell::new(None));
-    let mut current = 0;
-
-    core::run(
-        || {
-            let mut rng = rand::thread_rng();
-
-            let screen = setup()?;
-
-            let mut t = vec![];
-            for _ in 0..2000 {
-                let x = rng.gen_range(-(screen.width() as f32) / 2.0..screen.width() as f32 / 2.0);
-                let y =
-                    rng.gen_range(-(screen.height() as f32) / 2.0..screen.height() as f32 / 2.0);
-                let answer = if y < f(x) { -1 } else { 1 };
-                t.push(Trainer::new(x, y, answer));
+impl Sketch for Training {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        let ptron = &mut self.ptron;
+
+        core::background_grayscale(canvas, 255.0);
+
+        let hw = canvas.width() as f64 / 2.0;
+        let hh = canvas.width() as f64 / 2.0;
+
+        core::translate(canvas, hw, hh);
+
+        // draw the target line
+        core::stroke_weight(canvas, 4.0);
+        core::stroke_grayscale(canvas, 127.0);
+        core::shapes::line(canvas, -hw, f(-hw as f32) as f64, hw, f(hw as f32) as f64)?;
+
+        // draw the line based on the current weights
+        // formula is weights[0]*x + weights[1]*y + weights[2] = 0
+        core::stroke_weight(canvas, 1.0);
+        core::stroke_grayscale(canvas, 0.0);
+        core::shapes::line(
+            canvas,
+            -hw,
+            ((-ptron.weights[2] - ptron.weights[0] * -hw as f32) / ptron.weights[1]) as f64,
+            hw,
+            ((-ptron.weights[2] - ptron.weights[0] * hw as f32) / ptron.weights[1]) as f64,
+        )?;
+
+        let training = &self.training;
+        ptron.train(training[self.current].inputs, training[self.current].answer);
+        self.current = (self.current + 1) % training.len();
+
+        for trainer in training.iter().take(self.current) {
+            core::stroke_grayscale(canvas, 0.0);
+            let guess = ptron.feedforward(trainer.inputs);
+            if guess > 0 {
+                core::fill_off(canvas);
+            } else {
+                core::fill_grayscale(canvas, 0.0);
             }
-            *training.borrow_mut() = Some(t);
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                &mut ptron,
-                training.borrow().as_ref().unwrap(),
-                &mut current,
+
+            core::shapes::ellipse(
+                canvas,
+                trainer.inputs[0] as f64,
+                trainer.inputs[1] as f64,
+                8.0,
+                8.0,
             )?;
+        }
 
-            Ok(())
-        },
-    )?;
+        Ok(())
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), ProcessingErr> {
+    core::run_sketch(WIDTH, HEIGHT, Training::new(WIDTH, HEIGHT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn training_snapshot() {
+        core::random::set_seed(1);
+
+        let mut training = Training::new(WIDTH, HEIGHT);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 200, &mut training).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/training.png"),
+        );
+    }
 }
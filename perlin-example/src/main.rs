@@ -1,32 +1,91 @@
+use core::color::Color;
+use core::fbm::Fbm;
+use core::pixels::PixelBuffer;
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+// 2D noise written straight to pixels rather than a rect per pixel
+fn noise_pixels(width: u32, height: u32, noise: &Fbm) -> PixelBuffer {
+    PixelBuffer::from_fn(width, height, |x, y| {
+        let v = noise.get2d([x as f64 * 0.01, y as f64 * 0.01]);
+        Color::grayscale(core::math::map(v, -1.0, 1.0, 0.0, 255.0) as f32)
+    })
+}
+
+#[derive(Debug)]
+struct NoiseWalk {
+    noise: Fbm,
+    pixels: PixelBuffer,
+
+    tx: f64,
+    ty: f64,
+}
+
+impl NoiseWalk {
+    fn new(width: u32, height: u32) -> Self {
+        let noise = Fbm::new().octaves(4).persistence(0.5).frequency(0.5);
+        let pixels = noise_pixels(width, height, &noise);
+
+        Self {
+            noise,
+            pixels,
+
+            tx: 0.0,
+            ty: 1000.0,
+        }
+    }
 }
 
-fn draw(screen: &mut Screen, tx: &mut f64, ty: &mut f64) -> Result<(), ProcessingErr> {
-    let x = core::math::map(core::noise(*tx, 0.5), -1.0, 1.0, 0.0, screen.width() as f64);
-    let y = core::math::map(
-        core::noise(*ty, 0.5),
-        -1.0,
-        1.0,
-        0.0,
-        screen.height() as f64,
-    );
-    core::shapes::ellipse(screen, x, y, 16.0, 16.0)?;
-
-    *tx += 0.01;
-    *ty += 0.01;
-
-    Ok(())
+impl Sketch for NoiseWalk {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        // the image type depends on the canvas, so the noise is kept as pixels
+        core::update_pixels(canvas, &self.pixels)?;
+
+        core::fill_rgb(canvas, 255.0, 0.0, 0.0);
+        let x = core::math::map(
+            self.noise.get(self.tx),
+            -1.0,
+            1.0,
+            0.0,
+            canvas.width() as f64,
+        );
+        let y = core::math::map(
+            self.noise.get(self.ty),
+            -1.0,
+            1.0,
+            0.0,
+            canvas.height() as f64,
+        );
+        core::shapes::ellipse(canvas, x, y, 16.0, 16.0)?;
+
+        self.tx += 0.01;
+        self.ty += 0.01;
+
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let mut tx = 0.0;
-    let mut ty = 1000.0;
+    core::run_sketch(WIDTH, HEIGHT, NoiseWalk::new(WIDTH, HEIGHT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_walk_snapshot() {
+        core::random::set_seed(1);
 
-    core::run(setup, |screen, _| draw(screen, &mut tx, &mut ty))?;
+        let mut walk = NoiseWalk::new(WIDTH, HEIGHT);
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 30, &mut walk).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/noise-walk.png"),
+        );
+    }
 }
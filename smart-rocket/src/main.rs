@@ -1,11 +1,15 @@
 use std::cell::RefCell;
-use std::rc::Rc;
 
+use core::shapes::{EndMode, ShapeKind};
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 use rand::Rng;
 
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
 const MUTATION_RATE: f32 = 0.01; // 1% chance to mutate
 const TOTAL_POPULATION: usize = 50;
 const LIFETIME: usize = 500;
@@ -30,7 +34,7 @@ struct Dna {
 
 impl Dna {
     fn random_gene(max_force: f64) -> DVec2 {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         core::math::vector2_random_angle() * rng.gen_range(0.0..max_force)
     }
@@ -68,7 +72,7 @@ impl Dna {
     }
 
     fn crossover(&self, partner: &Dna, method: CrossoverMethod) -> Dna {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         assert_eq!(self.genes.len(), partner.genes.len());
         let mut child = Dna::new(self.genes.len());
@@ -100,7 +104,7 @@ impl Dna {
     }
 
     fn mutate(&mut self, mutation_rate: f32) {
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         for i in 0..self.genes.len() {
             if rng.gen_range(0.0..1.0) < mutation_rate {
@@ -142,7 +146,7 @@ impl Rocket {
     }
 
     fn heading(&self) -> f64 {
-        self.velocity.x.atan2(self.velocity.y)
+        self.velocity.y.atan2(self.velocity.x)
     }
 
     fn obstacles(&mut self, obstacles: impl AsRef<[Obstacle]>) {
@@ -165,47 +169,39 @@ impl Rocket {
         self.acceleration = DVec2::default();
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        let _theta = self.heading() + std::f64::consts::FRAC_PI_2;
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        let theta = self.heading() + std::f64::consts::FRAC_PI_2;
 
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale_alpha(screen, 200.0, 100.0);
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale_alpha(canvas, 200.0, 100.0);
 
-        screen.push_matrix();
+        let mut guard = core::transform::push(canvas);
+        let canvas = &mut *guard;
 
-        core::translate(screen, self.location.x, self.location.y);
-        //core::rotate(screen, theta);
+        core::translate(canvas, self.location.x, self.location.y);
+        core::rotate(canvas, theta);
 
-        // thrusters
-        screen.rect_mode(&core::shapes::RectMode::Center.to_string());
-        core::fill_grayscale(screen, 0.0);
-        core::shapes::rect(screen, -self.r / 2.0, self.r * 2.0, self.r / 2.0, self.r)?;
-        core::shapes::rect(screen, self.r / 2.0, self.r * 2.0, self.r / 2.0, self.r)?;
+        // thrusters, the guard only restores the transform so the rect mode is put back by hand
+        let state = canvas.draw_state();
+        core::shapes::rect_mode(canvas, core::shapes::RectMode::Center);
+        core::fill_grayscale(canvas, 0.0);
+        core::shapes::rect(canvas, -self.r / 2.0, self.r * 2.0, self.r / 2.0, self.r)?;
+        core::shapes::rect(canvas, self.r / 2.0, self.r * 2.0, self.r / 2.0, self.r)?;
+        canvas.set_draw_state(state);
 
         // rocket body
-        core::fill_grayscale(screen, 175.0);
-
-        // TODO: beginShape(TRIANGLES);
-
-        // TODO: convert to world coords
-        //vertex(0.0, -self.r * 2.0);
-        //vertex(-self., self.r * 2.0);
-        //vertex(self.r, self.r * 2.0);
+        core::fill_grayscale(canvas, 175.0);
 
-        // TODO: endShape();
-
-        screen.pop_matrix();
-
-        Ok(())
+        let mut body = core::shapes::begin_shape(ShapeKind::Triangles);
+        body.vertex(0.0, -self.r * 2.0)
+            .vertex(-self.r, self.r * 2.0)
+            .vertex(self.r, self.r * 2.0);
+        body.end_shape(canvas, EndMode::Open)
     }
 
-    fn run(
-        &mut self,
-        screen: &mut Screen,
-        obstacles: impl AsRef<[Obstacle]>,
-    ) -> Result<(), ProcessingErr> {
+    fn run(&mut self, obstacles: impl AsRef<[Obstacle]>) {
         if self.stopped {
-            return Ok(());
+            return;
         }
 
         let gene = self.dna.borrow().genes[self.gene_counter];
@@ -215,10 +211,6 @@ impl Rocket {
         self.update();
 
         self.obstacles(obstacles);
-
-        self.display(screen)?;
-
-        Ok(())
     }
 }
 
@@ -240,11 +232,13 @@ impl Obstacle {
             && v.y < self.location.y + self.h
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 0.0);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 0.0);
 
-        core::shapes::rect(screen, self.location.x, self.location.y, self.w, self.h)?;
+        // location is the top left corner, the same as `contains`
+        core::shapes::rect_mode(canvas, core::shapes::RectMode::Corner);
+        core::shapes::rect(canvas, self.location.x, self.location.y, self.w, self.h)?;
 
         Ok(())
     }
@@ -316,7 +310,7 @@ impl Population {
             return;
         }
 
-        let mut rng = rand::thread_rng();
+        let mut rng = core::random::rng();
 
         for member in self.population.iter_mut() {
             // select the parents
@@ -338,29 +332,15 @@ impl Population {
         self.generations += 1;
     }
 
-    fn live(
-        &mut self,
-        screen: &mut Screen,
-        obstacles: impl AsRef<[Obstacle]>,
-    ) -> Result<(), ProcessingErr> {
+    fn live(&mut self, obstacles: impl AsRef<[Obstacle]>) {
         for member in self.population.iter_mut() {
-            member.run(screen, obstacles.as_ref())?;
+            member.run(obstacles.as_ref());
         }
-
-        Ok(())
     }
 
-    fn run(
-        &mut self,
-        screen: &mut Screen,
-        obstacles: impl AsRef<[Obstacle]>,
-    ) -> Result<(), ProcessingErr> {
-        // target
-        core::fill_grayscale(screen, 0.0);
-        core::shapes::ellipse(screen, self.target.x, self.target.y, 24.0, 24.0)?;
-
+    fn run(&mut self, obstacles: impl AsRef<[Obstacle]>) {
         if self.life_counter < self.lifetime {
-            self.live(screen, obstacles)?;
+            self.live(obstacles);
             self.life_counter += 1;
         } else {
             self.life_counter = 0;
@@ -369,93 +349,115 @@ impl Population {
             self.selection();
             self.reproduction();
         }
+    }
+
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        // target
+        core::fill_grayscale(canvas, 0.0);
+        core::shapes::ellipse(canvas, self.target.x, self.target.y, 24.0, 24.0)?;
+
+        for member in self.population.iter().filter(|member| !member.stopped) {
+            member.display(canvas)?;
+        }
 
         Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+struct Launch {
+    population: Population,
+    obstacles: Vec<Obstacle>,
 }
 
-fn draw(
-    screen: &mut Screen,
-    _: f64,
-    population: &mut Population,
-    obstacles: impl AsRef<[Obstacle]>,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+impl Launch {
+    fn new(width: u32, height: u32) -> Self {
+        let target = DVec2::new(width as f64 / 2.0, 20.0);
 
-    for obstacle in obstacles.as_ref().iter() {
-        obstacle.display(screen)?;
-    }
+        let population = Population::new(
+            DVec2::new(width as f64 / 2.0, height as f64 / 2.0),
+            MUTATION_RATE,
+            TOTAL_POPULATION,
+            target,
+            LIFETIME,
+        );
 
-    population.run(screen, obstacles)?;
+        let obstacles = vec![Obstacle::new(
+            DVec2::new(width as f64 / 2.0, height as f64 / 2.0 - 40.0),
+            100.0,
+            10.0,
+        )];
 
-    /*let mut best_phrase = 0;
-    for i in 1..population.len() {
-        if population[i].fitness > population[best_phrase].fitness {
-            best_phrase = i;
+        Self {
+            population,
+            obstacles,
         }
     }
+}
+
+impl FixedSketch for Launch {
+    fn update(&mut self, _dt: f64) {
+        self.population.run(&self.obstacles);
+    }
+
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    let fittest = &population[best_phrase];
+        for obstacle in self.obstacles.iter() {
+            obstacle.display(canvas)?;
+        }
+
+        self.population.display(canvas)?;
+
+        /*let mut best_phrase = 0;
+        for i in 1..population.len() {
+            if population[i].fitness > population[best_phrase].fitness {
+                best_phrase = i;
+            }
+        }
 
-    core::text(
-        screen,
-        format!("Best phrase: {}", fittest.phrase()),
-        0.0,
-        0.0,
-    )?;
+        let fittest = &population[best_phrase];
 
-    if (fittest.fitness - 1.0).abs() < 0.01 {
-        println!("Generated target in {} generations", fittest.generation);
-        std::process::exit(0);
-    }*/
+        core::text(
+            canvas,
+            format!("Best phrase: {}", fittest.phrase()),
+            0.0,
+            0.0,
+        )?;
 
-    Ok(())
+        if (fittest.fitness - 1.0).abs() < 0.01 {
+            println!("Generated target in {} generations", fittest.generation);
+            std::process::exit(0);
+        }*/
+
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let population = Rc::new(RefCell::new(None));
-    let obstacles = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
-
-            let target = DVec2::new(screen.width() as f64 / 2.0, 20.0);
-
-            *population.borrow_mut() = Some(Population::new(
-                DVec2::new(screen.width() as f64 / 2.0, screen.height() as f64 / 2.0),
-                MUTATION_RATE,
-                TOTAL_POPULATION,
-                target,
-                LIFETIME,
-            ));
-
-            let obs = vec![Obstacle::new(
-                DVec2::new(
-                    screen.width() as f64 / 2.0,
-                    screen.height() as f64 / 2.0 - 40.0,
-                ),
-                100.0,
-                10.0,
-            )];
-
-            *obstacles.borrow_mut() = Some(obs);
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                population.borrow_mut().as_mut().unwrap(),
-                obstacles.borrow().as_ref().unwrap(),
-            )
-        },
-    )?;
-
-    Ok(())
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Launch::new(WIDTH, HEIGHT),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
+
+    use super::*;
+
+    #[test]
+    fn launch_snapshot() {
+        core::random::set_seed(1);
+
+        let mut launch = FixedSteps::new(FixedTimestep::default(), Launch::new(WIDTH, HEIGHT));
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 60, &mut launch).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/launch.png"),
+        );
+    }
 }
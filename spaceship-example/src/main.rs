@@ -1,128 +1,110 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::mover::{EdgePolicy, Mover};
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
+use processing::Key;
 
-#[derive(Debug, Default)]
-struct Spaceship {
-    location: DVec2,
-    velocity: DVec2,
-    acceleration: DVec2,
-    mass: f64,
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
 
-    angle: f64,
+#[derive(Debug)]
+struct Spaceship {
+    body: Mover,
+    size: DVec2,
 }
 
 impl Spaceship {
-    fn new(mass: f64, x: f64, y: f64) -> Self {
+    fn new(mass: f64, width: u32, height: u32) -> Self {
+        let mut body = Mover::new(mass, width as f64 / 2.0, height as f64 / 2.0);
+        body.edge_policy = EdgePolicy::Stop;
+
         Self {
-            location: DVec2::new(x, y),
-            mass,
-            ..Default::default()
+            body,
+            size: DVec2::new(width as f64, height as f64),
         }
     }
 
-    fn heading(&self) -> DVec2 {
-        let x = self.angle.cos();
-        let y = self.angle.sin();
-        DVec2::new(x, y).normalize()
-    }
+    fn display(&self, canvas: &mut impl Canvas, alpha: f64) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 175.0);
 
-    fn stop_edges(&mut self, screen: &Screen) {
-        if self.location.x > screen.width() as f64 {
-            self.location.x = screen.width() as f64;
-        } else if self.location.x < 0.0 {
-            self.location.x = 0.0;
-        }
+        core::push_matrix(canvas);
 
-        if self.location.y > screen.height() as f64 {
-            self.location.y = screen.height() as f64;
-        } else if self.location.y < 0.0 {
-            self.location.y = 0.0;
-        }
-    }
+        let location = self.body.interpolated_location(alpha);
+        core::translate(canvas, location.x, location.y);
+        core::rotate(canvas, self.body.interpolated_angle(alpha));
 
-    fn apply_force(&mut self, force: DVec2) {
-        let force = if self.mass > 0.0 {
-            force / self.mass
-        } else {
-            force
-        };
-        self.acceleration += force;
-    }
+        // thrusters
+        let state = canvas.draw_state();
+        core::shapes::rect_mode(canvas, core::shapes::RectMode::Center);
+        core::shapes::rect(canvas, -8.0, 8.0, 8.0, 8.0)?;
+        core::shapes::rect(canvas, -8.0, -8.0, 8.0, 8.0)?;
+        canvas.set_draw_state(state);
 
-    fn update(&mut self, dt: f64) {
-        self.velocity += self.acceleration * dt;
-        self.location += self.velocity * dt;
+        // main ship
+        core::shapes::triangle(canvas, -16.0, 16.0, -16.0, -16.0, 16.0, 0.0)?;
 
-        self.acceleration = DVec2::default();
-    }
+        core::pop_matrix(canvas);
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 175.0);
+        Ok(())
+    }
+}
 
-        screen.push_matrix();
+impl FixedSketch for Spaceship {
+    fn update(&mut self, dt: f64) {
+        let input = core::input::input();
 
-        core::translate(screen, self.location.x, self.location.y);
-        core::rotate(screen, self.angle);
+        if input.key_down(Key::Left) {
+            self.body.angle += 0.1;
+        }
+        if input.key_down(Key::Right) {
+            self.body.angle -= 0.1;
+        }
 
-        // thrusters
-        screen.rect_mode(&core::shapes::RectMode::Center.to_string());
-        core::shapes::rect(screen, -8.0, 8.0, 8.0, 8.0)?;
-        core::shapes::rect(screen, -8.0, -8.0, 8.0, 8.0)?;
+        if input.key_down(Key::Z) {
+            let thrust = self.body.heading() * 500.0;
+            self.body.apply_force(thrust);
+        }
 
-        // main ship
-        core::shapes::triangle(screen, -16.0, 16.0, -16.0, -16.0, 16.0, 0.0)?;
+        self.body.update(dt);
+        self.body.check_bounds(DVec2::ZERO, self.size);
+    }
 
-        screen.pop_matrix();
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-        Ok(())
+        self.display(canvas, alpha)
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+fn main() -> Result<(), ProcessingErr> {
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Spaceship::new(10.0, WIDTH, HEIGHT),
+    )
 }
 
-fn draw(screen: &mut Screen, dt: f64, spaceship: &mut Spaceship) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
 
-    if screen.key_press(processing::Key::Left) {
-        spaceship.angle += 0.1;
-    }
-    if screen.key_press(processing::Key::Right) {
-        spaceship.angle -= 0.1;
-    }
+    use super::*;
 
-    if screen.key_press(processing::Key::Z) {
-        spaceship.apply_force(spaceship.heading() * 500.0);
-    }
+    #[test]
+    fn spaceship_snapshot() {
+        let mut spaceship = Spaceship::new(10.0, WIDTH, HEIGHT);
+        spaceship.body.angle = 0.5;
 
-    spaceship.update(dt);
-    spaceship.stop_edges(screen);
-    spaceship.display(screen)?;
+        let mut spaceship = FixedSteps::new(FixedTimestep::default(), spaceship);
 
-    Ok(())
-}
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 1, &mut spaceship).unwrap();
 
-fn main() -> Result<(), ProcessingErr> {
-    let spaceship = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
-            *spaceship.borrow_mut() = Some(Spaceship::new(
-                10.0,
-                screen.width() as f64 / 2.0,
-                screen.height() as f64 / 2.0,
-            ));
-            Ok(screen)
-        },
-        |screen, dt| draw(screen, dt, spaceship.borrow_mut().as_mut().unwrap()),
-    )?;
-
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/spaceship.png"),
+        );
+    }
 }
@@ -1,9 +1,11 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::mover::{Mover, UNIT_STEP};
+use core::timestep::FixedTimestep;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
 
 #[derive(Debug, Default)]
 struct Spring {
@@ -22,7 +24,7 @@ impl Spring {
     }
 
     // implements Hooke's Law
-    fn connect(&self, b: &mut Bob) {
+    fn connect(&self, b: &mut Mover) {
         let force = b.location - self.anchor;
         let d = force.length();
         let stretch = d - self.len;
@@ -31,126 +33,99 @@ impl Spring {
         b.apply_force(-self.k * stretch * direction);
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::fill_grayscale(screen, 100.0);
-        screen.rect_mode(&core::shapes::RectMode::Center.to_string());
-        core::shapes::rect(screen, self.anchor.x, self.anchor.y, 10.0, 10.0)?;
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::fill_grayscale(canvas, 100.0);
+
+        let state = canvas.draw_state();
+        core::shapes::rect_mode(canvas, core::shapes::RectMode::Center);
+        core::shapes::rect(canvas, self.anchor.x, self.anchor.y, 10.0, 10.0)?;
+        canvas.set_draw_state(state);
 
         Ok(())
     }
 
-    fn display_line(&self, screen: &mut Screen, b: &Bob) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 255.0);
-        core::shapes::line(
-            screen,
-            b.location.x,
-            b.location.y,
-            self.anchor.x,
-            self.anchor.y,
-        )?;
+    fn display_line(&self, canvas: &mut impl Canvas, b: DVec2) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 255.0);
+        core::shapes::line(canvas, b.x, b.y, self.anchor.x, self.anchor.y)?;
 
         Ok(())
     }
 }
 
-#[derive(Debug, Default)]
+fn display_bob(bob: &Mover, canvas: &mut impl Canvas, alpha: f64) -> Result<(), ProcessingErr> {
+    core::stroke_grayscale(canvas, 0.0);
+    core::fill_grayscale(canvas, 0.0);
+
+    let location = bob.interpolated_location(alpha);
+    core::shapes::ellipse(
+        canvas,
+        location.x,
+        location.y,
+        bob.mass * 16.0,
+        bob.mass * 16.0,
+    )
+}
+
+#[derive(Debug)]
 struct Bob {
-    location: DVec2,
-    velocity: DVec2,
-    acceleration: DVec2,
-    mass: f64,
+    bob: Mover,
+    spring: Spring,
 }
 
 impl Bob {
-    fn new(mass: f64) -> Self {
+    fn new(width: u32, height: u32) -> Self {
         Self {
-            location: DVec2::default(),
-            mass,
-            ..Default::default()
+            bob: Mover::new(10.0, 0.0, 0.0),
+            spring: Spring::new(width as f64 / 2.0, 10.0, height as f64 / 2.0),
         }
     }
+}
 
-    fn apply_force(&mut self, force: DVec2) {
-        let force = if self.mass > 0.0 {
-            force / self.mass
-        } else {
-            force
-        };
-        self.acceleration += force;
-    }
-
+impl FixedSketch for Bob {
     fn update(&mut self, _dt: f64) {
-        self.velocity += self.acceleration;
-        self.location += self.velocity;
+        let gravity = DVec2::new(0.0, 1.0);
+        self.bob.apply_force(gravity);
+
+        self.spring.connect(&mut self.bob);
 
-        self.acceleration = DVec2::default();
+        self.bob.update(UNIT_STEP);
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 0.0);
-
-        core::shapes::ellipse(
-            screen,
-            self.location.x,
-            self.location.y,
-            self.mass * 16.0,
-            self.mass * 16.0,
-        )
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, alpha: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
+
+        display_bob(&self.bob, canvas, alpha)?;
+        self.spring.display(canvas)?;
+        self.spring
+            .display_line(canvas, self.bob.interpolated_location(alpha))?;
+
+        Ok(())
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
+fn main() -> Result<(), ProcessingErr> {
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Bob::new(WIDTH, HEIGHT),
+    )
 }
 
-fn draw(
-    screen: &mut Screen,
-    dt: f64,
-    bob: &mut Bob,
-    spring: &mut Spring,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
-
-    let gravity = DVec2::new(0.0, 1.0);
-    bob.apply_force(gravity);
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
 
-    spring.connect(bob);
+    use super::*;
 
-    bob.update(dt);
-    bob.display(screen)?;
-    spring.display(screen)?;
-    spring.display_line(screen, bob)?;
+    #[test]
+    fn bob_snapshot() {
+        let mut bob = FixedSteps::new(FixedTimestep::default(), Bob::new(WIDTH, HEIGHT));
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 60, &mut bob).unwrap();
 
-    Ok(())
-}
-
-fn main() -> Result<(), ProcessingErr> {
-    let bob = Rc::new(RefCell::new(None));
-    let spring = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
-
-            *spring.borrow_mut() = Some(Spring::new(
-                screen.width() as f64 / 2.0,
-                10.0,
-                screen.height() as f64 / 2.0,
-            ));
-            *bob.borrow_mut() = Some(Bob::new(10.0));
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                bob.borrow_mut().as_mut().unwrap(),
-                spring.borrow_mut().as_mut().unwrap(),
-            )
-        },
-    )?;
-
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/bob.png"),
+        );
+    }
 }
@@ -0,0 +1,134 @@
+use glam::DVec2;
+
+/// Position and velocity of something being steered
+///
+/// Behaviors return a steering force limited to `max_force`
+/// for the owner to apply as it sees fit
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Agent {
+    pub position: DVec2,
+    pub velocity: DVec2,
+
+    pub max_speed: f64,
+    pub max_force: f64,
+}
+
+impl Agent {
+    pub fn new(position: DVec2, velocity: DVec2, max_speed: f64, max_force: f64) -> Self {
+        Self {
+            position,
+            velocity,
+            max_speed,
+            max_force,
+        }
+    }
+
+    /// Direction of travel, zero when stopped
+    pub fn heading(&self) -> DVec2 {
+        self.velocity.normalize_or_zero()
+    }
+
+    /// Where the agent will be after `time` at its current velocity
+    pub fn predict(&self, time: f64) -> DVec2 {
+        self.position + self.velocity * time
+    }
+
+    /// Force turning the current velocity into the desired velocity
+    pub fn steer(&self, desired: DVec2) -> DVec2 {
+        (desired - self.velocity).clamp_length_max(self.max_force)
+    }
+
+    /// Heads for the target at full speed
+    pub fn seek(&self, target: DVec2) -> DVec2 {
+        self.steer((target - self.position).normalize_or_zero() * self.max_speed)
+    }
+
+    /// Heads away from the target at full speed
+    pub fn flee(&self, target: DVec2) -> DVec2 {
+        self.steer((self.position - target).normalize_or_zero() * self.max_speed)
+    }
+
+    /// Seeks the target, slowing down inside `slowing_radius` to stop on it
+    pub fn arrive(&self, target: DVec2, slowing_radius: f64) -> DVec2 {
+        let offset = target - self.position;
+
+        let distance = offset.length();
+        let speed = if distance < slowing_radius {
+            self.max_speed * distance / slowing_radius
+        } else {
+            self.max_speed
+        };
+
+        self.steer(offset.normalize_or_zero() * speed)
+    }
+
+    /// Seeks where the quarry is going to be
+    pub fn pursue(&self, quarry: &Agent) -> DVec2 {
+        self.seek(quarry.predict(self.lookahead(quarry)))
+    }
+
+    /// Flees from where the pursuer is going to be
+    pub fn evade(&self, pursuer: &Agent) -> DVec2 {
+        self.flee(pursuer.predict(self.lookahead(pursuer)))
+    }
+
+    // how far ahead to predict the other agent,
+    // further the longer it would take to get to it
+    fn lookahead(&self, other: &Agent) -> f64 {
+        if self.max_speed > 0.0 {
+            self.position.distance(other.position) / self.max_speed
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stopped(max_speed: f64) -> Agent {
+        Agent::new(DVec2::ZERO, DVec2::ZERO, max_speed, 100.0)
+    }
+
+    #[test]
+    fn arrive_is_full_speed_outside_the_radius() {
+        let agent = stopped(4.0);
+
+        assert_eq!(
+            agent.arrive(DVec2::new(20.0, 0.0), 10.0),
+            DVec2::new(4.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn arrive_slows_inside_the_radius() {
+        let agent = stopped(4.0);
+
+        assert_eq!(
+            agent.arrive(DVec2::new(5.0, 0.0), 10.0),
+            DVec2::new(2.0, 0.0)
+        );
+        assert_eq!(agent.arrive(DVec2::ZERO, 10.0), DVec2::ZERO);
+    }
+
+    #[test]
+    fn steer_is_limited_to_max_force() {
+        let agent = Agent::new(DVec2::ZERO, DVec2::ZERO, 4.0, 0.5);
+
+        assert!((agent.seek(DVec2::new(0.0, 10.0)).length() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pursue_leads_the_quarry() {
+        let agent = stopped(4.0);
+        let quarry = Agent::new(DVec2::new(40.0, 0.0), DVec2::new(0.0, 4.0), 4.0, 100.0);
+
+        // heads for where the quarry will be, not where it is
+        let force = agent.pursue(&quarry);
+        assert!(force.x > 0.0 && force.y > 0.0, "{:?}", force);
+
+        let force = agent.evade(&quarry);
+        assert!(force.x < 0.0 && force.y < 0.0, "{:?}", force);
+    }
+}
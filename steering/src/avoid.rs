@@ -0,0 +1,95 @@
+use glam::DVec2;
+
+use crate::Agent;
+
+/// Circle agents steer around
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Obstacle {
+    pub center: DVec2,
+    pub radius: f64,
+}
+
+impl Obstacle {
+    pub fn new(center: DVec2, radius: f64) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl Agent {
+    /// Steers sideways around the nearest obstacle in the way within `look_ahead`
+    ///
+    /// `radius` is the agent's own radius, obstacles closer than that to its path are in the way
+    pub fn avoid<'a>(
+        &self,
+        obstacles: impl IntoIterator<Item = &'a Obstacle>,
+        radius: f64,
+        look_ahead: f64,
+    ) -> DVec2 {
+        let heading = self.heading();
+        if heading == DVec2::ZERO {
+            return DVec2::ZERO;
+        }
+
+        let side = heading.perp();
+
+        // obstacle distances along and across the direction of travel
+        let mut nearest: Option<(f64, f64)> = None;
+        for obstacle in obstacles {
+            let offset = obstacle.center - self.position;
+            let reach = obstacle.radius + radius;
+
+            let along = offset.dot(heading);
+            if along < 0.0 || along > look_ahead + reach {
+                continue;
+            }
+
+            let across = offset.dot(side);
+            if across.abs() >= reach {
+                continue;
+            }
+
+            let closer = match nearest {
+                Some((nearest, _)) => along < nearest,
+                None => true,
+            };
+            if closer {
+                nearest = Some((along, across));
+            }
+        }
+
+        match nearest {
+            // turn to whichever side the obstacle isn't on
+            Some((_, across)) if across > 0.0 => -side * self.max_force,
+            Some(_) => side * self.max_force,
+            None => DVec2::ZERO,
+        }
+    }
+
+    /// Steers back inside `min..max` once the agent gets within `margin` of an edge
+    pub fn contain(&self, min: DVec2, max: DVec2, margin: f64) -> DVec2 {
+        let mut desired = self.velocity;
+        let mut outside = false;
+
+        if self.position.x < min.x + margin {
+            desired.x = self.max_speed;
+            outside = true;
+        } else if self.position.x > max.x - margin {
+            desired.x = -self.max_speed;
+            outside = true;
+        }
+
+        if self.position.y < min.y + margin {
+            desired.y = self.max_speed;
+            outside = true;
+        } else if self.position.y > max.y - margin {
+            desired.y = -self.max_speed;
+            outside = true;
+        }
+
+        if !outside {
+            return DVec2::ZERO;
+        }
+
+        self.steer(desired.normalize_or_zero() * self.max_speed)
+    }
+}
@@ -0,0 +1,64 @@
+use glam::DVec2;
+
+/// Sum of the forces scaled by their weights, limited to `max_force`
+pub fn weighted(max_force: f64, forces: impl IntoIterator<Item = (DVec2, f64)>) -> DVec2 {
+    forces
+        .into_iter()
+        .fold(DVec2::ZERO, |sum, (force, weight)| sum + force * weight)
+        .clamp_length_max(max_force)
+}
+
+/// Forces in order of importance, each getting what's left of `max_force`
+///
+/// Later behaviors only have a say once the earlier ones are satisfied,
+/// so avoiding an obstacle can't be outvoted by wanting to flock
+pub fn prioritized(max_force: f64, forces: impl IntoIterator<Item = DVec2>) -> DVec2 {
+    let mut total = DVec2::ZERO;
+    let mut remaining = max_force;
+
+    for force in forces {
+        if remaining <= 0.0 {
+            break;
+        }
+
+        let magnitude = force.length();
+        if magnitude <= remaining {
+            total += force;
+            remaining -= magnitude;
+        } else {
+            total += force * (remaining / magnitude);
+            remaining = 0.0;
+        }
+    }
+
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted_scales_and_limits() {
+        let force = weighted(
+            10.0,
+            [(DVec2::new(1.0, 0.0), 2.0), (DVec2::new(0.0, 1.0), 0.5)],
+        );
+        assert_eq!(force, DVec2::new(2.0, 0.5));
+
+        let force = weighted(1.0, [(DVec2::new(3.0, 0.0), 1.0)]);
+        assert_eq!(force, DVec2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn prioritized_gives_the_first_force_its_budget() {
+        let force = prioritized(1.0, [DVec2::new(2.0, 0.0), DVec2::new(0.0, 1.0)]);
+        assert_eq!(force, DVec2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn prioritized_passes_on_what_is_left() {
+        let force = prioritized(1.0, [DVec2::new(0.75, 0.0), DVec2::new(0.0, 1.0)]);
+        assert_eq!(force, DVec2::new(0.75, 0.25));
+    }
+}
@@ -0,0 +1,84 @@
+use glam::DVec2;
+
+use crate::Agent;
+
+/// How strongly and how far away one flocking behavior acts
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Rule {
+    pub weight: f64,
+    pub radius: f64,
+}
+
+impl Rule {
+    pub fn new(weight: f64, radius: f64) -> Self {
+        Self { weight, radius }
+    }
+}
+
+/// Separation, alignment and cohesion blended by weight
+///
+/// Only neighbors inside the field of view are counted
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Flocking {
+    pub separation: Rule,
+    pub alignment: Rule,
+    pub cohesion: Rule,
+
+    /// Full width of the vision cone in radians, TAU sees all the way around
+    pub field_of_view: f64,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Self {
+            separation: Rule::new(1.5, 25.0),
+            alignment: Rule::new(1.0, 50.0),
+            cohesion: Rule::new(1.0, 50.0),
+            field_of_view: std::f64::consts::TAU,
+        }
+    }
+}
+
+impl Flocking {
+    /// Largest radius any of the rules look at, for neighbor queries
+    pub fn radius(&self) -> f64 {
+        self.separation
+            .radius
+            .max(self.alignment.radius)
+            .max(self.cohesion.radius)
+    }
+
+    /// Weighted sum of the behaviors
+    ///
+    /// Not limited to the agent's max force so weights above 1 can outvote other behaviors
+    pub fn steer(&self, agent: &Agent, neighbors: &[Agent]) -> DVec2 {
+        let visible = || {
+            neighbors
+                .iter()
+                .filter(|other| agent.sees(other.position, self.field_of_view))
+        };
+
+        agent.separate(visible(), self.separation.radius) * self.separation.weight
+            + agent.align(visible(), self.alignment.radius) * self.alignment.weight
+            + agent.cohesion(visible(), self.cohesion.radius) * self.cohesion.weight
+    }
+}
+
+impl Agent {
+    /// Whether the point is inside the vision cone `field_of_view` radians wide
+    ///
+    /// Stopped agents see all the way around
+    pub fn sees(&self, point: DVec2, field_of_view: f64) -> bool {
+        let heading = self.heading();
+        if field_of_view >= std::f64::consts::TAU || heading == DVec2::ZERO {
+            return true;
+        }
+
+        let direction = (point - self.position).normalize_or_zero();
+        if direction == DVec2::ZERO {
+            return true;
+        }
+
+        heading.dot(direction) >= (field_of_view * 0.5).cos()
+    }
+}
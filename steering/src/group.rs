@@ -0,0 +1,192 @@
+use glam::DVec2;
+
+use crate::Agent;
+
+// neighbors may include the agent itself,
+// anything at exactly the agent's position is skipped
+
+impl Agent {
+    /// Steers away from neighbors closer than `distance`, harder the closer they are
+    pub fn separate<'a>(
+        &self,
+        neighbors: impl IntoIterator<Item = &'a Agent>,
+        distance: f64,
+    ) -> DVec2 {
+        let mut sum = DVec2::ZERO;
+        let mut count = 0;
+
+        for other in neighbors {
+            let d = self.position.distance(other.position);
+            if d > 0.0 && d < distance {
+                // weight how fast we flee by the distance
+                sum += (self.position - other.position).normalize_or_zero() / d;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return DVec2::ZERO;
+        }
+
+        self.steer(sum.normalize_or_zero() * self.max_speed)
+    }
+
+    /// Steers towards the average heading of neighbors within `distance`
+    pub fn align<'a>(
+        &self,
+        neighbors: impl IntoIterator<Item = &'a Agent>,
+        distance: f64,
+    ) -> DVec2 {
+        let mut sum = DVec2::ZERO;
+        let mut count = 0;
+
+        for other in neighbors {
+            let d = self.position.distance(other.position);
+            if d > 0.0 && d < distance {
+                sum += other.velocity;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return DVec2::ZERO;
+        }
+
+        self.steer(sum.normalize_or_zero() * self.max_speed)
+    }
+
+    /// Steers towards the average position of neighbors within `distance`
+    pub fn cohesion<'a>(
+        &self,
+        neighbors: impl IntoIterator<Item = &'a Agent>,
+        distance: f64,
+    ) -> DVec2 {
+        let mut sum = DVec2::ZERO;
+        let mut count = 0;
+
+        for other in neighbors {
+            let d = self.position.distance(other.position);
+            if d > 0.0 && d < distance {
+                sum += other.position;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return DVec2::ZERO;
+        }
+
+        self.seek(sum / count as f64)
+    }
+
+    /// Brakes while a neighbor in front of the agent is within `radius` of it
+    /// or of the point `ahead` of it, so agents line up instead of crowding through narrow spaces
+    ///
+    /// Neighbors behind are ignored so the front of a line doesn't wait for the rest of it
+    pub fn queue<'a>(
+        &self,
+        neighbors: impl IntoIterator<Item = &'a Agent>,
+        ahead: f64,
+        radius: f64,
+    ) -> DVec2 {
+        let heading = self.heading();
+        let ahead = self.position + heading * ahead;
+
+        let blocked = neighbors.into_iter().any(|other| {
+            (other.position - self.position).dot(heading) > 0.0
+                && (ahead.distance(other.position) < radius
+                    || self.position.distance(other.position) < radius)
+        });
+
+        if blocked {
+            // the force that brings the agent to a stop
+            self.steer(DVec2::ZERO)
+        } else {
+            DVec2::ZERO
+        }
+    }
+
+    /// Arrives at the point `distance` behind the leader,
+    /// getting out of the way if it ends up in front of the leader
+    pub fn follow(&self, leader: &Agent, distance: f64, slowing_radius: f64) -> DVec2 {
+        let heading = leader.heading();
+        let behind = leader.position - heading * distance;
+        let ahead = leader.position + heading * distance;
+
+        let mut force = self.arrive(behind, slowing_radius);
+
+        // in the leader's way, only in front of it so it doesn't fight the arrive
+        let in_front = (self.position - leader.position).dot(heading) > 0.0;
+        if in_front
+            && (self.position.distance(ahead) < distance
+                || self.position.distance(leader.position) < distance)
+        {
+            force += self.evade(leader);
+        }
+
+        force.clamp_length_max(self.max_force)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(x: f64, y: f64, vx: f64, vy: f64) -> Agent {
+        Agent::new(DVec2::new(x, y), DVec2::new(vx, vy), 4.0, 0.5)
+    }
+
+    #[test]
+    fn queue_brakes_for_neighbors_ahead() {
+        let front = agent(5.0, 0.0, 1.0, 0.0);
+        let back = agent(0.0, 0.0, 1.0, 0.0);
+        let line = [front, back];
+
+        let force = back.queue(&line, 5.0, 6.0);
+        assert!(force.x < 0.0, "{:?}", force);
+    }
+
+    #[test]
+    fn queue_ignores_neighbors_behind() {
+        let front = agent(5.0, 0.0, 1.0, 0.0);
+        let back = agent(0.0, 0.0, 1.0, 0.0);
+        let line = [front, back];
+
+        assert_eq!(front.queue(&line, 5.0, 6.0), DVec2::ZERO);
+    }
+
+    #[test]
+    fn queue_skips_self() {
+        let only = agent(0.0, 0.0, 1.0, 0.0);
+
+        assert_eq!(only.queue([&only], 5.0, 6.0), DVec2::ZERO);
+    }
+
+    #[test]
+    fn follow_behind_only_arrives() {
+        let leader = agent(0.0, 0.0, 2.0, 0.0);
+
+        // closer than `distance` but directly behind the leader
+        let follower = agent(-10.0, 0.0, 2.0, 0.0);
+
+        let behind = leader.position - leader.heading() * 20.0;
+        let expected = follower
+            .arrive(behind, 50.0)
+            .clamp_length_max(follower.max_force);
+
+        assert_eq!(follower.follow(&leader, 20.0, 50.0), expected);
+    }
+
+    #[test]
+    fn follow_in_front_gets_out_of_the_way() {
+        let leader = agent(0.0, 0.0, 2.0, 0.0);
+        let follower = agent(10.0, 1.0, 0.0, 0.0);
+
+        let behind = leader.position - leader.heading() * 20.0;
+        let arrive = follower
+            .arrive(behind, 50.0)
+            .clamp_length_max(follower.max_force);
+
+        assert_ne!(follower.follow(&leader, 20.0, 50.0), arrive);
+    }
+}
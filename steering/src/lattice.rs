@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use glam::DVec2;
+
+/// Bins points into square cells so radius queries only look at nearby points
+///
+/// Rebuilt from scratch every frame, queries return indices into
+/// whatever the points were rebuilt from
+#[derive(Debug, Clone)]
+pub struct BinLattice {
+    cell_size: f64,
+    bins: HashMap<(i64, i64), Vec<(usize, DVec2)>>,
+}
+
+impl BinLattice {
+    /// Queries are fastest with `cell_size` around the most common query radius
+    ///
+    /// Panics if `cell_size` isn't positive
+    pub fn new(cell_size: f64) -> Self {
+        assert!(cell_size > 0.0, "lattice cell size must be positive");
+
+        Self {
+            cell_size,
+            bins: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Replaces everything in the lattice with the points
+    pub fn rebuild(&mut self, points: impl IntoIterator<Item = DVec2>) {
+        // keep the allocations of bins that are still in use
+        self.bins.retain(|_, bin| !bin.is_empty());
+        for bin in self.bins.values_mut() {
+            bin.clear();
+        }
+
+        for (idx, point) in points.into_iter().enumerate() {
+            let cell = self.cell(point);
+            self.bins.entry(cell).or_default().push((idx, point));
+        }
+    }
+
+    /// Indices of the points within `radius` of `center`, in no particular order
+    pub fn query(&self, center: DVec2, radius: f64) -> impl Iterator<Item = usize> + '_ {
+        let min = self.cell(center - DVec2::splat(radius));
+        let max = self.cell(center + DVec2::splat(radius));
+        let radius_squared = radius * radius;
+
+        (min.0..=max.0)
+            .flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+            .filter_map(move |cell| self.bins.get(&cell))
+            .flatten()
+            .filter(move |(_, point)| point.distance_squared(center) <= radius_squared)
+            .map(|(idx, _)| *idx)
+    }
+
+    fn cell(&self, point: DVec2) -> (i64, i64) {
+        let cell = (point / self.cell_size).floor();
+        (cell.x as i64, cell.y as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    fn brute_force(points: &[DVec2], center: DVec2, radius: f64) -> Vec<usize> {
+        points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| point.distance_squared(center) <= radius * radius)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    fn query(lattice: &BinLattice, center: DVec2, radius: f64) -> Vec<usize> {
+        let mut found: Vec<_> = lattice.query(center, radius).collect();
+        found.sort_unstable();
+        found
+    }
+
+    #[test]
+    fn query_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        // around the origin so some cells have negative coordinates
+        let mut points: Vec<_> = (0..500)
+            .map(|_| DVec2::new(rng.gen_range(-200.0..200.0), rng.gen_range(-200.0..200.0)))
+            .collect();
+
+        // exactly on cell boundaries
+        for x in -4..=4 {
+            for y in -4..=4 {
+                points.push(DVec2::new(x as f64, y as f64) * 25.0);
+            }
+        }
+
+        let mut lattice = BinLattice::new(25.0);
+        lattice.rebuild(points.iter().copied());
+
+        // smaller than, equal to and larger than a cell
+        for radius in [10.0, 25.0, 60.0, 130.0] {
+            for _ in 0..50 {
+                let center = DVec2::new(rng.gen_range(-250.0..250.0), rng.gen_range(-250.0..250.0));
+                assert_eq!(
+                    query(&lattice, center, radius),
+                    brute_force(&points, center, radius),
+                    "center {:?} radius {}",
+                    center,
+                    radius
+                );
+            }
+
+            // centered on boundaries, reaching exactly to other boundary points
+            for center in [
+                DVec2::ZERO,
+                DVec2::new(-25.0, 50.0),
+                DVec2::new(-50.0, -75.0),
+            ] {
+                assert_eq!(
+                    query(&lattice, center, radius),
+                    brute_force(&points, center, radius)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rebuild_replaces_points() {
+        let mut lattice = BinLattice::new(10.0);
+        lattice.rebuild([DVec2::new(-5.0, -5.0), DVec2::new(5.0, 5.0)]);
+        lattice.rebuild([DVec2::new(100.0, 100.0)]);
+
+        assert_eq!(query(&lattice, DVec2::ZERO, 20.0), Vec::<usize>::new());
+        assert_eq!(query(&lattice, DVec2::new(100.0, 100.0), 1.0), vec![0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_cell_size_panics() {
+        BinLattice::new(0.0);
+    }
+}
@@ -0,0 +1,21 @@
+// https://www.red3d.com/cwr/steer/
+
+// kept free of processing and bevy so both the sketches and the ecosystem can use it
+
+mod agent;
+mod avoid;
+pub mod blend;
+mod flock;
+mod group;
+mod lattice;
+mod path;
+mod wander;
+
+pub use glam::DVec2;
+
+pub use crate::agent::Agent;
+pub use crate::avoid::Obstacle;
+pub use crate::flock::{Flocking, Rule};
+pub use crate::lattice::BinLattice;
+pub use crate::path::{Path, PathError, PathFollow, PathPoint};
+pub use crate::wander::Wander;
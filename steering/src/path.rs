@@ -0,0 +1,453 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+
+use glam::DVec2;
+
+use crate::Agent;
+
+/// Path loading errors
+#[derive(Debug)]
+pub enum PathError {
+    Io(io::Error),
+
+    /// Line that isn't a point, a point with a radius, or `closed`
+    Parse {
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read path: {}", err),
+            Self::Parse { line, text } => write!(f, "invalid path line {}: '{}'", line, text),
+        }
+    }
+}
+
+impl Error for PathError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for PathError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Closest point on a path to some other point
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct PathPoint {
+    pub segment: usize,
+
+    /// How far along the segment the point is, in [0..1]
+    pub t: f64,
+
+    pub point: DVec2,
+    pub direction: DVec2,
+    pub distance: f64,
+    pub radius: f64,
+}
+
+/// Everything that went into one path following decision, for debugging
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct PathFollow {
+    /// Where the agent is going to be
+    pub predicted: DVec2,
+
+    /// Closest point on the path to the predicted point
+    pub normal: PathPoint,
+
+    /// Point further along the path that the agent seeks
+    pub target: DVec2,
+
+    /// Zero while the predicted point is inside the path
+    pub force: DVec2,
+}
+
+/// Polyline with a radius per segment that agents stay within
+///
+/// Segment `i` runs from point `i` to point `i + 1`,
+/// closed paths have an extra segment from the last point back to the first
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Path {
+    points: Vec<DVec2>,
+
+    // radius of the segment starting at each point
+    radii: Vec<f64>,
+
+    closed: bool,
+}
+
+impl Path {
+    pub fn new(closed: bool) -> Self {
+        Self {
+            points: vec![],
+            radii: vec![],
+            closed,
+        }
+    }
+
+    /// Parses one point per line as `x y` or `x y radius`
+    ///
+    /// Points without a radius use `radius`, a line with just `closed` closes the path.
+    /// Blank lines and lines starting with `#` are skipped
+    pub fn parse(source: &str, radius: f64) -> Result<Self, PathError> {
+        let mut path = Self::new(false);
+
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line == "closed" {
+                path.closed = true;
+                continue;
+            }
+
+            let values = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<f64>, _>>();
+
+            match values.as_deref() {
+                Ok([x, y]) => path.add_point(DVec2::new(*x, *y), radius),
+                Ok([x, y, r]) => path.add_point(DVec2::new(*x, *y), *r),
+                _ => {
+                    return Err(PathError::Parse {
+                        line: i + 1,
+                        text: line.to_owned(),
+                    })
+                }
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Loads a point list file, see [`Path::parse`]
+    pub fn load(path: impl AsRef<std::path::Path>, radius: f64) -> Result<Self, PathError> {
+        let source = fs::read_to_string(path)?;
+        Self::parse(&source, radius)
+    }
+
+    /// Catmull-Rom spline through the path's points with `detail` segments between each pair
+    ///
+    /// Each new segment keeps the radius of the segment it was made from
+    pub fn smooth(&self, detail: usize) -> Self {
+        let count = self.segment_count();
+        if count == 0 || detail == 0 {
+            return self.clone();
+        }
+
+        let n = self.points.len();
+
+        // open paths repeat their end points so the curve reaches them
+        let control = |i: isize| {
+            if self.closed {
+                self.points[i.rem_euclid(n as isize) as usize]
+            } else {
+                self.points[i.clamp(0, n as isize - 1) as usize]
+            }
+        };
+
+        let mut path = Self::new(self.closed);
+        for segment in 0..count {
+            let i = segment as isize;
+            let (p0, p1, p2, p3) = (control(i - 1), control(i), control(i + 1), control(i + 2));
+
+            for step in 0..detail {
+                let t = step as f64 / detail as f64;
+                path.add_point(curve_point(p0, p1, p2, p3, t), self.radii[segment]);
+            }
+        }
+
+        if !self.closed {
+            path.add_point(self.points[n - 1], self.radii[n - 1]);
+        }
+
+        path
+    }
+
+    pub fn add_point(&mut self, point: DVec2, radius: f64) {
+        self.points.push(point);
+        self.radii.push(radius);
+    }
+
+    pub fn points(&self) -> &[DVec2] {
+        &self.points
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+    }
+
+    pub fn segment_count(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 0,
+            n if self.closed => n,
+            n => n - 1,
+        }
+    }
+
+    /// Start, end and radius of a segment
+    pub fn segment(&self, segment: usize) -> (DVec2, DVec2, f64) {
+        let a = self.points[segment];
+        let b = self.points[(segment + 1) % self.points.len()];
+
+        (a, b, self.radii[segment])
+    }
+
+    pub fn set_radius(&mut self, segment: usize, radius: f64) {
+        self.radii[segment] = radius;
+    }
+
+    pub fn length(&self) -> f64 {
+        (0..self.segment_count())
+            .map(|segment| {
+                let (a, b, _) = self.segment(segment);
+                a.distance(b)
+            })
+            .sum()
+    }
+
+    /// Closest point on any segment, `None` if the path has no segments
+    pub fn closest(&self, point: DVec2) -> Option<PathPoint> {
+        let mut closest: Option<PathPoint> = None;
+
+        for segment in 0..self.segment_count() {
+            let (a, b, radius) = self.segment(segment);
+            let ab = b - a;
+
+            // how far along the segment the point projects, clamped to its ends
+            let length_squared = ab.length_squared();
+            let t = if length_squared > 0.0 {
+                ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+
+            let projected = a + ab * t;
+            let distance = point.distance(projected);
+
+            let closer = match closest {
+                Some(closest) => distance < closest.distance,
+                None => true,
+            };
+            if closer {
+                closest = Some(PathPoint {
+                    segment,
+                    t,
+                    point: projected,
+                    direction: ab.normalize_or_zero(),
+                    distance,
+                    radius,
+                });
+            }
+        }
+
+        closest
+    }
+
+    /// Point `distance` further along the path from `from`
+    ///
+    /// Open paths stop at their last point, closed paths wrap around
+    pub fn advance(&self, from: &PathPoint, distance: f64) -> DVec2 {
+        let count = self.segment_count();
+
+        let mut remaining = distance;
+        if self.closed {
+            let length = self.length();
+            if length <= 0.0 {
+                return from.point;
+            }
+            remaining %= length;
+        }
+
+        let mut segment = from.segment;
+        let mut position = from.point;
+        loop {
+            let (a, b, _) = self.segment(segment);
+
+            let left = position.distance(b);
+            if remaining <= left {
+                return position + (b - a).normalize_or_zero() * remaining;
+            }
+            remaining -= left;
+
+            segment += 1;
+            if segment == count {
+                if !self.closed {
+                    return b;
+                }
+                segment = 0;
+            }
+            position = b;
+        }
+    }
+}
+
+impl Agent {
+    /// Seeks a point `ahead` along the path once the point `predict` ahead of the agent
+    /// leaves the path, `None` if the path has no segments
+    pub fn follow_path(&self, path: &Path, predict: f64, ahead: f64) -> Option<PathFollow> {
+        let predicted = self.position + self.heading() * predict;
+        let normal = path.closest(predicted)?;
+        let target = path.advance(&normal, ahead);
+
+        let force = if normal.distance > normal.radius {
+            self.seek(target)
+        } else {
+            DVec2::ZERO
+        };
+
+        Some(PathFollow {
+            predicted,
+            normal,
+            target,
+            force,
+        })
+    }
+}
+
+// point on a Catmull-Rom spline between p1 and p2 with t in [0..1]
+fn curve_point(p0: DVec2, p1: DVec2, p2: DVec2, p3: DVec2, t: f64) -> DVec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn path(points: &[(f64, f64)], closed: bool) -> Path {
+        let mut path = Path::new(closed);
+        for (x, y) in points {
+            path.add_point(DVec2::new(*x, *y), 10.0);
+        }
+        path
+    }
+
+    fn assert_near(a: DVec2, b: DVec2) {
+        assert!(a.distance(b) < EPSILON, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn closest_on_vertical_segment() {
+        let path = path(&[(0.0, 0.0), (0.0, 100.0)], false);
+
+        let closest = path.closest(DVec2::new(20.0, 40.0)).unwrap();
+        assert_near(closest.point, DVec2::new(0.0, 40.0));
+        assert_near(closest.direction, DVec2::new(0.0, 1.0));
+        assert!((closest.t - 0.4).abs() < EPSILON);
+        assert!((closest.distance - 20.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn closest_on_backtracking_segment() {
+        let path = path(&[(100.0, 0.0), (0.0, 0.0)], false);
+
+        let closest = path.closest(DVec2::new(25.0, -5.0)).unwrap();
+        assert_near(closest.point, DVec2::new(25.0, 0.0));
+        assert_near(closest.direction, DVec2::new(-1.0, 0.0));
+        assert!((closest.t - 0.75).abs() < EPSILON);
+
+        // past the end clamps to it rather than projecting onto the line
+        let closest = path.closest(DVec2::new(-50.0, 0.0)).unwrap();
+        assert_near(closest.point, DVec2::ZERO);
+        assert_eq!(closest.t, 1.0);
+    }
+
+    #[test]
+    fn closest_with_zero_length_segment() {
+        let path = path(&[(0.0, 0.0), (0.0, 0.0), (100.0, 0.0)], false);
+
+        let closest = path.closest(DVec2::new(50.0, 10.0)).unwrap();
+        assert_eq!(closest.segment, 1);
+        assert_near(closest.point, DVec2::new(50.0, 0.0));
+
+        // the degenerate segment is just its point
+        let single = self::path(&[(5.0, 5.0), (5.0, 5.0)], false);
+        let closest = single.closest(DVec2::new(10.0, 5.0)).unwrap();
+        assert_near(closest.point, DVec2::new(5.0, 5.0));
+        assert_eq!(closest.direction, DVec2::ZERO);
+        assert!(closest.distance.is_finite());
+    }
+
+    #[test]
+    fn closest_without_segments() {
+        assert!(path(&[], false).closest(DVec2::ZERO).is_none());
+        assert!(path(&[(1.0, 1.0)], true).closest(DVec2::ZERO).is_none());
+    }
+
+    #[test]
+    fn advance_wraps_across_closing_segment() {
+        let square = path(
+            &[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)],
+            true,
+        );
+
+        // on the closing segment from (0, 100) back to the start
+        let from = square.closest(DVec2::new(-5.0, 50.0)).unwrap();
+        assert_eq!(from.segment, 3);
+
+        assert_near(square.advance(&from, 70.0), DVec2::new(20.0, 0.0));
+
+        // whole laps come back around
+        assert_near(square.advance(&from, 400.0 + 70.0), DVec2::new(20.0, 0.0));
+    }
+
+    #[test]
+    fn advance_clamps_at_open_end() {
+        let line = path(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0)], false);
+
+        let from = line.closest(DVec2::new(50.0, 0.0)).unwrap();
+        assert_near(line.advance(&from, 100.0), DVec2::new(100.0, 50.0));
+        assert_near(line.advance(&from, 1000.0), DVec2::new(100.0, 100.0));
+    }
+
+    #[test]
+    fn parse_reads_points_radii_and_closed() {
+        let path = Path::parse("# comment\n\n0 0\n10 0 5\nclosed\n", 20.0).unwrap();
+
+        assert_eq!(path.points(), &[DVec2::ZERO, DVec2::new(10.0, 0.0)]);
+        assert!(path.is_closed());
+        assert_eq!(path.segment(0).2, 20.0);
+        assert_eq!(path.segment(1).2, 5.0);
+    }
+
+    #[test]
+    fn parse_errors_carry_line_number() {
+        let err = Path::parse("0 0\n\n# comment\n10 zero\n", 20.0).unwrap_err();
+        match err {
+            PathError::Parse { line, text } => {
+                assert_eq!(line, 4);
+                assert_eq!(text, "10 zero");
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        let err = Path::parse("0 0 1 2\n", 20.0).unwrap_err();
+        assert!(matches!(err, PathError::Parse { line: 1, .. }));
+    }
+}
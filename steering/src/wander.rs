@@ -0,0 +1,101 @@
+use glam::DVec2;
+use rand::Rng;
+
+use crate::Agent;
+
+/// Seeks a point on a circle projected ahead of the agent
+///
+/// The point drifts around the circle by a small random amount each update,
+/// so the agent meanders instead of jittering
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Wander {
+    /// How far ahead of the agent the circle is
+    pub distance: f64,
+    pub radius: f64,
+
+    /// Largest change in angle per update, in radians
+    pub jitter: f64,
+
+    // target angle relative to the heading
+    angle: f64,
+}
+
+impl Default for Wander {
+    fn default() -> Self {
+        Self::new(80.0, 25.0, 0.3)
+    }
+}
+
+impl Wander {
+    pub fn new(distance: f64, radius: f64, jitter: f64) -> Self {
+        Self {
+            distance,
+            radius,
+            jitter,
+            angle: 0.0,
+        }
+    }
+
+    /// Angle of the target around the circle, relative to the heading
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    /// Center of the circle in front of the agent
+    pub fn center(&self, agent: &Agent) -> DVec2 {
+        agent.position + agent.heading() * self.distance
+    }
+
+    /// Moves the target around the circle and returns it
+    pub fn target(&mut self, agent: &Agent, rng: &mut impl Rng) -> DVec2 {
+        self.angle += rng.gen_range(-self.jitter..=self.jitter);
+
+        let heading = agent.velocity.y.atan2(agent.velocity.x);
+        let theta = heading + self.angle;
+
+        self.center(agent) + self.radius * DVec2::new(theta.cos(), theta.sin())
+    }
+
+    /// Moves the target around the circle and seeks it
+    pub fn steer(&mut self, agent: &Agent, rng: &mut impl Rng) -> DVec2 {
+        let target = self.target(agent, rng);
+        agent.seek(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn angle_drifts_from_the_last_update() {
+        let agent = Agent::new(DVec2::ZERO, DVec2::new(1.0, 0.0), 4.0, 0.5);
+        let mut wander = Wander::new(80.0, 25.0, 0.3);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let mut previous = wander.angle();
+        for _ in 0..100 {
+            wander.target(&agent, &mut rng);
+
+            assert!((wander.angle() - previous).abs() <= wander.jitter);
+            previous = wander.angle();
+        }
+
+        // it wandered rather than resetting every update
+        assert!(wander.angle().abs() > wander.jitter);
+    }
+
+    #[test]
+    fn target_is_on_the_circle() {
+        let agent = Agent::new(DVec2::ZERO, DVec2::new(0.0, 2.0), 4.0, 0.5);
+        let mut wander = Wander::new(80.0, 25.0, 0.0);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let target = wander.target(&agent, &mut rng);
+        assert_eq!(wander.angle(), 0.0);
+        assert!(target.distance(DVec2::new(0.0, 105.0)) < 1e-9);
+    }
+}
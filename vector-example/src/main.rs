@@ -1,30 +1,47 @@
+use core::{Canvas, Sketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+#[derive(Debug, Default)]
+struct MouseVector;
 
-fn draw(screen: &mut Screen, _: f64) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+impl Sketch for MouseVector {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    let mut mouse = DVec2::new(screen.mouse_x(), screen.mouse_y());
-    let center = DVec2::new(screen.width() as f64 / 2.0, screen.height() as f64 / 2.0);
-    mouse -= center;
+        let mut mouse = core::input::mouse_position();
+        let center = DVec2::new(canvas.width() as f64 / 2.0, canvas.height() as f64 / 2.0);
+        mouse -= center;
 
-    let m = mouse.length();
-    core::fill_grayscale(screen, 0.0);
-    core::shapes::rect(screen, 0.0, 0.0, m, 10.0)?;
+        let m = mouse.length();
+        core::fill_grayscale(canvas, 0.0);
+        core::shapes::rect(canvas, 0.0, 0.0, m, 10.0)?;
 
-    core::translate(screen, center.x, center.y);
-    core::shapes::line(screen, 0.0, 0.0, mouse.x, mouse.y)?;
+        core::translate(canvas, center.x, center.y);
+        core::shapes::line(canvas, 0.0, 0.0, mouse.x, mouse.y)?;
 
-    Ok(())
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    core::run(setup, draw)?;
+    core::run_sketch(WIDTH, HEIGHT, MouseVector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mouse_vector_snapshot() {
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 1, &mut MouseVector).unwrap();
 
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/mouse-vector.png"),
+        );
+    }
 }
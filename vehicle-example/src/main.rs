@@ -1,10 +1,57 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use std::io;
+
+use core::color::Color;
+use core::fbm::Fbm;
+use core::flow::FlowField;
+use core::pixels::PixelBuffer;
+use core::shapes::{EndMode, ShapeKind};
+use core::timestep::FixedTimestep;
+use core::tweak::TweakPanel;
+use core::{Canvas, FixedSketch};
 use glam::DVec2;
 use processing::errors::ProcessingErr;
-use processing::Screen;
+use processing::{Key, MouseButton};
 use rand::Rng;
+use steering::{Agent, BinLattice, Flocking, Obstacle, Path, PathError, PathFollow, Rule};
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+// vehicles start slowing down this far from what they're seeking
+const SLOWING_RADIUS: f64 = 100.0;
+
+// how far away boids align and group with each other
+const NEIGHBOR_DISTANCE: f64 = 50.0;
+
+// enough boids that brute force neighbor checks are noticeably slower (toggle with L)
+const FLOCK_SIZE: usize = 1000;
+
+// flow field cell size in pixels and the noise distance between cells
+const FLOW_RESOLUTION: f64 = 20.0;
+const FLOW_SCALE: f64 = 0.1;
+
+// how fast the noise field changes, in noise units per second
+const FLOW_SPEED: f64 = 0.2;
+
+// brightness image the field can be built from
+const FLOW_IMAGE_PATH: &str = "data/smoke.png";
+
+// mouse strokes bend the flow this far around them, this much at their center
+const FLOW_STROKE_RADIUS: f64 = 40.0;
+const FLOW_STROKE_STRENGTH: f64 = 0.5;
+
+const PATH_PATH: &str = "data/vehicle-path.txt";
+
+// radius of path segments that don't set their own
+const PATH_RADIUS: f64 = 20.0;
+
+// segments of the smoothed path between each point in the file
+const PATH_DETAIL: usize = 8;
+
+// path followers look this far ahead of themselves
+// and seek this far along the path when they stray
+const PATH_PREDICT: f64 = 25.0;
+const PATH_AHEAD: f64 = 10.0;
 
 #[derive(Debug)]
 struct Vehicle {
@@ -30,464 +77,706 @@ impl Vehicle {
         }
     }
 
-    #[allow(dead_code)]
+    fn agent(&self) -> Agent {
+        Agent::new(self.location, self.velocity, self.maxspeed, self.maxforce)
+    }
+
     fn follow_flow(&self, flow: &FlowField) -> DVec2 {
         // desired velocity from the flow field
         let desired = flow.lookup(self.location) * self.maxspeed;
 
-        // steering force
-        (desired - self.velocity).clamp_length_max(self.maxforce)
+        self.agent().steer(desired)
     }
 
-    #[allow(dead_code)]
-    fn follow_path(&self, path: &Path, _dt: f64) -> DVec2 {
-        // predict 25 pixels ahead
-        let predict = self.location + self.velocity.normalize_or_zero() * 25.0; // * dt;
+    // what goes into the decision, also drawn by the debug view
+    fn path_follow(&self, path: &Path) -> Option<PathFollow> {
+        self.agent().follow_path(path, PATH_PREDICT, PATH_AHEAD)
+    }
 
-        let mut target = None;
-        let mut world_record = 1000000.0;
+    fn follow_path(&mut self, path: &Path) {
+        if let Some(follow) = self.path_follow(path) {
+            self.apply_force(follow.force);
+        }
+    }
 
-        // find the normal on the closest path segment
-        for i in 0..path.points.len() - 1 {
-            let a = path.points[i];
-            let b = path.points[i + 1];
+    // seeks, slowing down on arrival
+    fn seek(&self, target: DVec2) -> DVec2 {
+        self.agent().arrive(target, SLOWING_RADIUS)
+    }
 
-            // project the predicted point onto the segment
-            let mut proj = core::math::project(predict, a, b);
+    fn apply_force(&mut self, force: DVec2) {
+        let force = if self.mass > 0.0 {
+            force / self.mass
+        } else {
+            force
+        };
+        self.acceleration += force;
+    }
 
-            // if we're not on this segment, hackily take the "end" as the normal
-            if proj.x < a.x.min(b.x) || proj.x > a.x.max(b.x) {
-                proj = b;
-            }
+    fn apply_behaviors(&mut self, vehicles: &[Agent]) {
+        let separate = self.agent().separate(vehicles, self.r * 10.0);
+        let seek = self.seek(core::input::mouse_position());
 
-            let distance = predict.distance(proj);
-            if distance < world_record {
-                world_record = distance;
+        self.apply_force(separate);
+        self.apply_force(seek);
+    }
 
-                // target 10 pixels out from the predicted point
-                let dir = (b - a).normalize_or_zero();
-                target = Some(proj + dir * 10.0);
-            }
-        }
+    fn update(&mut self, _dt: f64) {
+        self.velocity += self.acceleration; // * dt;
+        self.velocity = self.velocity.clamp_length_max(self.maxspeed);
 
-        // only seek the target if we're outside the path radius
-        if let Some(target) = target {
-            if world_record > path.radius {
-                return self.seek(target);
-            }
-        }
+        self.location += self.velocity; // * dt;
 
-        DVec2::default()
+        self.acceleration = DVec2::default();
     }
 
-    #[allow(dead_code)]
-    fn wander(&self, target: DVec2, r: f64) -> DVec2 {
-        let mut rng = rand::thread_rng();
+    // wraps around the edges of the area
+    fn wrap(&mut self, size: DVec2) {
+        if self.location.x < -self.r {
+            self.location.x = size.x + self.r;
+        } else if self.location.x > size.x + self.r {
+            self.location.x = -self.r;
+        }
 
-        // target a random point on a circle around the target
-        let theta = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
-        let target = target + r * DVec2::new(theta.cos(), theta.sin());
+        if self.location.y < -self.r {
+            self.location.y = size.y + self.r;
+        } else if self.location.y > size.y + self.r {
+            self.location.y = -self.r;
+        }
+    }
 
-        self.seek(target)
+    fn heading(&self) -> f64 {
+        self.velocity.y.atan2(self.velocity.x)
     }
 
-    fn seek(&self, target: DVec2) -> DVec2 {
-        // desired velocity direction
-        let desired = target - self.location;
-
-        // desired velocity magnitude
-        let d = desired.length();
-        let m = if d < 100.0 {
-            // slow down on arrival
-            core::math::map(d, 0.0, 100.0, 0.0, self.maxspeed)
-        } else {
-            self.maxspeed
-        };
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        let theta = self.heading() + std::f64::consts::FRAC_PI_2;
 
-        let desired = desired.normalize_or_zero() * m;
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 175.0);
 
-        // steering force
-        (desired - self.velocity).clamp_length_max(self.maxforce)
-    }
+        let mut guard = core::transform::push(canvas);
+        let canvas = &mut *guard;
+
+        core::translate(canvas, self.location.x, self.location.y);
+        core::rotate(canvas, theta);
 
-    #[allow(dead_code)]
-    fn pursuit(&self, target: DVec2, velocity: DVec2, _dt: f64) -> DVec2 {
-        let predicted = target + velocity; // * dt;
-        self.seek(predicted)
+        let mut body = core::shapes::begin_shape(ShapeKind::Polygon);
+        body.vertex(0.0, -self.r * 2.0)
+            .vertex(-self.r, self.r * 2.0)
+            .vertex(self.r, self.r * 2.0);
+        body.end_shape(canvas, EndMode::Close)
     }
+}
 
-    #[allow(dead_code)]
-    fn flee(&self, target: DVec2) -> DVec2 {
-        let desired = -(target - self.location).normalize_or_zero() * self.maxspeed;
+type Boid = Vehicle;
 
-        // steering force
-        (desired - self.velocity).clamp_length_max(self.maxforce)
-    }
+impl Boid {
+    fn flock(&mut self, boids: &[Agent], rules: &FlockRules, obstacles: &[Obstacle], size: DVec2) {
+        self.maxspeed = rules.max_speed;
+        self.maxforce = rules.max_force;
 
-    #[allow(dead_code)]
-    fn separate(&self, vehicles: impl AsRef<[RefCell<Vehicle>]>) -> DVec2 {
-        let desired_separation = self.r * 10.0;
+        let agent = self.agent();
 
-        let mut sum = DVec2::default();
-        let mut count = 0;
+        let flock = rules.flocking.steer(&agent, boids);
+        let avoid = agent.avoid(obstacles, self.r, rules.look_ahead) * rules.avoid_weight;
+        let contain =
+            agent.contain(DVec2::ZERO, size, rules.boundary_margin) * rules.boundary_weight;
 
-        for other in vehicles.as_ref().iter() {
-            // this check should stop us comparing against ourself
-            if let Ok(other) = other.try_borrow() {
-                let d = self.location.distance(other.location);
-                if d < desired_separation {
-                    let mut diff = (self.location - other.location).normalize_or_zero();
+        self.apply_force(flock);
+        self.apply_force(avoid);
+        self.apply_force(contain);
+    }
+}
 
-                    // weight how fast we flee by the distance
-                    diff /= d;
+// tweak panel names
+const SEPARATION_WEIGHT: &str = "separation weight";
+const SEPARATION_RADIUS: &str = "separation radius";
+const ALIGNMENT_WEIGHT: &str = "alignment weight";
+const ALIGNMENT_RADIUS: &str = "alignment radius";
+const COHESION_WEIGHT: &str = "cohesion weight";
+const COHESION_RADIUS: &str = "cohesion radius";
+const FIELD_OF_VIEW: &str = "field of view";
+const MAX_SPEED: &str = "max speed";
+const MAX_FORCE: &str = "max force";
+const AVOID_WEIGHT: &str = "avoid weight";
+const LOOK_AHEAD: &str = "look ahead";
+const BOUNDARY_WEIGHT: &str = "boundary weight";
+const BOUNDARY_MARGIN: &str = "boundary margin";
+
+// classic setups from chapter 6, picked with the number keys
+const PRESET_KEYS: [Key; 4] = [Key::Key1, Key::Key2, Key::Key3, Key::Key4];
+const PRESETS: [&[(&str, f64)]; 4] = [
+    // flock
+    &[
+        (SEPARATION_WEIGHT, 1.5),
+        (SEPARATION_RADIUS, 30.0),
+        (ALIGNMENT_WEIGHT, 1.0),
+        (ALIGNMENT_RADIUS, 50.0),
+        (COHESION_WEIGHT, 1.0),
+        (COHESION_RADIUS, 50.0),
+        (FIELD_OF_VIEW, 270.0),
+    ],
+    // swarm, clumping together without lining up
+    &[
+        (SEPARATION_WEIGHT, 1.0),
+        (SEPARATION_RADIUS, 20.0),
+        (ALIGNMENT_WEIGHT, 0.0),
+        (ALIGNMENT_RADIUS, 50.0),
+        (COHESION_WEIGHT, 1.5),
+        (COHESION_RADIUS, 80.0),
+        (FIELD_OF_VIEW, 360.0),
+    ],
+    // school, long parallel lines that only watch what's ahead
+    &[
+        (SEPARATION_WEIGHT, 1.5),
+        (SEPARATION_RADIUS, 20.0),
+        (ALIGNMENT_WEIGHT, 2.0),
+        (ALIGNMENT_RADIUS, 80.0),
+        (COHESION_WEIGHT, 0.5),
+        (COHESION_RADIUS, 50.0),
+        (FIELD_OF_VIEW, 120.0),
+    ],
+    // scatter
+    &[
+        (SEPARATION_WEIGHT, 3.0),
+        (SEPARATION_RADIUS, 60.0),
+        (ALIGNMENT_WEIGHT, 0.0),
+        (ALIGNMENT_RADIUS, 50.0),
+        (COHESION_WEIGHT, 0.0),
+        (COHESION_RADIUS, 50.0),
+        (FIELD_OF_VIEW, 360.0),
+    ],
+];
+
+// everything the boids steer by, read from the tweak panel each frame
+#[derive(Debug, Default, Copy, Clone)]
+struct FlockRules {
+    flocking: Flocking,
+    max_speed: f64,
+    max_force: f64,
+    avoid_weight: f64,
+    look_ahead: f64,
+    boundary_weight: f64,
+    boundary_margin: f64,
+}
 
-                    sum += diff;
-                    count += 1;
-                }
-            }
-        }
+impl FlockRules {
+    fn panel() -> TweakPanel {
+        TweakPanel::new()
+            .with_tweak(SEPARATION_WEIGHT, 1.5, 0.0, 5.0, 0.1)
+            .with_tweak(SEPARATION_RADIUS, 30.0, 0.0, 200.0, 1.0)
+            .with_tweak(ALIGNMENT_WEIGHT, 1.0, 0.0, 5.0, 0.1)
+            .with_tweak(ALIGNMENT_RADIUS, NEIGHBOR_DISTANCE, 0.0, 200.0, 1.0)
+            .with_tweak(COHESION_WEIGHT, 1.0, 0.0, 5.0, 0.1)
+            .with_tweak(COHESION_RADIUS, NEIGHBOR_DISTANCE, 0.0, 200.0, 1.0)
+            .with_tweak(FIELD_OF_VIEW, 270.0, 0.0, 360.0, 5.0)
+            .with_tweak(MAX_SPEED, 4.0, 0.0, 20.0, 0.1)
+            .with_tweak(MAX_FORCE, 0.1, 0.0, 2.0, 0.01)
+            .with_tweak(AVOID_WEIGHT, 3.0, 0.0, 10.0, 0.1)
+            .with_tweak(LOOK_AHEAD, 50.0, 0.0, 200.0, 1.0)
+            .with_tweak(BOUNDARY_WEIGHT, 0.0, 0.0, 10.0, 0.1)
+            .with_tweak(BOUNDARY_MARGIN, 25.0, 0.0, 200.0, 1.0)
+    }
 
-        if count > 0 {
-            sum /= count as f64;
+    fn from_panel(panel: &TweakPanel) -> Self {
+        Self {
+            flocking: Flocking {
+                separation: Rule::new(panel.get(SEPARATION_WEIGHT), panel.get(SEPARATION_RADIUS)),
+                alignment: Rule::new(panel.get(ALIGNMENT_WEIGHT), panel.get(ALIGNMENT_RADIUS)),
+                cohesion: Rule::new(panel.get(COHESION_WEIGHT), panel.get(COHESION_RADIUS)),
+                field_of_view: panel.get(FIELD_OF_VIEW).to_radians(),
+            },
+            max_speed: panel.get(MAX_SPEED),
+            max_force: panel.get(MAX_FORCE),
+            avoid_weight: panel.get(AVOID_WEIGHT),
+            look_ahead: panel.get(LOOK_AHEAD),
+            boundary_weight: panel.get(BOUNDARY_WEIGHT),
+            boundary_margin: panel.get(BOUNDARY_MARGIN),
+        }
+    }
+}
 
-            // set the magnitude to the max speed
-            sum = sum.normalize_or_zero() * self.maxspeed;
+// finds agents near each other, either through a bin lattice or by checking every agent
+#[derive(Debug)]
+struct Neighbors {
+    lattice: BinLattice,
+    use_lattice: bool,
+    found: Vec<Agent>,
+}
 
-            // steering force
-            return (sum - self.velocity).clamp_length_max(self.maxforce);
+impl Default for Neighbors {
+    fn default() -> Self {
+        Self {
+            lattice: BinLattice::new(NEIGHBOR_DISTANCE),
+            use_lattice: true,
+            found: vec![],
         }
+    }
+}
 
-        DVec2::default()
+impl Neighbors {
+    fn handle_input(&mut self) {
+        if core::input::key_pressed(Key::L) {
+            self.use_lattice = !self.use_lattice;
+        }
     }
 
-    fn apply_force(&mut self, force: DVec2) {
-        let force = if self.mass > 0.0 {
-            force / self.mass
-        } else {
-            force
-        };
-        self.acceleration += force;
+    fn rebuild(&mut self, agents: &[Agent]) {
+        if self.use_lattice {
+            self.lattice
+                .rebuild(agents.iter().map(|agent| agent.position));
+        }
     }
 
-    fn apply_behaviors(&mut self, screen: &mut Screen, vehicles: impl AsRef<[RefCell<Vehicle>]>) {
-        let separate = self.separate(vehicles);
-        let seek = self.seek(DVec2::new(screen.mouse_x(), screen.mouse_y()));
+    // candidates within radius, brute force hands back every agent
+    fn near<'a>(&'a mut self, agents: &'a [Agent], position: DVec2, radius: f64) -> &'a [Agent] {
+        if !self.use_lattice {
+            return agents;
+        }
+
+        self.found.clear();
+        self.found
+            .extend(self.lattice.query(position, radius).map(|idx| agents[idx]));
 
-        self.apply_force(separate);
-        self.apply_force(seek);
+        &self.found
     }
+}
 
-    fn update(&mut self, _dt: f64) {
-        self.velocity += self.acceleration; // * dt;
-        self.velocity.clamp_length_max(self.maxspeed);
+#[derive(Debug)]
+struct Flock {
+    boids: Vec<Boid>,
+    neighbors: Neighbors,
+    panel: TweakPanel,
+    obstacles: Vec<Obstacle>,
+    size: DVec2,
+}
 
-        self.location += self.velocity; // * dt;
+impl Flock {
+    fn new(width: u32, height: u32) -> Self {
+        let (width, height) = (width as f64, height as f64);
 
-        self.acceleration = DVec2::default();
+        Self {
+            boids: vec![],
+            neighbors: Neighbors::default(),
+            panel: FlockRules::panel(),
+            obstacles: vec![
+                Obstacle::new(DVec2::new(width * 0.25, height * 0.5), 30.0),
+                Obstacle::new(DVec2::new(width * 0.75, height * 0.3), 20.0),
+                Obstacle::new(DVec2::new(width * 0.6, height * 0.75), 25.0),
+            ],
+            size: DVec2::new(width, height),
+        }
     }
 
-    fn heading(&self) -> f64 {
-        self.velocity.x.atan2(self.velocity.y)
+    fn add_boid(&mut self, boid: Boid) {
+        self.boids.push(boid);
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        let theta = self.heading();
-
-        core::stroke_grayscale(screen, 0.0);
-        core::fill_grayscale(screen, 175.0);
+    fn handle_input(&mut self) {
+        self.neighbors.handle_input();
+        self.panel.handle_input();
 
-        screen.push_matrix();
+        for (key, preset) in PRESET_KEYS.iter().zip(PRESETS.iter()) {
+            if core::input::key_pressed(*key) {
+                for (name, value) in preset.iter() {
+                    self.panel.set(name, *value);
+                }
+            }
+        }
+    }
 
-        core::translate(screen, self.location.x, self.location.y);
-        core::rotate(screen, theta);
+    fn update(&mut self, dt: f64) {
+        let rules = FlockRules::from_panel(&self.panel);
 
-        /*
-        // TODO: beginShape();
+        // every boid steers from where the flock was at the start of the step
+        let agents: Vec<_> = self.boids.iter().map(Boid::agent).collect();
+        self.neighbors.rebuild(&agents);
 
-        //vertex(0, -self.r * 2.0);
-        //vertex(-self.r, self.r * 2.0);
-        //vertex(self.r, self.r * 2.0);
+        for boid in self.boids.iter_mut() {
+            let neighbors = self
+                .neighbors
+                .near(&agents, boid.location, rules.flocking.radius());
 
-        // TODO: endShape();
-        */
+            boid.flock(neighbors, &rules, &self.obstacles, self.size);
+            boid.update(dt);
+            boid.wrap(self.size);
+        }
+    }
 
-        core::shapes::ellipse(
-            screen,
-            self.location.x,
-            self.location.y,
-            self.r * 2.0,
-            self.r * 2.0,
-        )?;
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::fill_grayscale(canvas, 225.0);
+        for obstacle in self.obstacles.iter() {
+            let diameter = obstacle.radius * 2.0;
+            core::shapes::ellipse(
+                canvas,
+                obstacle.center.x,
+                obstacle.center.y,
+                diameter,
+                diameter,
+            )?;
+        }
 
-        screen.pop_matrix();
+        for boid in self.boids.iter() {
+            boid.display(canvas)?;
+        }
 
-        Ok(())
+        self.panel.display(canvas, 10.0, 10.0)
     }
 }
 
-type Boid = Vehicle;
+struct FlowFollowers {
+    field: FlowField,
+    noise: Fbm,
+    z: f64,
+    animate: bool,
+    show_field: bool,
+    vehicles: Vec<Vehicle>,
+    size: DVec2,
+}
 
-impl Boid {
-    fn flock(&mut self, boids: impl AsRef<[RefCell<Boid>]>) {
-        let separate = self.separate(boids.as_ref()) * 1.5;
-        let align = self.align(boids.as_ref()) * 1.0;
-        let cohesion = self.cohesion(boids.as_ref()) * 1.0;
+impl FlowFollowers {
+    fn new(width: u32, height: u32, count: usize) -> Self {
+        let size = DVec2::new(width as f64, height as f64);
+        let noise = Fbm::new().octaves(1).frequency(0.5);
+
+        let mut rng = core::random::rng();
+        let mut vehicles = Vec::with_capacity(count);
+        for _ in 0..count {
+            vehicles.push(Vehicle::new(
+                rng.gen_range(0.0..size.x),
+                rng.gen_range(0.0..size.y),
+            ));
+        }
 
-        self.apply_force(separate);
-        self.apply_force(align);
-        self.apply_force(cohesion);
+        Self {
+            field: FlowField::from_noise(size.x, size.y, FLOW_RESOLUTION, &noise, FLOW_SCALE),
+            noise,
+            z: 0.0,
+            animate: true,
+            show_field: false,
+            vehicles,
+            size,
+        }
     }
 
-    fn align(&self, boids: impl AsRef<[RefCell<Boid>]>) -> DVec2 {
-        let neighbordist = 50.0;
+    fn handle_input(&mut self) {
+        if core::input::key_pressed(Key::F) {
+            self.show_field = !self.show_field;
+        }
+
+        if core::input::key_pressed(Key::Space) {
+            self.animate = !self.animate;
+        }
 
-        let mut sum = DVec2::default();
-        let mut count = 0;
+        // back to noise
+        if core::input::key_pressed(Key::N) {
+            self.field.set_noise(&self.noise, FLOW_SCALE, self.z);
+            self.animate = true;
+        }
 
-        for other in boids.as_ref().iter() {
-            // this check should stop us comparing against ourself
-            if let Ok(other) = other.try_borrow() {
-                let d = self.location.distance(other.location);
-                if d < neighbordist {
-                    sum += other.velocity;
-                    count += 1;
+        // flow towards the bright parts of an image, keeping the current field if it's missing
+        if core::input::key_pressed(Key::I) {
+            match PixelBuffer::load(FLOW_IMAGE_PATH) {
+                Ok(pixels) => {
+                    self.field =
+                        FlowField::from_image(self.size.x, self.size.y, FLOW_RESOLUTION, &pixels);
+                    self.animate = false;
                 }
+                Err(err) => eprintln!("Failed to load {}: {:?}", FLOW_IMAGE_PATH, err),
             }
         }
 
-        if count > 0 {
-            sum /= count as f64;
+        // drag to draw currents, animating would wipe them out
+        if core::input::mouse_down(MouseButton::Left) {
+            let to = core::input::mouse_position();
+            let from = to - core::input::mouse_delta();
 
-            // set the magnitude to the max speed
-            sum = sum.normalize_or_zero() * self.maxspeed;
-
-            return (sum - self.velocity).clamp_length_max(self.maxforce);
+            self.field
+                .stroke(from, to, FLOW_STROKE_RADIUS, FLOW_STROKE_STRENGTH);
+            self.animate = false;
         }
-
-        DVec2::default()
     }
 
-    fn cohesion(&self, boids: impl AsRef<[RefCell<Boid>]>) -> DVec2 {
-        let neighbordist = 50.0;
-
-        let mut sum = DVec2::default();
-        let mut count = 0;
-
-        for other in boids.as_ref().iter() {
-            // this check should stop us comparing against ourself
-            if let Ok(other) = other.try_borrow() {
-                let d = self.location.distance(other.location);
-                if d < neighbordist {
-                    sum += other.location;
-                    count += 1;
-                }
-            }
+    fn update(&mut self, dt: f64) {
+        if self.animate {
+            self.z += FLOW_SPEED * dt;
+            self.field.set_noise(&self.noise, FLOW_SCALE, self.z);
         }
 
-        if count > 0 {
-            sum /= count as f64;
-            return self.seek(sum);
-        }
+        for v in self.vehicles.iter_mut() {
+            let force = v.follow_flow(&self.field);
+            v.apply_force(force);
 
-        DVec2::default()
+            v.update(dt);
+            v.wrap(self.size);
+        }
     }
 
-    fn run(
-        &mut self,
-        screen: &mut Screen,
-        boids: impl AsRef<[RefCell<Boid>]>,
-        dt: f64,
-    ) -> Result<(), ProcessingErr> {
-        self.flock(boids);
-        self.update(dt);
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        if self.show_field {
+            core::stroke_grayscale(canvas, 200.0);
+            core::stroke_weight(canvas, 1.0);
+            self.field.display(canvas)?;
+        }
 
-        self.display(screen)?;
+        for v in self.vehicles.iter() {
+            v.display(canvas)?;
+        }
 
         Ok(())
     }
 }
 
-#[derive(Debug, Default)]
-struct Flock {
-    boids: Vec<RefCell<Boid>>,
+struct PathFollowers {
+    path: Path,
+    vehicles: Vec<Vehicle>,
+    debug: bool,
 }
 
-impl Flock {
-    fn add_boid(&mut self, boid: Boid) {
-        self.boids.push(RefCell::new(boid));
+impl PathFollowers {
+    fn load(count: usize) -> Result<Self, ProcessingErr> {
+        let path = Path::load(PATH_PATH, PATH_RADIUS).map_err(path_not_loaded)?;
+
+        Self::new(&path, count)
     }
 
-    fn run(&mut self, screen: &mut Screen, dt: f64) -> Result<(), ProcessingErr> {
-        for boid in self.boids.iter() {
-            boid.borrow_mut().run(screen, &self.boids, dt)?;
+    fn new(path: &Path, count: usize) -> Result<Self, ProcessingErr> {
+        let path = path.smooth(PATH_DETAIL);
+
+        // vehicles start on a random segment
+        if path.segment_count() == 0 {
+            return Err(ProcessingErr::ImageNotFound(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: paths need at least 2 points", PATH_PATH),
+            )));
         }
 
-        Ok(())
+        let mut rng = core::random::rng();
+        let mut vehicles = Vec::with_capacity(count);
+        for _ in 0..count {
+            // start somewhere near the path
+            let (a, _, _) = path.segment(rng.gen_range(0..path.segment_count()));
+            let offset = DVec2::new(rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0));
+
+            vehicles.push(Vehicle::new(a.x + offset.x, a.y + offset.y));
+        }
+
+        Ok(Self {
+            path,
+            vehicles,
+            debug: false,
+        })
     }
-}
 
-struct FlowField {
-    field: Vec<Vec<DVec2>>,
-    cols: usize,
-    rows: usize,
-    resolution: usize,
-}
+    fn handle_input(&mut self) {
+        if core::input::key_pressed(Key::D) {
+            self.debug = !self.debug;
+        }
+    }
 
-impl FlowField {
-    #[allow(dead_code)]
-    fn noise_field(cols: usize, rows: usize) -> Vec<Vec<DVec2>> {
-        let mut field = Vec::with_capacity(cols);
-
-        let mut xoff = 0.0;
-        for _ in 0..cols {
-            let mut row = Vec::with_capacity(rows);
-
-            let mut yoff = 0.0;
-            for _ in 0..rows {
-                let theta = core::math::map(
-                    core::noise2d([xoff, yoff], 0.5),
-                    0.0,
-                    1.0,
-                    0.0,
-                    2.0 * std::f64::consts::PI,
-                );
-                row.push(DVec2::new(theta.cos(), theta.sin()));
-
-                yoff += 0.1;
-            }
-            field.push(row);
+    fn update(&mut self, dt: f64) {
+        for v in self.vehicles.iter_mut() {
+            v.follow_path(&self.path);
+            v.update(dt);
+        }
+    }
+
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        self.display_path(canvas)?;
 
-            xoff += 0.1;
+        for v in self.vehicles.iter() {
+            v.display(canvas)?;
+
+            if self.debug {
+                if let Some(follow) = v.path_follow(&self.path) {
+                    Self::display_follow(canvas, v, &follow)?;
+                }
+            }
         }
 
-        field
+        Ok(())
     }
 
-    #[allow(dead_code)]
-    fn new(screen: &Screen, resolution: usize) -> Self {
-        let cols = screen.width() as usize / resolution;
-        let rows = screen.height() as usize / resolution;
+    fn display_path(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
+        // the area vehicles are allowed to wander in
+        core::stroke_grayscale(canvas, 225.0);
+        for segment in 0..self.path.segment_count() {
+            let (a, b, radius) = self.path.segment(segment);
 
-        Self {
-            field: FlowField::noise_field(cols, rows),
-            cols,
-            rows,
-            resolution,
+            core::stroke_weight(canvas, radius * 2.0);
+            core::shapes::linev(canvas, a, b)?;
         }
+
+        core::stroke_grayscale(canvas, 0.0);
+        core::stroke_weight(canvas, 1.0);
+        core::fill_off(canvas);
+
+        let mode = if self.path.is_closed() {
+            EndMode::Close
+        } else {
+            EndMode::Open
+        };
+        core::shapes::polygon(canvas, self.path.points(), mode)
     }
 
-    fn lookup(&self, pos: DVec2) -> DVec2 {
-        let col = core::math::clamp(pos.x as usize / self.resolution, 0, self.cols - 1);
-        let row = core::math::clamp(pos.y as usize / self.resolution, 0, self.rows - 1);
+    fn display_follow(
+        canvas: &mut impl Canvas,
+        vehicle: &Vehicle,
+        follow: &PathFollow,
+    ) -> Result<(), ProcessingErr> {
+        core::stroke_grayscale(canvas, 0.0);
+        core::stroke_weight(canvas, 1.0);
+        core::fill_off(canvas);
+
+        // predicted point and its normal onto the path
+        core::shapes::linev(canvas, vehicle.location, follow.predicted)?;
+        core::shapes::ellipse(canvas, follow.predicted.x, follow.predicted.y, 4.0, 4.0)?;
+        core::shapes::linev(canvas, follow.predicted, follow.normal.point)?;
+        core::shapes::ellipse(
+            canvas,
+            follow.normal.point.x,
+            follow.normal.point.y,
+            4.0,
+            4.0,
+        )?;
 
-        self.field[col][row]
+        // target goes red while the vehicle is steering back to the path
+        if follow.force != DVec2::ZERO {
+            core::fill(canvas, Color::RED);
+        }
+        core::shapes::ellipse(canvas, follow.target.x, follow.target.y, 8.0, 8.0)
     }
 }
 
-struct Path {
-    points: Vec<DVec2>,
-    radius: f64,
+// processing has no error for assets other than images
+fn path_not_loaded(err: PathError) -> ProcessingErr {
+    let err = match err {
+        PathError::Io(err) => io::Error::new(err.kind(), format!("{}: {}", PATH_PATH, err)),
+        err => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", PATH_PATH, err),
+        ),
+    };
+
+    ProcessingErr::ImageNotFound(err)
 }
 
-impl Path {
-    #[allow(dead_code)]
-    fn new() -> Self {
-        Self {
-            points: vec![],
-            radius: 20.0,
+struct Behaviors {
+    vehicles: Vec<Vehicle>,
+    flock: Flock,
+    followers: PathFollowers,
+    neighbors: Neighbors,
+    flow: FlowFollowers,
+}
+
+impl Behaviors {
+    fn new(width: u32, height: u32, followers: PathFollowers) -> Self {
+        let mut rng = core::random::rng();
+
+        let mut vehicles = vec![];
+        for _ in 0..100 {
+            vehicles.push(Vehicle::new(
+                rng.gen_range(0..width) as f64,
+                rng.gen_range(0..height) as f64,
+            ));
         }
-    }
 
-    #[allow(dead_code)]
-    fn add_point(&mut self, x: f64, y: f64) {
-        self.points.push(DVec2::new(x, y));
-    }
+        let mut flock = Flock::new(width, height);
 
-    #[allow(dead_code)]
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
-        core::stroke_grayscale(screen, 0.0);
-        screen.fill_off();
+        for _ in 0..FLOCK_SIZE {
+            let mut boid = Boid::new(width as f64 / 2.0, height as f64 / 2.0);
 
-        // TODO: beginShape();
+            // scatter them so they don't all sit on top of each other
+            boid.velocity = DVec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
 
-        for _v in self.points.iter() {
-            //vertex(v.x, v.y);
+            flock.add_boid(boid);
         }
 
-        // TODO: endShape(CLOSE);
-
-        Ok(())
+        Self {
+            vehicles,
+            flock,
+            followers,
+            neighbors: Neighbors::default(),
+            flow: FlowFollowers::new(width, height, 20),
+        }
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(640, 360)
-}
+impl FixedSketch for Behaviors {
+    fn update(&mut self, dt: f64) {
+        self.flow.update(dt);
+
+        self.followers.update(dt);
 
-fn draw(
-    screen: &mut Screen,
-    dt: f64,
-    vehicles: impl AsRef<[RefCell<Vehicle>]>,
-    flock: &mut Flock,
-) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+        let agents: Vec<_> = self.vehicles.iter().map(Vehicle::agent).collect();
+        self.neighbors.rebuild(&agents);
 
-    for v in vehicles.as_ref().iter() {
-        let mut v = v.borrow_mut();
+        for v in self.vehicles.iter_mut() {
+            let near = self.neighbors.near(&agents, v.location, v.r * 10.0);
+            v.apply_behaviors(near);
 
-        v.apply_behaviors(screen, &vehicles);
+            v.update(dt);
+        }
 
-        v.update(dt);
-        v.display(screen)?;
+        self.flock.update(dt);
     }
 
-    flock.run(screen, dt)?;
+    // key presses only last a frame, so input is handled here rather than per step
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _alpha: f64) -> Result<(), ProcessingErr> {
+        self.flow.handle_input();
+        self.followers.handle_input();
+        self.neighbors.handle_input();
+        self.flock.handle_input();
 
-    Ok(())
+        core::background_grayscale(canvas, 255.0);
+
+        self.flow.display(canvas)?;
+
+        self.followers.display(canvas)?;
+
+        for v in self.vehicles.iter() {
+            v.display(canvas)?;
+        }
+
+        self.flock.display(canvas)
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let vehicles = Rc::new(RefCell::new(None));
-    let flock = Rc::new(RefCell::new(None));
+    let followers = PathFollowers::load(10)?;
+
+    core::run_sketch_fixed(
+        WIDTH,
+        HEIGHT,
+        FixedTimestep::default(),
+        Behaviors::new(WIDTH, HEIGHT, followers),
+    )
+}
 
-    core::run(
-        || {
-            let mut rng = rand::thread_rng();
+#[cfg(test)]
+mod tests {
+    use core::FixedSteps;
 
-            let screen = setup()?;
+    use super::*;
 
-            let mut vs = vec![];
-            for _ in 0..100 {
-                vs.push(RefCell::new(Vehicle::new(
-                    rng.gen_range(0..screen.width()) as f64,
-                    rng.gen_range(0..screen.height()) as f64,
-                )));
-            }
+    #[test]
+    fn behaviors_snapshot() {
+        core::random::set_seed(1);
 
-            *vehicles.borrow_mut() = Some(vs);
+        let path = Path::parse(include_str!("../../data/vehicle-path.txt"), PATH_RADIUS).unwrap();
+        let followers = PathFollowers::new(&path, 10).unwrap();
 
-            let mut f = Flock::default();
+        let mut behaviors = FixedSteps::new(
+            FixedTimestep::default(),
+            Behaviors::new(WIDTH, HEIGHT, followers),
+        );
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 30, &mut behaviors).unwrap();
 
-            for _ in 0..100 {
-                f.add_boid(Boid::new(
-                    screen.width() as f64 / 2.0,
-                    screen.height() as f64 / 2.0,
-                ));
-            }
-
-            *flock.borrow_mut() = Some(f);
-
-            Ok(screen)
-        },
-        |screen, dt| {
-            draw(
-                screen,
-                dt,
-                vehicles.borrow_mut().as_mut().unwrap(),
-                flock.borrow_mut().as_mut().unwrap(),
-            )
-        },
-    )?;
-
-    Ok(())
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/behaviors.png"),
+        );
+    }
 }
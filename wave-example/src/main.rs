@@ -1,8 +1,8 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use core::{Canvas, Sketch};
 use processing::errors::ProcessingErr;
-use processing::Screen;
+
+const WIDTH: u32 = 400;
+const HEIGHT: u32 = 200;
 
 #[derive(Debug)]
 struct Wave {
@@ -24,18 +24,18 @@ impl Wave {
         self.angle += dt;
     }
 
-    fn display(&self, screen: &mut Screen) -> Result<(), ProcessingErr> {
+    fn display(&self, canvas: &mut impl Canvas) -> Result<(), ProcessingErr> {
         let mut angle = self.angle;
 
-        for x in (0..screen.width()).step_by(24) {
+        for x in (0..canvas.width()).step_by(24) {
             let y = angle.sin();
             //let y = core::noise(angle, 1.0); // exercise 3.9
-            let y = core::math::map(y, -1.0, 1.0, 0.0, screen.height() as f64);
+            let y = core::math::map(y, -1.0, 1.0, 0.0, canvas.height() as f64);
 
-            core::stroke_grayscale(screen, 0.0);
-            core::fill_grayscale_alpha(screen, 0.0, 50.0);
+            core::stroke_grayscale(canvas, 0.0);
+            core::fill_grayscale_alpha(canvas, 0.0, 50.0);
 
-            core::shapes::ellipse(screen, x as f64, y, 48.0, 48.0)?;
+            core::shapes::ellipse(canvas, x as f64, y, 48.0, 48.0)?;
 
             angle += self.angular_velocity;
         }
@@ -44,45 +44,54 @@ impl Wave {
     }
 }
 
-fn setup<'a>() -> Result<Screen<'a>, ProcessingErr> {
-    core::create_canvas(400, 200)
+#[derive(Debug)]
+struct Waves(Vec<Wave>);
+
+impl Default for Waves {
+    fn default() -> Self {
+        Self(vec![
+            Wave {
+                angle: 2.0,
+                angular_velocity: 0.2,
+            },
+            Wave::default(),
+            Wave {
+                angle: 5.0,
+                angular_velocity: 0.3,
+            },
+        ])
+    }
 }
 
-fn draw(screen: &mut Screen, mut waves: impl AsMut<[Wave]>) -> Result<(), ProcessingErr> {
-    core::background_grayscale(screen, 255.0);
+impl Sketch for Waves {
+    fn draw<C: Canvas>(&mut self, canvas: &mut C, _: f64) -> Result<(), ProcessingErr> {
+        core::background_grayscale(canvas, 255.0);
 
-    for wave in waves.as_mut() {
-        wave.update(0.02);
-        wave.display(screen)?;
-    }
+        for wave in self.0.iter_mut() {
+            wave.update(0.02);
+            wave.display(canvas)?;
+        }
 
-    Ok(())
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), ProcessingErr> {
-    let waves = Rc::new(RefCell::new(None));
-
-    core::run(
-        || {
-            let screen = setup()?;
-
-            let w = vec![
-                Wave {
-                    angle: 2.0,
-                    angular_velocity: 0.2,
-                },
-                Wave::default(),
-                Wave {
-                    angle: 5.0,
-                    angular_velocity: 0.3,
-                },
-            ];
-            *waves.borrow_mut() = Some(w);
-
-            Ok(screen)
-        },
-        |screen, _| draw(screen, waves.borrow_mut().as_mut().unwrap()),
-    )?;
-
-    Ok(())
+    core::run_sketch(WIDTH, HEIGHT, Waves::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waves_snapshot() {
+        let mut waves = Waves::default();
+        let canvas = core::headless::render_sketch(WIDTH, HEIGHT, 1, &mut waves).unwrap();
+
+        core::headless::assert_snapshot(
+            &canvas,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/waves.png"),
+        );
+    }
 }